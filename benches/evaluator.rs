@@ -0,0 +1,125 @@
+//! Benchmarks of the evaluator on typical workloads.
+//!
+//! Every benchmark is parsed once and evaluated repeatedly in a fresh scope,
+//! so only evaluation is measured. Pass a name to run only matching benchmarks,
+//! eg. `cargo bench -- fibonacci`.
+//!
+//! The benchmarks time themselves instead of using Criterion, so that the
+//! crate does not need extra dependencies.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use lisper::{
+    eval::evaluate,
+    expr::Expr,
+    lexer::lex,
+    parser::parse,
+    scope::{Output, Scope},
+};
+
+/// How long every benchmark is run for at least
+const MEASUREMENT_TIME: Duration = Duration::from_secs(2);
+
+/// Programs that are measured, by name
+const BENCHMARKS: &[(&str, &str)] = &[
+    (
+        "fibonacci",
+        "(
+            (defun fib (lambda (n) (
+                if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))
+            )))
+            (fib 18)
+        )",
+    ),
+    (
+        "list_construction",
+        "(for/list (i 200) (for/list (j 50) (repeat 5 (+ i j))))",
+    ),
+    (
+        "variable_lookup_depth",
+        "(
+            (def x 1)
+            (for/list (a 8) (for/list (b 8) (for/list (c 8) (for/list (d 8)
+                (+ x x x x x x x x a b c d)
+            ))))
+        )",
+    ),
+    (
+        "string_heavy",
+        "(
+            (def text \"A longer string that is copied on every lookup and compared\")
+            (for/list (i 5000) (= text text \"A longer string that is copied on every lookup\"))
+        )",
+    ),
+    (
+        "symbol_heavy",
+        "(
+            (defun name (lambda (i) (gensym)))
+            (for/list (i 5000) (eq? (name i) (name i)))
+        )",
+    ),
+];
+
+/// Evaluates the program once in a fresh scope without printing anything
+fn run(program: &Expr) {
+    let mut env = Scope::new().wrap();
+    env.borrow().globals().borrow_mut().output = Output::Captured(String::new());
+
+    black_box(evaluate(program, &mut env).expect("Benchmark failed"));
+}
+
+/// Formats a duration with a unit fitting its magnitude
+fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+
+    match nanos {
+        0..=999 => format!("{nanos} ns"),
+        1_000..=999_999 => format!("{:.2} µs", nanos as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.2} ms", nanos as f64 / 1e6),
+        _ => format!("{:.2} s", nanos as f64 / 1e9),
+    }
+}
+
+fn main() {
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+
+    for (name, code) in BENCHMARKS {
+        if filter
+            .as_ref()
+            .is_some_and(|filter| !name.contains(filter.as_str()))
+        {
+            continue;
+        }
+
+        let tokens = lex(code).unwrap_or_else(|err| panic!("Benchmark {name} does not lex: {err}"));
+        let program = parse(&mut tokens.into_iter().peekable())
+            .unwrap_or_else(|err| panic!("Benchmark {name} does not parse: {err}"));
+
+        // Warm up caches and the allocator
+        run(&program);
+
+        let mut samples = Vec::new();
+        let start = Instant::now();
+
+        while start.elapsed() < MEASUREMENT_TIME {
+            let sample = Instant::now();
+            run(&program);
+            samples.push(sample.elapsed());
+        }
+
+        samples.sort();
+
+        let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+
+        println!(
+            "{name:<24} mean {:>10}   median {:>10}   min {:>10}   ({} runs)",
+            format_duration(mean),
+            format_duration(samples[samples.len() / 2]),
+            format_duration(samples[0]),
+            samples.len()
+        );
+    }
+}
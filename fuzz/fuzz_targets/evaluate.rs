@@ -0,0 +1,38 @@
+//! Evaluates arbitrary input in a sandbox with limited fuel
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lisper::{
+    eval::evaluate,
+    lexer::lex,
+    parser::parse,
+    scope::{Output, Scope},
+};
+
+/// Evaluation steps a single input can take
+const FUEL: u64 = 100_000;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(tokens) = lex(&String::from_utf8_lossy(data)) else {
+        return;
+    };
+
+    let Ok(expr) = parse(&mut tokens.into_iter().peekable()) else {
+        return;
+    };
+
+    let mut env = Scope::new().wrap();
+    let globals = env.borrow().globals();
+
+    {
+        let mut globals = globals.borrow_mut();
+        globals.fuel = Some(FUEL);
+        globals.sandboxed = true;
+        globals.output = Output::Captured(String::new());
+    }
+
+    if let Ok(value) = evaluate(&expr, &mut env) {
+        let _ = value.to_string();
+    }
+});
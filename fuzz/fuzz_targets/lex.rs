@@ -0,0 +1,10 @@
+//! Lexes arbitrary input
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lisper::lexer::lex;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = lex(&String::from_utf8_lossy(data));
+});
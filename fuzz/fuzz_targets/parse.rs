@@ -0,0 +1,16 @@
+//! Parses arbitrary input and prints the parsed expression
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lisper::{lexer::lex, parser::parse};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(tokens) = lex(&String::from_utf8_lossy(data)) else {
+        return;
+    };
+
+    if let Ok(expr) = parse(&mut tokens.into_iter().peekable()) {
+        let _ = expr.to_string();
+    }
+});
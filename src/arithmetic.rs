@@ -0,0 +1,676 @@
+//! Arithmetic operations on numbers
+
+use std::cmp::Ordering;
+
+use crate::{
+    bigint::BigInt,
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+/// A number during arithmetic, exact numbers are kept as a fraction
+#[derive(Debug, Clone)]
+enum Number {
+    /// Integer or rational number with a positive denominator, both fit into `i64`
+    Exact(i128, i128),
+    /// Integer that does not fit into `i64`
+    Big(BigInt),
+    /// Rational number whose numerator or denominator does not fit into `i64`
+    BigFraction(BigInt, BigInt),
+    Float(f64),
+}
+
+impl Number {
+    fn from_expr(expr: &Expr) -> Option<Number> {
+        match expr {
+            Expr::Integer(value) => Some(Number::Exact(*value as i128, 1)),
+            Expr::BigInt(value) => Some(Number::Big(value.clone())),
+            Expr::Rational(numerator, denominator) => {
+                Some(Number::Exact(*numerator as i128, *denominator as i128))
+            }
+            Expr::BigRational(numerator, denominator) => Some(Number::BigFraction(
+                *numerator.clone(),
+                *denominator.clone(),
+            )),
+            Expr::Float(value) => Some(Number::Float(*value)),
+            _ => None,
+        }
+    }
+
+    fn to_float(&self) -> f64 {
+        match self {
+            Number::Exact(numerator, denominator) => *numerator as f64 / *denominator as f64,
+            Number::Big(value) => value.to_f64(),
+            Number::BigFraction(numerator, denominator) => {
+                fraction_to_float(numerator, denominator)
+            }
+            Number::Float(value) => *value,
+        }
+    }
+
+    /// Returns the number as a fraction of big integers, `None` for floats
+    fn to_fraction(&self) -> Option<(BigInt, BigInt)> {
+        match self {
+            Number::Exact(numerator, denominator) => Some((
+                BigInt::from_i128(*numerator),
+                BigInt::from_i128(*denominator),
+            )),
+            Number::Big(value) => Some((value.clone(), BigInt::from_i128(1))),
+            Number::BigFraction(numerator, denominator) => {
+                Some((numerator.clone(), denominator.clone()))
+            }
+            Number::Float(_) => None,
+        }
+    }
+
+    fn is_integer(&self) -> bool {
+        matches!(self, Number::Exact(_, 1) | Number::Big(_))
+    }
+
+    fn into_expr(self) -> Expr {
+        match self {
+            Number::Exact(numerator, denominator) => rational(numerator, denominator),
+            Number::Big(value) => integer(value),
+            Number::BigFraction(numerator, denominator) => big_rational(numerator, denominator),
+            Number::Float(value) => Expr::Float(value),
+        }
+    }
+}
+
+/// Divides big integers as floats. Integers too large to be floats are
+/// shifted to their leading bits first and the shifts are applied to the
+/// result.
+fn fraction_to_float(numerator: &BigInt, denominator: &BigInt) -> f64 {
+    if numerator.bit_length().max(denominator.bit_length()) <= 1000 {
+        return numerator.to_f64() / denominator.to_f64();
+    }
+
+    let leading = |value: &BigInt| {
+        let shift = value.bit_length().saturating_sub(64);
+        let (value, _) = value
+            .div_rem(&BigInt::from_i128(2).pow(shift))
+            .unwrap_or_default();
+
+        (value.to_f64(), shift as f64)
+    };
+
+    let (numerator, numerator_shift) = leading(numerator);
+    let (denominator, denominator_shift) = leading(denominator);
+
+    numerator / denominator * (numerator_shift - denominator_shift).exp2()
+}
+
+/// Creates an integer, big integers are only used when it does not fit into `i64`
+pub fn integer(value: BigInt) -> Expr {
+    match value.to_i64() {
+        Some(value) => Expr::Integer(value),
+        None => Expr::BigInt(value),
+    }
+}
+
+/// Creates a normalized rational number, or an integer when the denominator
+/// divides the numerator. The denominator must not be zero.
+///
+/// Big integers are only used when the numerator or the denominator does not
+/// fit into `i64`.
+pub fn rational(numerator: i128, denominator: i128) -> Expr {
+    let divisor = gcd(numerator, denominator);
+    let sign = if denominator < 0 { -1 } else { 1 };
+
+    let numerator = sign * numerator / divisor;
+    let denominator = sign * denominator / divisor;
+
+    if denominator == 1 {
+        return integer(BigInt::from_i128(numerator));
+    }
+
+    match (i64::try_from(numerator), i64::try_from(denominator)) {
+        (Ok(numerator), Ok(denominator)) => Expr::Rational(numerator, denominator),
+        _ => Expr::BigRational(
+            Box::new(BigInt::from_i128(numerator)),
+            Box::new(BigInt::from_i128(denominator)),
+        ),
+    }
+}
+
+/// Creates a normalized rational number from big integers, like `rational`
+pub fn big_rational(numerator: BigInt, denominator: BigInt) -> Expr {
+    let divisor = numerator.gcd(&denominator);
+    let (numerator, _) = numerator.div_rem(&divisor).unwrap_or_default();
+    let (denominator, _) = denominator.div_rem(&divisor).unwrap_or_default();
+
+    // The denominator is kept positive
+    let (numerator, denominator) = if denominator < BigInt::default() {
+        (-&numerator, -&denominator)
+    } else {
+        (numerator, denominator)
+    };
+
+    match (numerator.to_i128(), denominator.to_i128()) {
+        (Some(numerator), Some(denominator)) => rational(numerator, denominator),
+        _ if denominator == BigInt::from_i128(1) => integer(numerator),
+        _ => Expr::BigRational(Box::new(numerator), Box::new(denominator)),
+    }
+}
+
+/// Greatest common divisor, always positive
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a.max(1) as i128
+}
+
+/// Applies an arithmetic operation to two numbers
+fn apply(op: &'static str, a: Number, b: Number) -> Result<Number, EvalError> {
+    let division_by_zero = EvalError::DivisionByZero("/");
+
+    if let (Number::Exact(a_num, a_den), Number::Exact(b_num, b_den)) = (&a, &b) {
+        let (a_num, a_den, b_num, b_den) = (*a_num, *a_den, *b_num, *b_den);
+
+        if op == "/" && b_num == 0 {
+            return Err(division_by_zero);
+        }
+
+        let exact = || -> Option<(i128, i128)> {
+            match op {
+                "+" => Some((
+                    a_num
+                        .checked_mul(b_den)?
+                        .checked_add(b_num.checked_mul(a_den)?)?,
+                    a_den.checked_mul(b_den)?,
+                )),
+                "-" => Some((
+                    a_num
+                        .checked_mul(b_den)?
+                        .checked_sub(b_num.checked_mul(a_den)?)?,
+                    a_den.checked_mul(b_den)?,
+                )),
+                "*" => Some((a_num.checked_mul(b_num)?, a_den.checked_mul(b_den)?)),
+                _ => Some((a_num.checked_mul(b_den)?, a_den.checked_mul(b_num)?)),
+            }
+        };
+
+        if let Some((numerator, denominator)) = exact() {
+            return Number::from_expr(&rational(numerator, denominator))
+                .ok_or(EvalError::Unreachable);
+        }
+    }
+
+    // Exact numbers that do not fit are computed exactly with big integers
+    if let (Some((a_num, a_den)), Some((b_num, b_den))) = (a.to_fraction(), b.to_fraction()) {
+        let (numerator, denominator) = match op {
+            "+" => (&(&a_num * &b_den) + &(&b_num * &a_den), &a_den * &b_den),
+            "-" => (&(&a_num * &b_den) - &(&b_num * &a_den), &a_den * &b_den),
+            "*" => (&a_num * &b_num, &a_den * &b_den),
+            _ if b_num.is_zero() => return Err(division_by_zero),
+            _ => (&a_num * &b_den, &a_den * &b_num),
+        };
+
+        return Number::from_expr(&big_rational(numerator, denominator))
+            .ok_or(EvalError::Unreachable);
+    }
+
+    let (a, b) = (a.to_float(), b.to_float());
+
+    match op {
+        "+" => Ok(Number::Float(a + b)),
+        "-" => Ok(Number::Float(a - b)),
+        "*" => Ok(Number::Float(a * b)),
+        _ if b == 0.0 => Err(division_by_zero),
+        _ => Ok(Number::Float(a / b)),
+    }
+}
+
+/// Evaluates arithmetic operations `+`, `-`, `*` and `/`.
+///
+/// `(- x)` negates the number and `(/ x)` returns its reciprocal. Integers
+/// are promoted to rationals and rationals to floats when the arguments are
+/// mixed. Dividing integers results in a rational when the
+/// result is not whole. Integers that overflow are promoted to big integers.
+pub fn evaluate_arithmetic(
+    op: &'static str,
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let numbers = args
+        .iter()
+        .map(|expr| {
+            Number::from_expr(&evaluate_expr(expr, env, depth + 1)?).ok_or(
+                EvalError::IllegalArgument(op, "All arguments must be numbers"),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Subtraction and division start with the first argument, with a single
+    // argument they negate it or return its reciprocal
+    let (first, rest) = match (op, numbers.len()) {
+        ("+", _) | ("-", 1) => (Number::Exact(0, 1), numbers.as_slice()),
+        ("*", _) | ("/", 1) => (Number::Exact(1, 1), numbers.as_slice()),
+        _ => (numbers[0].clone(), &numbers[1..]),
+    };
+
+    rest.iter()
+        .try_fold(first, |a, b| apply(op, a, b.clone()))
+        .map(Number::into_expr)
+}
+
+/// Evaluates `mod` and `rem` of two numbers, exact numbers have an exact
+/// remainder.
+///
+/// The result of `rem` has the sign of the dividend, like the remainder of
+/// division rounded towards zero. The result of `mod` has the sign of the
+/// divisor, like the remainder of division rounded down.
+pub fn evaluate_remainder(
+    op: &'static str,
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if args.len() != 2 {
+        return Err(EvalError::ArgumentCount(op.to_string(), 2));
+    }
+
+    let dividend = evaluate_expr(&args[0], env, depth + 1)?;
+    let divisor = evaluate_expr(&args[1], env, depth + 1)?;
+
+    let division_by_zero = EvalError::DivisionByZero(op);
+
+    match (Number::from_expr(&dividend), Number::from_expr(&divisor)) {
+        (Some(a), Some(b)) if a.to_fraction().is_some() && b.to_fraction().is_some() => {
+            let (Some((a_num, a_den)), Some((b_num, b_den))) = (a.to_fraction(), b.to_fraction())
+            else {
+                return Err(EvalError::Unreachable);
+            };
+
+            // With both fractions over the same denominator, the remainder is
+            // the one of their numerators
+            let denominator = &a_den * &b_den;
+            let (_, remainder) = (&a_num * &b_den)
+                .div_rem(&(&b_num * &a_den))
+                .ok_or(division_by_zero)?;
+            let zero = BigInt::default();
+
+            if op == "mod" && remainder != zero && (remainder < zero) != (b_num < zero) {
+                return Ok(big_rational(&remainder + &(&b_num * &a_den), denominator));
+            }
+
+            Ok(big_rational(remainder, denominator))
+        }
+        (Some(Number::Float(_)), Some(b)) | (Some(_), Some(b @ Number::Float(_))) => {
+            let a = as_float(&dividend).unwrap_or_default();
+            let b = b.to_float();
+
+            if b == 0.0 {
+                return Err(division_by_zero);
+            }
+
+            let remainder = a % b;
+
+            if op == "mod" && remainder != 0.0 && (remainder < 0.0) != (b < 0.0) {
+                return Ok(Expr::Float(remainder + b));
+            }
+
+            Ok(Expr::Float(remainder))
+        }
+        _ => Err(EvalError::IllegalArgument(op, "Arguments must be numbers")),
+    }
+}
+
+/// Largest number of bits of an exact power computed by `expt`
+const MAX_POWER_BITS: u64 = 1 << 18;
+
+/// Evaluates `expt`, raising a number to a power.
+///
+/// Exact numbers raised to an integer are computed exactly, promoting to big
+/// integers when needed. Negative exponents result in rationals. Powers with
+/// a float or a rational exponent are computed with floats.
+pub fn evaluate_expt(
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if args.len() != 2 {
+        return Err(EvalError::ArgumentCount("expt".to_string(), 2));
+    }
+
+    let base = Number::from_expr(&evaluate_expr(&args[0], env, depth + 1)?);
+    let exponent = Number::from_expr(&evaluate_expr(&args[1], env, depth + 1)?);
+
+    let (base, exponent) = match (base, exponent) {
+        (Some(base), Some(exponent)) => (base, exponent),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "expt",
+                "All arguments must be numbers",
+            ))
+        }
+    };
+
+    let exact = match (base.to_fraction(), exponent.is_integer()) {
+        (Some(base), true) => exponent.to_fraction().map(|(exponent, _)| (base, exponent)),
+        _ => None,
+    };
+
+    let ((numerator, denominator), exponent) = match exact {
+        Some(exact) => exact,
+        None => return Ok(Expr::Float(base.to_float().powf(exponent.to_float()))),
+    };
+
+    let zero = BigInt::default();
+
+    let power = exponent
+        .to_i64()
+        .map(i64::unsigned_abs)
+        .filter(|power| {
+            let bits = numerator.bit_length().max(denominator.bit_length());
+
+            bits.saturating_sub(1).saturating_mul(*power) <= MAX_POWER_BITS
+        })
+        .ok_or(EvalError::IllegalArgument("expt", "Exponent is too large"))?;
+
+    // Negative exponents invert the base
+    let (numerator, denominator) = if exponent >= zero {
+        (numerator.pow(power), denominator.pow(power))
+    } else if numerator.is_zero() {
+        return Err(EvalError::DivisionByZero("expt"));
+    } else {
+        (denominator.pow(power), numerator.pow(power))
+    };
+
+    Ok(big_rational(numerator, denominator))
+}
+
+/// Evaluates `abs`, the absolute value of a number
+pub fn evaluate_abs(
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if args.len() != 1 {
+        return Err(EvalError::ArgumentCount("abs".to_string(), 1));
+    }
+
+    match evaluate_expr(&args[0], env, depth + 1)? {
+        Expr::Integer(value) => Ok(integer(BigInt::from_i128((value as i128).abs()))),
+        Expr::BigInt(value) => Ok(integer(value.abs())),
+        Expr::Rational(numerator, denominator) => {
+            Ok(rational((numerator as i128).abs(), denominator as i128))
+        }
+        Expr::BigRational(numerator, denominator) => {
+            Ok(big_rational(numerator.abs(), *denominator))
+        }
+        Expr::Float(value) => Ok(Expr::Float(value.abs())),
+        _ => Err(EvalError::IllegalArgument(
+            "abs",
+            "Argument must be a number",
+        )),
+    }
+}
+
+/// Evaluates `min` and `max`, returning the argument that is ordered as
+/// `wanted` against all the others
+pub fn evaluate_extremum(
+    op: &'static str,
+    wanted: Ordering,
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let mut result = None;
+
+    for arg in args {
+        let value = evaluate_expr(arg, env, depth + 1)?;
+
+        if Number::from_expr(&value).is_none() {
+            return Err(EvalError::IllegalArgument(
+                op,
+                "All arguments must be numbers",
+            ));
+        }
+
+        result = match result {
+            Some(best) if compare(&value, &best) != Some(wanted) => Some(best),
+            _ => Some(value),
+        };
+    }
+
+    result.ok_or(EvalError::ArgumentCount(op.to_string(), 1))
+}
+
+/// Converts a finite float to the exact number it represents, `None` for
+/// infinite floats and NaN
+fn float_to_exact(value: f64) -> Option<Expr> {
+    if !value.is_finite() {
+        return None;
+    }
+
+    // Split the float into its mantissa and binary exponent
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64;
+    let mantissa = (bits & ((1 << 52) - 1)) as i128;
+
+    let (mantissa, exponent) = match exponent {
+        0 => (mantissa, 1 - 1075),
+        _ => (mantissa | 1 << 52, exponent - 1075),
+    };
+
+    let mantissa = BigInt::from_i128(if value < 0.0 { -mantissa } else { mantissa });
+    let power = BigInt::from_i128(2).pow(exponent.unsigned_abs());
+
+    if exponent >= 0 {
+        Some(integer(&mantissa * &power))
+    } else {
+        Some(big_rational(mantissa, power))
+    }
+}
+
+/// Evaluates `floor`, `ceiling`, `round` and `truncate`, rounding a number to
+/// an integer. `round` rounds halves to the even integer.
+pub fn evaluate_rounding(
+    op: &'static str,
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if args.len() != 1 {
+        return Err(EvalError::ArgumentCount(op.to_string(), 1));
+    }
+
+    match evaluate_expr(&args[0], env, depth + 1)? {
+        value @ (Expr::Integer(_) | Expr::BigInt(_)) => Ok(value),
+        Expr::Rational(numerator, denominator) => {
+            let (numerator, denominator) = (numerator as i128, denominator as i128);
+
+            // The denominator is always positive, the remainder is not negative
+            let quotient = numerator.div_euclid(denominator);
+            let remainder = numerator.rem_euclid(denominator);
+
+            let rounded = match op {
+                "floor" => quotient,
+                "ceiling" => quotient + 1,
+                "truncate" if numerator < 0 => quotient + 1,
+                "truncate" => quotient,
+                _ if 2 * remainder > denominator => quotient + 1,
+                _ if 2 * remainder < denominator => quotient,
+                _ => quotient + quotient.rem_euclid(2),
+            };
+
+            Ok(integer(BigInt::from_i128(rounded)))
+        }
+        Expr::BigRational(numerator, denominator) => {
+            let (numerator, denominator) = (*numerator, *denominator);
+            let zero = BigInt::default();
+            let one = BigInt::from_i128(1);
+
+            // Round the quotient down, so that the remainder is not negative
+            let (quotient, remainder) = numerator.div_rem(&denominator).unwrap_or_default();
+            let (quotient, remainder) = if remainder < zero {
+                (&quotient - &one, &remainder + &denominator)
+            } else {
+                (quotient, remainder)
+            };
+
+            let twice_remainder = &remainder + &remainder;
+            let (_, parity) = quotient.div_rem(&BigInt::from_i128(2)).unwrap_or_default();
+
+            let rounded = match op {
+                "floor" => quotient,
+                "ceiling" => &quotient + &one,
+                "truncate" if numerator < zero => &quotient + &one,
+                "truncate" => quotient,
+                _ if twice_remainder > denominator => &quotient + &one,
+                _ if twice_remainder < denominator => quotient,
+                _ => &quotient + &parity.abs(),
+            };
+
+            Ok(integer(rounded))
+        }
+        Expr::Float(value) => {
+            let rounded = match op {
+                "floor" => value.floor(),
+                "ceiling" => value.ceil(),
+                "truncate" => value.trunc(),
+                _ => value.round_ties_even(),
+            };
+
+            float_to_exact(rounded).ok_or(EvalError::IllegalArgument(
+                op,
+                "Infinite numbers cannot be rounded",
+            ))
+        }
+        _ => Err(EvalError::IllegalArgument(op, "Argument must be a number")),
+    }
+}
+
+/// Evaluates `exact->inexact` and `inexact->exact`, converting exact numbers
+/// to floats and floats to the exact numbers they represent
+pub fn evaluate_exactness(
+    op: &'static str,
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if args.len() != 1 {
+        return Err(EvalError::ArgumentCount(op.to_string(), 1));
+    }
+
+    let value = evaluate_expr(&args[0], env, depth + 1)?;
+
+    match (op, value) {
+        ("inexact->exact", Expr::Float(value)) => float_to_exact(value).ok_or(
+            EvalError::IllegalArgument(op, "Infinite numbers have no exact value"),
+        ),
+        ("exact->inexact", value) if Number::from_expr(&value).is_some() => {
+            Ok(Expr::Float(as_float(&value).unwrap_or_default()))
+        }
+        (_, value) if Number::from_expr(&value).is_some() => Ok(value),
+        _ => Err(EvalError::IllegalArgument(op, "Argument must be a number")),
+    }
+}
+
+/// Evaluates `gcd` and `lcm`, the greatest common divisor and the least common
+/// multiple of integers. Both are never negative, without arguments they are
+/// 0 and 1.
+pub fn evaluate_divisors(
+    op: &'static str,
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let mut result = BigInt::from_i128(if op == "gcd" { 0 } else { 1 });
+
+    for arg in args {
+        let value = match evaluate_expr(arg, env, depth + 1)? {
+            Expr::Integer(value) => BigInt::from_i128(value as i128),
+            Expr::BigInt(value) => value,
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    op,
+                    "All arguments must be integers",
+                ))
+            }
+        };
+
+        let divisor = result.gcd(&value);
+
+        result = match op {
+            "gcd" => divisor,
+            _ if divisor.is_zero() => divisor,
+            _ => {
+                let (quotient, _) = (&result * &value).div_rem(&divisor).unwrap_or_default();
+
+                quotient.abs()
+            }
+        };
+    }
+
+    Ok(integer(result))
+}
+
+/// Evaluates `sqrt`, `sin`, `cos`, `tan`, `log` and `exp`, always resulting
+/// in a float. `log` is the natural logarithm.
+pub fn evaluate_float_function(
+    op: &'static str,
+    args: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if args.len() != 1 {
+        return Err(EvalError::ArgumentCount(op.to_string(), 1));
+    }
+
+    let value = as_float(&evaluate_expr(&args[0], env, depth + 1)?)
+        .ok_or(EvalError::IllegalArgument(op, "Argument must be a number"))?;
+
+    let result = match op {
+        "sqrt" if value < 0.0 => {
+            return Err(EvalError::IllegalArgument(
+                op,
+                "Argument must not be negative",
+            ))
+        }
+        "log" if value <= 0.0 => {
+            return Err(EvalError::IllegalArgument(op, "Argument must be positive"))
+        }
+        "sqrt" => value.sqrt(),
+        "sin" => value.sin(),
+        "cos" => value.cos(),
+        "tan" => value.tan(),
+        "log" => value.ln(),
+        _ => value.exp(),
+    };
+
+    Ok(Expr::Float(result))
+}
+
+/// Orders two numbers, `None` if they are not numbers or cannot be ordered
+pub fn compare(a: &Expr, b: &Expr) -> Option<Ordering> {
+    match (Number::from_expr(a)?, Number::from_expr(b)?) {
+        (Number::Exact(a_num, a_den), Number::Exact(b_num, b_den)) => {
+            Some((a_num * b_den).cmp(&(b_num * a_den)))
+        }
+        (a, b) => match (a.to_fraction(), b.to_fraction()) {
+            (Some((a_num, a_den)), Some((b_num, b_den))) => {
+                Some((&a_num * &b_den).cmp(&(&b_num * &a_den)))
+            }
+            _ => a.to_float().partial_cmp(&b.to_float()),
+        },
+    }
+}
+
+/// Converts a number to a float, `None` if the value is not a number
+pub fn as_float(value: &Expr) -> Option<f64> {
+    Number::from_expr(value).as_ref().map(Number::to_float)
+}
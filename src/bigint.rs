@@ -0,0 +1,389 @@
+//! Arbitrary-precision integers, used when integers do not fit into 64 bits
+
+use std::{
+    cmp::Ordering,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+/// Arbitrary-precision integer stored as its sign and magnitude
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    /// Digits in base 2^32, least significant first, without leading zeros
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+
+        BigInt {
+            // Zero is never negative
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn from_i128(value: i128) -> Self {
+        let mut rest = value.unsigned_abs();
+        let mut magnitude = Vec::new();
+
+        while rest != 0 {
+            magnitude.push(rest as u32);
+            rest >>= 32;
+        }
+
+        BigInt::new(value < 0, magnitude)
+    }
+
+    /// Converts the integer to `i128`, `None` if it does not fit
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.magnitude.len() > 4 {
+            return None;
+        }
+
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u128, |acc, digit| acc << 32 | *digit as u128);
+
+        if self.negative {
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// Converts the integer to `i64`, `None` if it does not fit
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_i128().and_then(|value| i64::try_from(value).ok())
+    }
+
+    /// Converts the integer to the nearest float, infinite if it is too large
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |acc, digit| acc * 4294967296.0 + *digit as f64);
+
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Parses a decimal integer with an optional sign
+    pub fn parse(text: &str) -> Option<Self> {
+        BigInt::parse_radix(text, 10)
+    }
+
+    /// Parses an integer with an optional sign written in a radix between 2
+    /// and 36, digits above 9 are letters of either case
+    pub fn parse_radix(text: &str, radix: u32) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut magnitude = Vec::new();
+
+        for c in digits.chars() {
+            mul_small_add(&mut magnitude, radix, c.to_digit(radix)?);
+        }
+
+        Some(BigInt::new(negative, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::new(false, self.magnitude.clone())
+    }
+
+    /// Number of bits of the magnitude, without leading zeros
+    pub fn bit_length(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(last) => self.magnitude.len() as u64 * 32 - last.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// Raises the integer to a power using repeated squaring
+    pub fn pow(&self, mut exponent: u64) -> BigInt {
+        let mut result = BigInt::from_i128(1);
+        let mut base = self.clone();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+
+            exponent >>= 1;
+
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+
+        result
+    }
+
+    /// Divides with the quotient rounded towards zero, the remainder has the
+    /// sign of the dividend. Returns `None` when dividing by zero.
+    pub fn div_rem(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return None;
+        }
+
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &divisor.magnitude);
+
+        Some((
+            BigInt::new(self.negative != divisor.negative, quotient),
+            BigInt::new(self.negative, remainder),
+        ))
+    }
+
+    /// Greatest common divisor, never negative
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        let mut a = BigInt::new(false, self.magnitude.clone());
+        let mut b = BigInt::new(false, other.magnitude.clone());
+
+        while !b.is_zero() {
+            let (_, remainder) = div_rem_magnitude(&a.magnitude, &b.magnitude);
+            a = b;
+            b = BigInt::new(false, remainder);
+        }
+
+        a
+    }
+}
+
+/// Multiplies a magnitude by a small factor and adds a small value to it
+fn mul_small_add(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+
+    for digit in magnitude.iter_mut() {
+        let value = *digit as u64 * factor as u64 + carry;
+        *digit = value as u32;
+        carry = value >> 32;
+    }
+
+    if carry != 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+/// Divides a magnitude by a small divisor in place, returning the remainder
+fn div_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+
+    for digit in magnitude.iter_mut().rev() {
+        let value = remainder << 32 | *digit as u64;
+        *digit = (value / divisor as u64) as u32;
+        remainder = value % divisor as u64;
+    }
+
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+
+    remainder as u32
+}
+
+fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+
+    for i in 0..a.len().max(b.len()) {
+        let value = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(value as u32);
+        carry = value >> 32;
+    }
+
+    if carry != 0 {
+        result.push(carry as u32);
+    }
+
+    result
+}
+
+/// Subtracts magnitudes, `a` must not be smaller than `b`
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+
+    for (i, digit) in a.iter().enumerate() {
+        let mut value = *digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+
+        borrow = if value < 0 {
+            value += 1 << 32;
+            1
+        } else {
+            0
+        };
+
+        result.push(value as u32);
+    }
+
+    result
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+
+        for (j, y) in b.iter().enumerate() {
+            let value = result[i + j] as u64 + *x as u64 * *y as u64 + carry;
+            result[i + j] = value as u32;
+            carry = value >> 32;
+        }
+
+        result[i + b.len()] = carry as u32;
+    }
+
+    result
+}
+
+/// Long division of magnitudes, one bit at a time. The divisor must not be zero.
+fn div_rem_magnitude(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if divisor.len() == 1 {
+        let mut quotient = dividend.to_vec();
+        let remainder = div_small(&mut quotient, divisor[0]);
+
+        return (quotient, vec![remainder]);
+    }
+
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+
+    for bit in (0..dividend.len() * 32).rev() {
+        mul_small_add(&mut remainder, 2, dividend[bit / 32] >> (bit % 32) & 1);
+
+        if compare_magnitude(&remainder, divisor) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, divisor);
+
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+
+    (quotient, remainder)
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(
+                self.negative,
+                add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+
+        match compare_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::new(
+                other.negative,
+                sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::new(
+                self.negative,
+                sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            mul_magnitude(&self.magnitude, &other.magnitude),
+        )
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Split into groups of nine decimal digits, least significant first
+        let mut rest = self.magnitude.clone();
+        let mut groups = Vec::new();
+
+        while !rest.is_empty() {
+            groups.push(div_small(&mut rest, 1_000_000_000));
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        write!(f, "{}", groups.last().unwrap_or(&0))?;
+
+        for group in groups.iter().rev().skip(1) {
+            write!(f, "{group:09}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! Named blocks that can be left early with `return-from`
+
+use std::borrow::Cow;
+
+use crate::{
+    eval::{evaluate_expr, forget_error_span, EvalError, Step},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+/// Evaluates `block` built-in, evaluating its body and returning the value of
+/// the last expression. Evaluating `return-from` with the name of the block
+/// anywhere inside of the body, including functions called from it, leaves
+/// the block with the returned value.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (block search
+///     (dolist (x xs) (if (> x 10) (return-from search x) nil))
+///     nil
+/// )
+/// ```
+pub(crate) fn evaluate_block<'a>(
+    list: &'a [Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Step<'a>, EvalError> {
+    let name = match list.get(1) {
+        Some(Expr::Symbol(name)) => name,
+        Some(_) => {
+            return Err(EvalError::IllegalArgument(
+                "block",
+                "Block name must be a symbol",
+            ))
+        }
+        None => return Err(EvalError::ArgumentCount("block".to_string(), 1)),
+    };
+
+    // The last expression is in tail position, where the evaluation
+    // catches `return-from` for the block
+    let (last, rest) = match list[2..].split_last() {
+        Some(split) => split,
+        None => return Ok(Step::Value(Expr::Nil)),
+    };
+
+    for expr in rest {
+        match evaluate_expr(expr, env, depth + 1) {
+            Ok(_) => {}
+            Err(EvalError::ReturnFrom(block, value)) if block == *name => {
+                forget_error_span(env);
+
+                return Ok(Step::Value(value));
+            }
+            Err(err) => return Err(err),
+        };
+    }
+
+    Ok(Step::Block(name.clone(), Cow::Borrowed(last)))
+}
+
+/// Evaluates `return-from` built-in, leaving the innermost block with the
+/// name that is being evaluated. The value defaults to `nil`.
+///
+/// Expected Lisper syntax:
+///
+/// ```(return-from search x)```
+pub fn evaluate_return_from(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 && list.len() != 3 {
+        return Err(EvalError::ArgumentCount("return-from".to_string(), 2));
+    }
+
+    let name = match &list[1] {
+        Expr::Symbol(name) => name.clone(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "return-from",
+                "Block name must be a symbol",
+            ))
+        }
+    };
+
+    let value = match list.get(2) {
+        Some(expr) => evaluate_expr(expr, env, depth + 1)?,
+        None => Expr::Nil,
+    };
+
+    Err(EvalError::ReturnFrom(name, value))
+}
@@ -0,0 +1,302 @@
+//! Byte strings and reading and writing files
+
+use std::io::Write;
+
+use crate::{
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+/// Characters encoding the values from 0 to 63 in base64
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Evaluates `bytes` built-in, creating a byte string from numbers between
+/// 0 and 255, or from the UTF-8 encoding of a string.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (bytes 72 105)
+/// (bytes "Hi")
+/// ```
+pub fn evaluate_bytes(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let values = list[1..]
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let [Expr::String(string)] = values.as_slice() {
+        return Ok(Expr::Bytes(string.as_bytes().to_vec()));
+    }
+
+    values
+        .iter()
+        .map(|value| match value {
+            Expr::Integer(byte) => u8::try_from(*byte).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(Expr::Bytes)
+        .ok_or(EvalError::IllegalArgument(
+            "bytes",
+            "Arguments must be numbers between 0 and 255 or a single string",
+        ))
+}
+
+/// Evaluates `base64-encode` built-in, encoding a byte string or the UTF-8
+/// encoding of a string in base64 with padding.
+///
+/// Expected Lisper syntax:
+///
+/// ```(base64-encode b"Hi\x00")```
+pub fn evaluate_base64_encode(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("base64-encode".to_string(), 1));
+    }
+
+    let bytes = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::Bytes(bytes) => bytes,
+        Expr::String(string) => string.into_bytes(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "base64-encode",
+                "Argument must be a byte string or a string",
+            ))
+        }
+    };
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, byte)| acc | (*byte as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    Ok(Expr::String(encoded))
+}
+
+/// Evaluates `base64-decode` built-in, decoding base64 into a byte string.
+/// Padding is optional and whitespace is ignored.
+///
+/// Expected Lisper syntax:
+///
+/// ```(base64-decode "SGkA")```
+pub fn evaluate_base64_decode(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("base64-decode".to_string(), 1));
+    }
+
+    let encoded = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::String(string) => string,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "base64-decode",
+                "Argument must be a string",
+            ))
+        }
+    };
+
+    let invalid = EvalError::IllegalArgument("base64-decode", "Invalid base64");
+
+    let encoded: Vec<u8> = encoded
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    let data = encoded
+        .strip_suffix(b"==")
+        .or_else(|| encoded.strip_suffix(b"="))
+        .unwrap_or(&encoded);
+
+    // Padding completes the last group to four characters
+    if data.len() % 4 == 1 || (data.len() != encoded.len() && !encoded.len().is_multiple_of(4)) {
+        return Err(invalid);
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() / 4 * 3 + 2);
+
+    for chunk in data.chunks(4) {
+        let mut group = 0u32;
+
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|a| a == c)
+                .ok_or(invalid.clone())?;
+
+            group |= (value as u32) << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Ok(Expr::Bytes(bytes))
+}
+
+/// Evaluates `read-bytes` built-in, returning the content of a file.
+///
+/// Expected Lisper syntax:
+///
+/// ```(read-bytes "image.png")```
+pub fn evaluate_read_bytes(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("read-bytes".to_string(), 1));
+    }
+
+    let path = evaluate_path("read-bytes", &list[1], env, depth)?;
+
+    std::fs::read(&path)
+        .map(Expr::Bytes)
+        .map_err(|err| EvalError::IoFailed(path, err.to_string()))
+}
+
+/// Evaluates `write-bytes` built-in, replacing the content of a file.
+///
+/// Expected Lisper syntax:
+///
+/// ```(write-bytes "copy.png" (read-bytes "image.png"))```
+pub fn evaluate_write_bytes(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("write-bytes".to_string(), 2));
+    }
+
+    let path = evaluate_path("write-bytes", &list[1], env, depth)?;
+
+    let bytes = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::Bytes(bytes) => bytes,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "write-bytes",
+                "Content must be a byte string",
+            ))
+        }
+    };
+
+    std::fs::write(&path, bytes)
+        .map(|_| Expr::Nil)
+        .map_err(|err| EvalError::IoFailed(path, err.to_string()))
+}
+
+/// Evaluates `read-file` built-in, returning the content of a text file as
+/// a string. Files that are not valid UTF-8 are signalled as `io-error`.
+///
+/// Expected Lisper syntax:
+///
+/// ```(read-file "notes.txt")```
+pub fn evaluate_read_file(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("read-file".to_string(), 1));
+    }
+
+    let path = evaluate_path("read-file", &list[1], env, depth)?;
+
+    std::fs::read_to_string(&path)
+        .map(Expr::String)
+        .map_err(|err| EvalError::IoFailed(path, err.to_string()))
+}
+
+/// Evaluates `write-file` and `append-file` built-ins, replacing the content
+/// of a text file with a string or adding the string to its end. Both create
+/// the file if it does not exist.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (write-file "notes.txt" "first line\n")
+/// (append-file "notes.txt" "second line\n")
+/// ```
+pub fn evaluate_write_file(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 2));
+    }
+
+    let path = evaluate_path(name, &list[1], env, depth)?;
+
+    let content = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::String(content) => content,
+        _ => return Err(EvalError::IllegalArgument(name, "Content must be a string")),
+    };
+
+    let written = match name {
+        "append-file" => std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes())),
+        _ => std::fs::write(&path, content),
+    };
+
+    written
+        .map(|_| Expr::Nil)
+        .map_err(|err| EvalError::IoFailed(path, err.to_string()))
+}
+
+/// Evaluates the path of a file, which cannot be accessed in the sandbox
+fn evaluate_path(
+    name: &'static str,
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<String, EvalError> {
+    let path = match evaluate_expr(expr, env, depth + 1)? {
+        Expr::String(path) => path,
+        _ => return Err(EvalError::IllegalArgument(name, "Path must be a string")),
+    };
+
+    if env.borrow().globals().borrow().sandboxed {
+        return Err(EvalError::IoFailed(
+            path,
+            "Files cannot be accessed in the sandbox".to_string(),
+        ));
+    }
+
+    Ok(path)
+}
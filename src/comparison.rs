@@ -1,51 +1,78 @@
 //! Comparison helpers for evaluation
 
+use std::cmp::Ordering;
+
 use crate::{
-    eval::{evaluate, EvalError},
+    arithmetic::{as_float, compare},
+    eval::{evaluate_expr, EvalError},
     expr::Expr,
     scope::PassableScope,
 };
 
-/// Evaluates a list of expressions and compares them in windows with the provided function
-pub fn compare_integers(
+/// Evaluates a list of expressions and compares them in windows with the provided function.
+///
+/// Numbers of different types are compared by their value.
+pub fn compare_numbers(
     args: &[Expr],
     env: &mut PassableScope,
-    predicate: fn(i64, i64) -> bool,
+    depth: usize,
+    predicate: fn(Ordering) -> bool,
 ) -> Result<Expr, EvalError> {
-    let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
-
-    // Check if there are any errors
-    if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
-        return Err(err.clone());
-    }
+    let evaluated = args
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
 
     // Check if all elements are numbers
-    for expr in evaluated.iter() {
-        match expr {
-            Ok(Expr::Integer(_)) => {}
-            _ => {
-                return Err(EvalError::IllegalArgument(
-                    "compare",
-                    "All arguments must be numbers",
-                ));
-            }
-        }
+    if evaluated.iter().any(|expr| as_float(expr).is_none()) {
+        return Err(EvalError::IllegalArgument(
+            "compare",
+            "All arguments must be numbers",
+        ));
     }
 
     Ok(Expr::Boolean(
         evaluated
-            .iter()
-            .filter_map(|e| e.clone().ok())
-            .collect::<Vec<Expr>>()
             .windows(2)
-            .all(|w| {
-                match (&w[0], &w[1]) {
-                    (Expr::Integer(a), Expr::Integer(b)) => predicate(*a, *b),
-                    _ => {
-                        // This arm would never match, we panic if it does
-                        panic!("Something unexpected happened in evaluating comparison");
-                    }
-                }
-            }),
+            .all(|w| compare(&w[0], &w[1]).is_some_and(predicate)),
     ))
 }
+
+/// Compares two values by their value, as done by `=`.
+///
+/// Values are compared structurally, except for numbers which are equal when
+/// they have the same value, even when they are of different types.
+pub fn is_equal(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::List(a), Expr::List(b)) | (Expr::Values(a), Expr::Values(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| is_equal(a, b))
+        }
+        (
+            Expr::Integer(_)
+            | Expr::BigInt(_)
+            | Expr::Rational(_, _)
+            | Expr::BigRational(_, _)
+            | Expr::Float(_),
+            Expr::Integer(_)
+            | Expr::BigInt(_)
+            | Expr::Rational(_, _)
+            | Expr::BigRational(_, _)
+            | Expr::Float(_),
+        ) => compare(a, b) == Some(Ordering::Equal),
+        _ => a == b,
+    }
+}
+
+/// Compares two values by identity, as done by `eq?`.
+///
+/// Atoms are identical when they are equal. Lists are values that are copied
+/// around, so they only are identical when both of them are empty. Functions
+/// are identical when they were created by the same expression in the same scope.
+pub fn is_identical(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::List(a), Expr::List(b)) | (Expr::Values(a), Expr::Values(b)) => {
+            a.is_empty() && b.is_empty()
+        }
+        _ => a == b,
+    }
+}
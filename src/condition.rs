@@ -0,0 +1,592 @@
+//! Condition system, typed errors that can be signalled and handled
+
+use crate::{
+    eval::{evaluate_expr, forget_error_span, EvalError},
+    expr::Expr,
+    scope::{PassableScope, Scope},
+};
+
+/// Root of the condition hierarchy, every condition is an `error`
+pub const ROOT_CONDITION: &str = "error";
+
+/// Condition raised by `throw` with a value that is not a condition
+const THROWN_CONDITION: &str = "thrown";
+
+/// Condition types that interpreter errors are mapped into, and the ones
+/// raised by `error`, `assert` and `throw`
+const BUILTIN_CONDITIONS: [&str; 14] = [
+    "undefined-variable",
+    "undefined-function",
+    "argument-count",
+    "illegal-argument",
+    "division-by-zero",
+    "index-out-of-range",
+    "recursion-limit",
+    "constant-reassignment",
+    "import-error",
+    "io-error",
+    "internal-error",
+    "simple-error",
+    "assertion-failed",
+    THROWN_CONDITION,
+];
+
+/// A condition type defined by `define-condition`
+#[derive(Debug, Clone)]
+pub struct ConditionType {
+    /// Name of the parent condition type
+    pub parent: String,
+    /// Names of all fields, including the ones of parent types
+    pub fields: Vec<String>,
+}
+
+/// Converts an `EvalError` into a condition value that can be handled
+pub fn to_condition(err: &EvalError) -> Expr {
+    let name = match err {
+        EvalError::Signal(condition) => return condition.clone(),
+        EvalError::UndefinedVariable(_) => "undefined-variable",
+        EvalError::UndefinedFunction(_) => "undefined-function",
+        EvalError::ArgumentCount(_, _) | EvalError::ArgumentCountRange(_, _, _) => "argument-count",
+        EvalError::IllegalArgument(_, _) => "illegal-argument",
+        EvalError::DivisionByZero(_) => "division-by-zero",
+        EvalError::IndexOutOfRange(_, _, _) => "index-out-of-range",
+        EvalError::MaximumRecursionDepthReached(_) => "recursion-limit",
+        EvalError::ConstantReassignment(_) => "constant-reassignment",
+        EvalError::ImportFailed(_, _) => "import-error",
+        EvalError::IoFailed(_, _) => "io-error",
+        EvalError::Unimplemented
+        | EvalError::Unreachable
+        | EvalError::Internal
+        | EvalError::ReturnFrom(_, _)
+        | EvalError::Interrupted
+        | EvalError::FuelExhausted => "internal-error",
+    };
+
+    Expr::Condition(
+        name.to_string(),
+        vec![("message".to_string(), Expr::String(err.to_string()))],
+    )
+}
+
+/// Returns the name of a condition type, which is a symbol apart from `error`,
+/// lexed as the keyword raising it
+fn type_name(expr: &Expr) -> Option<&String> {
+    match expr {
+        Expr::Symbol(name) => Some(name),
+        Expr::Keyword(name) if name == ROOT_CONDITION => Some(name),
+        _ => None,
+    }
+}
+
+/// Checks whether `name` is the condition type `ancestor` or derived from it
+fn is_subtype(name: &str, ancestor: &str, env: &PassableScope) -> bool {
+    let globals = env.borrow().globals();
+    let mut current = name.to_string();
+
+    loop {
+        if current == ancestor {
+            return true;
+        }
+
+        if BUILTIN_CONDITIONS.contains(&current.as_str()) {
+            current = ROOT_CONDITION.to_string();
+            continue;
+        }
+
+        match globals.borrow().conditions.get(&current) {
+            Some(condition_type) => current = condition_type.parent.clone(),
+            None => return false,
+        }
+    }
+}
+
+/// Returns the fields of a condition type or `None` if it does not exist
+fn condition_fields(name: &str, env: &PassableScope) -> Option<Vec<String>> {
+    if name == ROOT_CONDITION {
+        return Some(Vec::new());
+    }
+
+    if name == THROWN_CONDITION {
+        return Some(vec!["value".to_string()]);
+    }
+
+    if BUILTIN_CONDITIONS.contains(&name) {
+        return Some(vec!["message".to_string()]);
+    }
+
+    let globals = env.borrow().globals();
+    let fields = globals
+        .borrow()
+        .conditions
+        .get(name)
+        .map(|condition_type| condition_type.fields.clone());
+
+    fields
+}
+
+/// Evaluates `define-condition` built-in, defining a new condition type.
+///
+/// The parent type defaults to `error`.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (define-condition file-error (path))
+/// (define-condition missing-file file-error (mode))
+/// ```
+pub fn evaluate_define_condition(
+    list: &[Expr],
+    env: &mut PassableScope,
+) -> Result<Expr, EvalError> {
+    let (name, parent, own_fields) = match list {
+        [_, Expr::Symbol(name), Expr::List(fields)] => (name, ROOT_CONDITION, fields),
+        [_, Expr::Symbol(name), parent, Expr::List(fields)] if type_name(parent).is_some() => {
+            (name, type_name(parent).unwrap().as_str(), fields)
+        }
+        [_, _, _] | [_, _, _, _] => {
+            return Err(EvalError::IllegalArgument(
+                "define-condition",
+                "Expected a name, an optional parent and a list of fields",
+            ))
+        }
+        _ => return Err(EvalError::ArgumentCount("define-condition".to_string(), 2)),
+    };
+
+    if name == ROOT_CONDITION || BUILTIN_CONDITIONS.contains(&name.as_str()) {
+        return Err(EvalError::IllegalArgument(
+            "define-condition",
+            "Built-in condition types cannot be redefined",
+        ));
+    }
+
+    // Redefining a type with a parent derived from it would create a cycle
+    if is_subtype(parent, name, env) {
+        return Err(EvalError::IllegalArgument(
+            "define-condition",
+            "Condition type cannot be derived from itself",
+        ));
+    }
+
+    let mut fields = condition_fields(parent, env).ok_or(EvalError::IllegalArgument(
+        "define-condition",
+        "Parent condition type is not defined",
+    ))?;
+
+    for field in own_fields {
+        match field {
+            Expr::Symbol(field) => fields.push(field.clone()),
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "define-condition",
+                    "Fields must be symbols",
+                ))
+            }
+        }
+    }
+
+    let globals = env.borrow().globals();
+    globals.borrow_mut().conditions.insert(
+        name.clone(),
+        ConditionType {
+            parent: parent.to_string(),
+            fields,
+        },
+    );
+
+    Ok(Expr::Nil)
+}
+
+/// Evaluates `signal` built-in, raising a condition.
+///
+/// Either creates a new condition from its type and field values, or raises
+/// an existing condition value again.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (signal file-error "missing.txt")
+/// (signal e)
+/// ```
+pub fn evaluate_signal(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let name = match list.get(1).map(type_name) {
+        Some(Some(name)) => name,
+        Some(None) => {
+            return Err(EvalError::IllegalArgument(
+                "signal",
+                "Condition type must be a symbol",
+            ))
+        }
+        None => return Err(EvalError::ArgumentCount("signal".to_string(), 1)),
+    };
+
+    let fields = match condition_fields(name, env) {
+        Some(fields) => fields,
+        None => {
+            // Raise a condition value bound to a variable again
+            if list.len() == 2 {
+                if let condition @ Expr::Condition(_, _) = evaluate_expr(&list[1], env, depth + 1)?
+                {
+                    return Err(EvalError::Signal(condition));
+                }
+            }
+
+            return Err(EvalError::IllegalArgument(
+                "signal",
+                "Condition type is not defined",
+            ));
+        }
+    };
+
+    if list.len() - 2 != fields.len() {
+        return Err(EvalError::ArgumentCount(name.clone(), fields.len()));
+    }
+
+    let values = list[2..]
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Err(EvalError::Signal(Expr::Condition(
+        name.clone(),
+        fields.into_iter().zip(values).collect(),
+    )))
+}
+
+/// Evaluates `handler-case` built-in.
+///
+/// When evaluating the expression fails, the first clause handling the type of
+/// the condition (or any of its parents) is evaluated with the condition bound.
+/// Unhandled conditions are propagated further.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (handler-case (read-config)
+///     (file-error (e) (condition-slot e path))
+///     (error () 0)
+/// )
+/// ```
+pub fn evaluate_handler_case(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 2 {
+        return Err(EvalError::ArgumentCount("handler-case".to_string(), 1));
+    }
+
+    let err = match evaluate_expr(&list[1], env, depth + 1) {
+        Ok(result) => return Ok(result),
+        // Leaving a block, interrupting the evaluation or running out of fuel cannot be handled
+        Err(
+            err @ (EvalError::ReturnFrom(_, _) | EvalError::Interrupted | EvalError::FuelExhausted),
+        ) => return Err(err),
+        Err(err) => err,
+    };
+
+    forget_error_span(env);
+
+    let condition = to_condition(&err);
+
+    let condition_name = match &condition {
+        Expr::Condition(name, _) => name.clone(),
+        _ => return Err(EvalError::Unreachable),
+    };
+
+    for clause in &list[2..] {
+        let (handled, variable, body) = match clause {
+            Expr::List(clause) => match clause.as_slice() {
+                [handled, Expr::List(variable), body @ ..]
+                    if type_name(handled).is_some() && variable.len() <= 1 =>
+                {
+                    (type_name(handled).unwrap(), variable.first(), body)
+                }
+                _ => {
+                    return Err(EvalError::IllegalArgument(
+                        "handler-case",
+                        "Clauses must have a type, a list with the bound variable and a body",
+                    ))
+                }
+            },
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "handler-case",
+                    "Clauses must be lists",
+                ))
+            }
+        };
+
+        if !is_subtype(&condition_name, handled, env) {
+            continue;
+        }
+
+        let mut extended_env = Scope::extend(env.clone());
+
+        match variable {
+            Some(Expr::Symbol(variable)) => extended_env
+                .borrow_mut()
+                .set(variable.clone(), condition.clone()),
+            Some(_) => {
+                return Err(EvalError::IllegalArgument(
+                    "handler-case",
+                    "Bound variable must be a symbol",
+                ))
+            }
+            None => {}
+        }
+
+        let mut result = Expr::Nil;
+
+        for expr in body {
+            result = evaluate_expr(expr, &mut extended_env, depth + 1)?;
+        }
+
+        return Ok(result);
+    }
+
+    Err(err)
+}
+
+/// Evaluates `try` built-in, a simpler form of `handler-case` catching any
+/// error.
+///
+/// The variable of the `catch` clause is bound to the value passed to `throw`,
+/// or to the condition for other errors.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (try (throw 42) (catch e (+ e 1)))
+/// (try (error "Failed") (catch e (condition-slot e message)))
+/// ```
+pub fn evaluate_try(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("try".to_string(), 2));
+    }
+
+    let (variable, body) = match &list[2] {
+        Expr::List(clause) => match clause.as_slice() {
+            [Expr::Symbol(catch), Expr::Symbol(variable), body @ ..] if catch == "catch" => {
+                (variable, body)
+            }
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "try",
+                    "Expected a catch clause with a variable and a body",
+                ))
+            }
+        },
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "try",
+                "Expected a catch clause with a variable and a body",
+            ))
+        }
+    };
+
+    let err = match evaluate_expr(&list[1], env, depth + 1) {
+        Ok(result) => return Ok(result),
+        // Leaving a block, interrupting the evaluation or running out of fuel cannot be handled
+        Err(
+            err @ (EvalError::ReturnFrom(_, _) | EvalError::Interrupted | EvalError::FuelExhausted),
+        ) => return Err(err),
+        Err(err) => err,
+    };
+
+    forget_error_span(env);
+
+    let caught = match to_condition(&err) {
+        Expr::Condition(name, mut fields) if name == THROWN_CONDITION => {
+            fields.pop().map(|(_, value)| value).unwrap_or(Expr::Nil)
+        }
+        condition => condition,
+    };
+
+    let mut extended_env = Scope::extend(env.clone());
+    extended_env.borrow_mut().set(variable.clone(), caught);
+
+    let mut result = Expr::Nil;
+
+    for expr in body {
+        result = evaluate_expr(expr, &mut extended_env, depth + 1)?;
+    }
+
+    Ok(result)
+}
+
+/// Evaluates `throw` built-in, raising any value to be caught by `try`.
+///
+/// Conditions are raised as they are, other values are wrapped in a `thrown`
+/// condition with a `value` field.
+///
+/// Expected Lisper syntax:
+///
+/// ```(throw 'not-found)```
+pub fn evaluate_throw(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("throw".to_string(), 1));
+    }
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        condition @ Expr::Condition(_, _) => Err(EvalError::Signal(condition)),
+        value => Err(EvalError::Signal(Expr::Condition(
+            THROWN_CONDITION.to_string(),
+            vec![("value".to_string(), value)],
+        ))),
+    }
+}
+
+/// Evaluates `error` built-in, raising a `simple-error` condition with a message.
+///
+/// Expected Lisper syntax:
+///
+/// ```(error "File not found")```
+pub fn evaluate_error(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("error".to_string(), 1));
+    }
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        message @ Expr::String(_) => Err(EvalError::Signal(Expr::Condition(
+            "simple-error".to_string(),
+            vec![("message".to_string(), message)],
+        ))),
+        _ => Err(EvalError::IllegalArgument(
+            "error",
+            "Message must be a string",
+        )),
+    }
+}
+
+/// Evaluates `assert` built-in, raising an `assertion-failed` condition when
+/// the condition does not hold. The message contains the condition as written
+/// in the code, followed by the optional message.
+///
+/// Expected Lisper syntax:
+///
+/// ```(assert (> (len xs) 0) "List must not be empty")```
+pub fn evaluate_assert(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 && list.len() != 3 {
+        return Err(EvalError::ArgumentCount("assert".to_string(), 1));
+    }
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::Boolean(true) => return Ok(Expr::Nil),
+        Expr::Boolean(false) => {}
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "assert",
+                "Condition must evaluate to bool",
+            ))
+        }
+    }
+
+    let mut message = format!("Assertion failed: {}", list[1]);
+
+    if let Some(expr) = list.get(2) {
+        match evaluate_expr(expr, env, depth + 1)? {
+            Expr::String(text) => message = format!("{message}, {text}"),
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "assert",
+                    "Message must be a string",
+                ))
+            }
+        }
+    }
+
+    Err(EvalError::Signal(Expr::Condition(
+        "assertion-failed".to_string(),
+        vec![("message".to_string(), Expr::String(message))],
+    )))
+}
+
+/// Evaluates `condition-slot` built-in, reading a field of a condition.
+///
+/// Expected Lisper syntax:
+///
+/// ```(condition-slot e path)```
+pub fn evaluate_condition_slot(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("condition-slot".to_string(), 2));
+    }
+
+    let field = match &list[2] {
+        Expr::Symbol(field) => field,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "condition-slot",
+                "Field name must be a symbol",
+            ))
+        }
+    };
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::Condition(_, fields) => fields
+            .into_iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+            .ok_or(EvalError::IllegalArgument(
+                "condition-slot",
+                "Condition has no such field",
+            )),
+        _ => Err(EvalError::IllegalArgument(
+            "condition-slot",
+            "First argument must be a condition",
+        )),
+    }
+}
+
+/// Evaluates `condition-type` built-in, returning the type name of a condition.
+///
+/// Expected Lisper syntax:
+///
+/// ```(condition-type e)```
+pub fn evaluate_condition_type(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("condition-type".to_string(), 1));
+    }
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::Condition(name, _) => Ok(Expr::Symbol(name)),
+        _ => Err(EvalError::IllegalArgument(
+            "condition-type",
+            "Argument must be a condition",
+        )),
+    }
+}
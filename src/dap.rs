@@ -0,0 +1,507 @@
+//! Debug adapter speaking the Debug Adapter Protocol over stdio
+//!
+//! Expressions carry no source positions, so breakpoints are set on functions
+//! (`setFunctionBreakpoints`) and line breakpoints are reported as unverified.
+//! Stepping works on the level of evaluated lists.
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::Rc,
+    sync::mpsc::{channel, Receiver, TryRecvError},
+};
+
+use crate::{
+    debugger::Debugger,
+    eval::{evaluate_source, EvalError, LocatedError},
+    expr::Expr,
+    json::Json,
+    lexer::lex,
+    lsp::{read_message, write_message},
+    parser::parse_program_with_spans,
+    scope::{Output, PassableScope},
+};
+
+/// The only thread reported to the client
+const THREAD_ID: i64 = 1;
+
+/// When evaluation should stop next
+#[derive(Debug, Clone, Copy)]
+enum Stop {
+    /// At the next evaluated list, with the reason reported to the client
+    Next(&'static str),
+    /// At the next list evaluated with at most this many frames on the stack
+    Over(usize),
+    /// At the next list evaluated with less than this many frames on the stack
+    Out(usize),
+}
+
+/// How evaluation continues after handling a message
+enum Control {
+    Stay,
+    Resume,
+    Terminate,
+}
+
+/// A function call on the stack
+#[derive(Debug)]
+struct Frame {
+    name: String,
+    env: PassableScope,
+}
+
+/// Program to debug, set by the `launch` request
+#[derive(Debug, Default)]
+struct Launch {
+    program: Option<String>,
+    stop_on_entry: bool,
+}
+
+#[derive(Debug)]
+struct DapDebugger {
+    /// Messages from the client, read by a separate thread
+    messages: Receiver<Json>,
+    /// Sequence number of the last message sent
+    seq: i64,
+    function_breakpoints: HashSet<String>,
+    frames: Vec<Frame>,
+    stop: Option<Stop>,
+    launch: Launch,
+}
+
+impl DapDebugger {
+    fn send(&mut self, mut message: Vec<(String, Json)>) {
+        self.seq += 1;
+        message.insert(0, ("seq".to_string(), self.seq.into()));
+
+        // A client that went away is noticed when no more messages arrive
+        let _ = write_message(&mut std::io::stdout().lock(), Json::Object(message));
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.send(vec![
+            ("type".to_string(), "event".into()),
+            ("event".to_string(), event.into()),
+            ("body".to_string(), body),
+        ]);
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) {
+        self.send(vec![
+            ("type".to_string(), "response".into()),
+            (
+                "request_seq".to_string(),
+                request.get("seq").cloned().unwrap_or(Json::Null),
+            ),
+            ("success".to_string(), true.into()),
+            (
+                "command".to_string(),
+                request.get("command").cloned().unwrap_or(Json::Null),
+            ),
+            ("body".to_string(), body),
+        ]);
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) {
+        self.send(vec![
+            ("type".to_string(), "response".into()),
+            (
+                "request_seq".to_string(),
+                request.get("seq").cloned().unwrap_or(Json::Null),
+            ),
+            ("success".to_string(), false.into()),
+            (
+                "command".to_string(),
+                request.get("command").cloned().unwrap_or(Json::Null),
+            ),
+            ("message".to_string(), message.into()),
+        ]);
+    }
+
+    fn output(&mut self, category: &str, text: String) {
+        if !text.is_empty() {
+            self.event(
+                "output",
+                Json::object([("category", category.into()), ("output", text.into())]),
+            );
+        }
+    }
+
+    /// Sends the output printed by the program so far
+    fn flush_output(&mut self, env: &PassableScope) {
+        let text = env.borrow().globals().borrow_mut().take_output();
+
+        self.output("stdout", text);
+    }
+
+    /// Handles a request of the client
+    fn handle(&mut self, request: &Json, paused: bool) -> Control {
+        let command = request.get("command").and_then(Json::as_str).unwrap_or("");
+        let arguments = request.get("arguments").cloned().unwrap_or(Json::Null);
+
+        match command {
+            "initialize" => {
+                self.respond(
+                    request,
+                    Json::object([
+                        ("supportsConfigurationDoneRequest", true.into()),
+                        ("supportsFunctionBreakpoints", true.into()),
+                    ]),
+                );
+                self.event("initialized", Json::object([]));
+            }
+            "launch" => {
+                self.launch = Launch {
+                    program: arguments
+                        .get("program")
+                        .and_then(Json::as_str)
+                        .map(str::to_string),
+                    stop_on_entry: arguments.get("stopOnEntry") == Some(&Json::Bool(true)),
+                };
+                self.respond(request, Json::object([]));
+            }
+            "setBreakpoints" => {
+                let count = arguments
+                    .get("breakpoints")
+                    .and_then(Json::as_array)
+                    .map_or(0, |breakpoints| breakpoints.len());
+
+                let breakpoints = (0..count)
+                    .map(|_| {
+                        Json::object([
+                            ("verified", false.into()),
+                            ("message", "Only function breakpoints are supported".into()),
+                        ])
+                    })
+                    .collect();
+
+                self.respond(
+                    request,
+                    Json::object([("breakpoints", Json::Array(breakpoints))]),
+                );
+            }
+            "setFunctionBreakpoints" => {
+                self.function_breakpoints = arguments
+                    .get("breakpoints")
+                    .and_then(Json::as_array)
+                    .unwrap_or(&[])
+                    .iter()
+                    .filter_map(|breakpoint| breakpoint.get("name").and_then(Json::as_str))
+                    .map(str::to_string)
+                    .collect();
+
+                let breakpoints = self
+                    .function_breakpoints
+                    .iter()
+                    .map(|_| Json::object([("verified", true.into())]))
+                    .collect();
+
+                self.respond(
+                    request,
+                    Json::object([("breakpoints", Json::Array(breakpoints))]),
+                );
+            }
+            "setExceptionBreakpoints" | "configurationDone" => {
+                self.respond(request, Json::object([]));
+            }
+            "threads" => {
+                let thread = Json::object([("id", THREAD_ID.into()), ("name", "main".into())]);
+
+                self.respond(
+                    request,
+                    Json::object([("threads", Json::Array(vec![thread]))]),
+                );
+            }
+            "stackTrace" => {
+                let frames: Vec<Json> = self
+                    .frames
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(id, frame)| {
+                        Json::object([
+                            ("id", (id as i64).into()),
+                            ("name", frame.name.as_str().into()),
+                            ("line", 0.into()),
+                            ("column", 0.into()),
+                        ])
+                    })
+                    .collect();
+
+                let total = frames.len() as i64;
+
+                self.respond(
+                    request,
+                    Json::object([
+                        ("stackFrames", Json::Array(frames)),
+                        ("totalFrames", total.into()),
+                    ]),
+                );
+            }
+            "scopes" => {
+                // Variables of a frame are referenced by its id shifted by one, zero means none
+                let frame = arguments.get("frameId").and_then(Json::as_i64).unwrap_or(0);
+
+                let scope = Json::object([
+                    ("name", "Locals".into()),
+                    ("variablesReference", (frame + 1).into()),
+                    ("expensive", false.into()),
+                ]);
+
+                self.respond(
+                    request,
+                    Json::object([("scopes", Json::Array(vec![scope]))]),
+                );
+            }
+            "variables" => {
+                let reference = arguments
+                    .get("variablesReference")
+                    .and_then(Json::as_i64)
+                    .unwrap_or(0);
+
+                let variables = match self.frames.get((reference - 1) as usize) {
+                    Some(frame) => frame
+                        .env
+                        .borrow()
+                        .entries()
+                        .into_iter()
+                        .map(|(name, value)| {
+                            Json::object([
+                                ("name", name.into()),
+                                ("value", value.to_string().into()),
+                                ("variablesReference", 0.into()),
+                            ])
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+
+                self.respond(
+                    request,
+                    Json::object([("variables", Json::Array(variables))]),
+                );
+            }
+            "pause" => {
+                if !paused {
+                    self.stop = Some(Stop::Next("pause"));
+                }
+
+                self.respond(request, Json::object([]));
+            }
+            "continue" | "next" | "stepIn" | "stepOut" if paused => {
+                self.stop = match command {
+                    "next" => Some(Stop::Over(self.frames.len())),
+                    "stepIn" => Some(Stop::Next("step")),
+                    "stepOut" => Some(Stop::Out(self.frames.len())),
+                    _ => None,
+                };
+
+                self.respond(request, Json::object([]));
+
+                return Control::Resume;
+            }
+            "continue" | "next" | "stepIn" | "stepOut" => {
+                self.fail(request, "The program is not paused");
+            }
+            "disconnect" | "terminate" => {
+                self.respond(request, Json::object([]));
+
+                return Control::Terminate;
+            }
+            _ => self.fail(request, "Unsupported request"),
+        }
+
+        Control::Stay
+    }
+
+    /// Pauses the evaluation until the client resumes it
+    fn pause(&mut self, reason: &str, env: &PassableScope) -> Result<(), EvalError> {
+        self.flush_output(env);
+        self.event(
+            "stopped",
+            Json::object([("reason", reason.into()), ("threadId", THREAD_ID.into())]),
+        );
+
+        loop {
+            let request = match self.messages.recv() {
+                Ok(request) => request,
+                Err(_) => return Err(EvalError::Interrupted),
+            };
+
+            match self.handle(&request, true) {
+                Control::Stay => {}
+                Control::Resume => return Ok(()),
+                Control::Terminate => return Err(EvalError::Interrupted),
+            }
+        }
+    }
+}
+
+impl Debugger for DapDebugger {
+    fn before_eval(&mut self, _expr: &Expr, env: &PassableScope) -> Result<(), EvalError> {
+        // Answer requests that arrived while running
+        loop {
+            match self.messages.try_recv() {
+                Ok(request) => {
+                    if let Control::Terminate = self.handle(&request, false) {
+                        return Err(EvalError::Interrupted);
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Err(EvalError::Interrupted),
+            }
+        }
+
+        let depth = self.frames.len();
+
+        let reason = match self.stop {
+            Some(Stop::Next(reason)) => reason,
+            Some(Stop::Over(frames)) if depth <= frames => "step",
+            Some(Stop::Out(frames)) if depth < frames => "step",
+            _ => return Ok(()),
+        };
+
+        self.stop = None;
+        self.pause(reason, env)
+    }
+
+    fn enter_function(&mut self, name: &str, env: &PassableScope) {
+        self.frames.push(Frame {
+            name: name.to_string(),
+            env: env.clone(),
+        });
+
+        if self.function_breakpoints.contains(name) {
+            self.stop = Some(Stop::Next("function breakpoint"));
+        }
+    }
+
+    fn exit_function(&mut self) {
+        self.frames.pop();
+    }
+}
+
+/// Runs the debug adapter until the client disconnects
+pub fn run_dap(mut env: PassableScope) -> std::io::Result<()> {
+    let (sender, messages) = channel();
+
+    // Reads messages while the program runs, so it can be paused
+    std::thread::spawn(move || {
+        let mut input = std::io::stdin().lock();
+
+        while let Ok(Some(message)) = read_message(&mut input) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let debugger = Rc::new(RefCell::new(DapDebugger {
+        messages,
+        seq: 0,
+        function_breakpoints: HashSet::new(),
+        frames: Vec::new(),
+        stop: None,
+        launch: Launch::default(),
+    }));
+
+    // Configure the debugger until the program may be started
+    loop {
+        let request = match debugger.borrow().messages.recv() {
+            Ok(request) => request,
+            Err(_) => return Ok(()),
+        };
+
+        let control = debugger.borrow_mut().handle(&request, false);
+
+        match control {
+            Control::Terminate => return Ok(()),
+            _ if request.get("command") == Some(&"configurationDone".into()) => break,
+            _ => {}
+        }
+    }
+
+    let program = debugger.borrow().launch.program.clone();
+    let content = program
+        .as_ref()
+        .map(|program| (program, std::fs::read_to_string(program)));
+
+    let exit_code = match content {
+        None => {
+            let text = "No program to debug, `program` is missing in `launch`\n";
+            debugger.borrow_mut().output("stderr", text.to_string());
+
+            1
+        }
+        Some((_, Err(err))) => {
+            debugger
+                .borrow_mut()
+                .output("stderr", format!("READ FILE ERROR: {err}\n"));
+
+            1
+        }
+        Some((program, Ok(content))) => {
+            let globals = env.borrow().globals();
+            globals.borrow_mut().current_file = std::fs::canonicalize(program).ok();
+            globals.borrow_mut().output = Output::Captured(String::new());
+            globals.borrow_mut().debugger = Some(debugger.clone());
+
+            {
+                let mut debugger = debugger.borrow_mut();
+
+                debugger.frames.push(Frame {
+                    name: "<program>".to_string(),
+                    env: env.clone(),
+                });
+
+                if debugger.launch.stop_on_entry {
+                    debugger.stop = Some(Stop::Next("entry"));
+                }
+            }
+
+            let result = match lex(&content) {
+                Err(lexer_error) => Err(format!("LEXER ERROR: {lexer_error}\n")),
+                Ok(tokens) => match parse_program_with_spans(&mut tokens.into_iter().peekable()) {
+                    Err(parser_error) => Err(format!("PARSER ERROR: {parser_error}\n")),
+                    Ok((program, source_map)) => {
+                        match evaluate_source(&program, source_map, &mut env) {
+                            Ok(_)
+                            | Err(LocatedError {
+                                error: EvalError::Interrupted,
+                                ..
+                            }) => Ok(()),
+                            Err(err) => Err(format!("EVAL ERROR: {err}\n")),
+                        }
+                    }
+                },
+            };
+
+            globals.borrow_mut().debugger = None;
+
+            let mut debugger = debugger.borrow_mut();
+            debugger.flush_output(&env);
+
+            match result {
+                Ok(()) => 0,
+                Err(err) => {
+                    debugger.output("stderr", err);
+
+                    1
+                }
+            }
+        }
+    };
+
+    let mut debugger = debugger.borrow_mut();
+    debugger.event("exited", Json::object([("exitCode", exit_code.into())]));
+    debugger.event("terminated", Json::object([]));
+
+    // Answer the remaining requests until the client disconnects
+    while let Ok(request) = debugger.messages.recv() {
+        if let Control::Terminate = debugger.handle(&request, false) {
+            break;
+        }
+    }
+
+    Ok(())
+}
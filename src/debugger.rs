@@ -0,0 +1,45 @@
+//! Hooks for observing and pausing evaluation, used by the debug adapter
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{eval::EvalError, expr::Expr, scope::PassableScope};
+
+/// Receives events of the evaluation when attached to the interpreter
+pub trait Debugger: std::fmt::Debug {
+    /// Called before every list is evaluated. Returning an error stops the evaluation.
+    fn before_eval(&mut self, expr: &Expr, env: &PassableScope) -> Result<(), EvalError>;
+
+    /// Called when a function is called, with the scope of its body
+    fn enter_function(&mut self, name: &str, env: &PassableScope);
+
+    /// Called when a function returns
+    fn exit_function(&mut self);
+}
+
+pub type PassableDebugger = Rc<RefCell<dyn Debugger>>;
+
+/// Returns the debugger attached to the interpreter, if any
+pub fn attached_debugger(env: &PassableScope) -> Option<PassableDebugger> {
+    env.borrow().globals().borrow().debugger.clone()
+}
+
+/// Evaluates the body of a function, notifying the attached debugger
+pub fn in_function<T>(
+    name: &str,
+    env: &mut PassableScope,
+    body: impl FnOnce(&mut PassableScope) -> T,
+) -> T {
+    let debugger = attached_debugger(env);
+
+    if let Some(debugger) = &debugger {
+        debugger.borrow_mut().enter_function(name, env);
+    }
+
+    let result = body(env);
+
+    if let Some(debugger) = &debugger {
+        debugger.borrow_mut().exit_function();
+    }
+
+    result
+}
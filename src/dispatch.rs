@@ -0,0 +1,99 @@
+//! `case` form dispatching on the value of an expression
+
+use crate::{
+    bigint::BigInt,
+    eval::{evaluate_body, evaluate_expr, EvalError, Step},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+/// Key of a `case` clause, only atoms that can be compared exactly are allowed
+#[derive(Debug, PartialEq, Eq)]
+enum CaseKey<'a> {
+    Integer(i64),
+    BigInt(&'a BigInt),
+    Symbol(&'a str),
+    String(&'a str),
+    Boolean(bool),
+    Nil,
+}
+
+impl CaseKey<'_> {
+    fn from_expr(expr: &Expr) -> Option<CaseKey<'_>> {
+        match expr {
+            Expr::Integer(value) => Some(CaseKey::Integer(*value)),
+            Expr::BigInt(value) => Some(CaseKey::BigInt(value)),
+            Expr::Symbol(value) => Some(CaseKey::Symbol(value)),
+            Expr::String(value) => Some(CaseKey::String(value)),
+            Expr::Boolean(value) => Some(CaseKey::Boolean(*value)),
+            Expr::Nil => Some(CaseKey::Nil),
+            _ => None,
+        }
+    }
+}
+
+/// Evaluates `case` built-in, evaluating the body of the clause whose key is
+/// the value of the expression. Keys are not evaluated, a clause can list
+/// several of them. The `else` clause is used when no key matches, `nil`
+/// is returned if there is none.
+///
+/// Clauses are tried in order, the first one with a matching key wins.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (case (car command)
+///     (start (println "Starting"))
+///     ((stop quit) (println "Stopping"))
+///     (else (println "Unknown command"))
+/// )
+/// ```
+pub(crate) fn evaluate_case<'a>(
+    list: &'a [Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Step<'a>, EvalError> {
+    // Check argument count
+    if list.len() < 2 {
+        return Err(EvalError::ArgumentCount("case".to_string(), 1));
+    }
+
+    let value = evaluate_expr(&list[1], env, depth + 1)?;
+    let value = CaseKey::from_expr(&value);
+
+    let mut default = None;
+
+    for clause in &list[2..] {
+        let (keys, body) = match clause {
+            Expr::List(clause) if !clause.is_empty() => (&clause[0], &clause[1..]),
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "case",
+                    "Clauses must be lists starting with their keys",
+                ))
+            }
+        };
+
+        let keys = match keys {
+            Expr::Symbol(name) if name == "else" => {
+                default.get_or_insert(body);
+                continue;
+            }
+            Expr::List(keys) => keys.as_slice(),
+            key => std::slice::from_ref(key),
+        };
+
+        for key in keys {
+            let key = CaseKey::from_expr(key).ok_or(EvalError::IllegalArgument(
+                "case",
+                "Keys must be numbers, symbols, strings, booleans or nil",
+            ))?;
+
+            if value.as_ref() == Some(&key) {
+                return evaluate_body(body, env.clone(), depth);
+            }
+        }
+    }
+
+    evaluate_body(default.unwrap_or_default(), env.clone(), depth)
+}
@@ -1,12 +1,68 @@
 //! Evaluation logic
 
-use crate::comparison::compare_integers;
+use std::{borrow::Cow, cmp::Ordering};
+
+use crate::arithmetic::{
+    evaluate_abs, evaluate_arithmetic, evaluate_divisors, evaluate_exactness, evaluate_expt,
+    evaluate_extremum, evaluate_float_function, evaluate_remainder, evaluate_rounding, integer,
+};
+use crate::bigint::BigInt;
+use crate::block::{evaluate_block, evaluate_return_from};
+use crate::bytes::{
+    evaluate_base64_decode, evaluate_base64_encode, evaluate_bytes, evaluate_read_bytes,
+    evaluate_read_file, evaluate_write_bytes, evaluate_write_file,
+};
+use crate::comparison::{compare_numbers, is_equal, is_identical};
+use crate::condition::{
+    evaluate_assert, evaluate_condition_slot, evaluate_condition_type, evaluate_define_condition,
+    evaluate_error, evaluate_handler_case, evaluate_signal, evaluate_throw, evaluate_try,
+};
+use crate::debugger::{attached_debugger, in_function};
+use crate::dispatch::evaluate_case;
 use crate::expr::Expr;
+use crate::hash::evaluate_hash;
+use crate::iteration::{
+    evaluate_dolist, evaluate_dotimes, evaluate_filter, evaluate_fold, evaluate_for_list,
+    evaluate_iterate, evaluate_map, evaluate_quantifier, evaluate_range, evaluate_reduce,
+    evaluate_repeat, evaluate_sort, evaluate_take_while, evaluate_unfold,
+};
+use crate::json::{evaluate_json_parse, evaluate_json_stringify};
+use crate::list::{
+    evaluate_assoc, evaluate_car, evaluate_cdr, evaluate_concat, evaluate_cons, evaluate_contains,
+    evaluate_flatten, evaluate_len, evaluate_list, evaluate_member, evaluate_nth, evaluate_reverse,
+    evaluate_take, evaluate_zip,
+};
+use crate::module::evaluate_import;
+use crate::promise::{
+    evaluate_delay, evaluate_force, evaluate_stream_car, evaluate_stream_cdr, evaluate_stream_cons,
+    evaluate_stream_take,
+};
+use crate::quasiquote::evaluate_quasiquote;
+use crate::regex::{evaluate_re_find_all, evaluate_re_match, evaluate_re_replace};
 use crate::scope::*;
+use crate::span::{SourceMap, Span};
+use crate::string::{
+    display, evaluate_format, evaluate_number_to_string, evaluate_pad, evaluate_string_append,
+    evaluate_string_length, evaluate_string_ref, evaluate_string_to_number,
+    evaluate_string_transform, evaluate_substring,
+};
+use crate::system::{evaluate_argv, evaluate_exec, evaluate_getenv, evaluate_setenv};
+use crate::time::{evaluate_format_time, evaluate_now, evaluate_parse_time};
 
 /// Defines the maximum recursion depth, meaning how many times can the `evaluate_expr` method can be called recursively.
 pub static MAX_RECURSION_DEPTH: usize = 1024;
 
+/// Decides which values count as true in conditional forms (`if`, `and`, `or`)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Truthiness {
+    /// Only booleans are accepted as conditions
+    #[default]
+    Strict,
+
+    /// `false`, the empty list and `nil` are false, everything else is true
+    Lenient,
+}
+
 /// When an error occurs during evaluation, `EvalError` is returned
 #[derive(Debug, Clone)]
 pub enum EvalError {
@@ -19,15 +75,46 @@ pub enum EvalError {
     /// An invalid argument count has been provided to a (built-in) function
     ArgumentCount(String, usize),
 
+    /// An argument count outside of the accepted range, with the smallest and
+    /// the largest accepted count, has been provided to a built-in
+    ArgumentCountRange(String, usize, usize),
+
     /// The type of arguments passed to a function is not supported
     IllegalArgument(&'static str, &'static str),
 
+    /// A number has been divided by zero in the operation
+    DivisionByZero(&'static str),
+
+    /// An index outside of a list has been accessed, with the index and the length of the list
+    IndexOutOfRange(&'static str, i64, usize),
+
     /// Can occur when the interpreter tries to call a function that is lexed as a built-in but hasn't been implemented yet
     Unimplemented,
 
     /// Execution was stopped due to reaching the recursion limit
     MaximumRecursionDepthReached(usize),
 
+    /// Occurs when a constant is being redefined
+    ConstantReassignment(String),
+
+    /// A module could not be found, read or parsed
+    ImportFailed(String, String),
+
+    /// A file could not be read or written
+    IoFailed(String, String),
+
+    /// A condition has been signalled and not handled
+    Signal(Expr),
+
+    /// A block is being left early by `return-from`, with the returned value
+    ReturnFrom(String, Expr),
+
+    /// Evaluation was stopped from the outside, eg. by the debugger
+    Interrupted,
+
+    /// Evaluation was stopped after running out of its step budget
+    FuelExhausted,
+
     /// Internal error that should never occur
     Unreachable,
 
@@ -45,24 +132,141 @@ impl std::fmt::Display for EvalError {
                 "Invalid argument count for {}, {} needed",
                 name, arg_count
             ),
+            EvalError::ArgumentCountRange(name, min, max) if max - min == 1 => write!(
+                f,
+                "Invalid argument count for {}, {} or {} needed",
+                name, min, max
+            ),
+            EvalError::ArgumentCountRange(name, min, max) => write!(
+                f,
+                "Invalid argument count for {}, {} to {} needed",
+                name, min, max
+            ),
             EvalError::Unreachable => write!(f, "Internal error (Unreachable)"),
             EvalError::Unimplemented => write!(f, "Internal error (Unimplemented)"),
             EvalError::MaximumRecursionDepthReached(max) => {
                 write!(f, "Maximum recursion depth ({}) exceeded", max)
             }
             EvalError::IllegalArgument(name, msg) => write!(f, "Illegal argument in {name}: {msg}"),
+            EvalError::DivisionByZero(name) => write!(f, "Division by zero in {name}"),
+            EvalError::IndexOutOfRange(name, index, length) => {
+                write!(
+                    f,
+                    "Index {index} out of range in {name}, length is {length}"
+                )
+            }
+            EvalError::ConstantReassignment(name) => {
+                write!(f, "Cannot redefine constant: {}", name)
+            }
+            EvalError::ImportFailed(module, reason) => {
+                write!(f, "Cannot import {module}: {reason}")
+            }
+            EvalError::IoFailed(path, reason) => write!(f, "Cannot access {path}: {reason}"),
+            EvalError::Signal(condition) => write!(f, "Unhandled condition {condition}"),
+            EvalError::ReturnFrom(name, _) => {
+                write!(f, "Cannot return from {name}, no such block is evaluated")
+            }
+            EvalError::Interrupted => write!(f, "Evaluation interrupted"),
+            EvalError::FuelExhausted => write!(f, "Evaluation ran out of fuel"),
             EvalError::Internal => write!(f, "Internal error"),
         }
     }
 }
 
+/// Error raised by a program, with the position of the innermost expression
+/// of its source code it has been raised in
+#[derive(Debug, Clone)]
+pub struct LocatedError {
+    pub error: EvalError,
+    pub span: Option<Span>,
+}
+
+impl std::fmt::Display for LocatedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {span}", self.error),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
 /// Top level function for starting the interpreter from other modules
 pub fn evaluate(expr: &Expr, env: &mut PassableScope) -> Result<Expr, EvalError> {
     evaluate_expr(expr, env, 0)
 }
 
+/// Evaluates the top-level expressions of a program in order, returning the
+/// value of the last one
+pub fn evaluate_program(program: &[Expr], env: &mut PassableScope) -> Result<Expr, EvalError> {
+    let mut value = Expr::Nil;
+
+    for expr in program {
+        value = evaluate(expr, env)?;
+    }
+
+    Ok(value)
+}
+
+/// Evaluates the top-level expressions of a program parsed by
+/// `parse_program_with_spans`, locating errors in its source code
+pub fn evaluate_source(
+    program: &[Expr],
+    source_map: SourceMap,
+    env: &mut PassableScope,
+) -> Result<Expr, LocatedError> {
+    let globals = env.borrow().globals();
+
+    let enclosing = std::mem::replace(&mut globals.borrow_mut().source_map, source_map);
+    globals.borrow_mut().error_span = None;
+
+    let result = evaluate_program(program, env);
+
+    let mut globals = globals.borrow_mut();
+    globals.source_map = enclosing;
+    let span = globals.error_span.take();
+
+    result.map_err(|error| LocatedError { error, span })
+}
+
+/// Result of evaluating an expression, possibly with its tail left to evaluate
+pub(crate) enum Step<'a> {
+    /// The expression has been fully evaluated
+    Value(Expr),
+
+    /// The element of the evaluated list at the index is evaluated in its place
+    Element(usize),
+
+    /// An expression inside of the evaluated one is evaluated in its place, in the scope
+    Nested(Cow<'a, Expr>, PassableScope),
+
+    /// The last expression of a block is evaluated in its place, `return-from`
+    /// with the name of the block still leaves it
+    Block(String, Cow<'a, Expr>),
+
+    /// The body of a called function is evaluated in its place, in the scope of the call
+    Call(Vec<Expr>, PassableScope),
+}
+
+impl Step<'_> {
+    /// Copies the expression left to evaluate, so that it does not borrow the
+    /// evaluated one
+    fn into_owned(self) -> Step<'static> {
+        match self {
+            Step::Value(value) => Step::Value(value),
+            Step::Element(index) => Step::Element(index),
+            Step::Nested(expr, scope) => Step::Nested(Cow::Owned(expr.into_owned()), scope),
+            Step::Block(name, expr) => Step::Block(name, Cow::Owned(expr.into_owned())),
+            Step::Call(body, scope) => Step::Call(body, scope),
+        }
+    }
+}
+
 /// Top level function for recursive evaluation of the provided expression
-fn evaluate_expr(expr: &Expr, env: &mut PassableScope, depth: usize) -> Result<Expr, EvalError> {
+pub fn evaluate_expr(
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
     // Barebones recursion depth checking, only checks "stupid" recursion like
     // ```
     // fn a() {
@@ -73,30 +277,238 @@ fn evaluate_expr(expr: &Expr, env: &mut PassableScope, depth: usize) -> Result<E
         return Err(EvalError::MaximumRecursionDepthReached(MAX_RECURSION_DEPTH));
     }
 
-    match expr {
+    // Expressions in tail position replace the current one instead of being
+    // evaluated recursively, so that tail calls do not increase the depth
+    let source = expr;
+    let mut expr = Cow::Borrowed(expr);
+    let mut scope = env.clone();
+    // Blocks whose last expression is being evaluated
+    let mut blocks: Vec<String> = Vec::new();
+
+    loop {
+        consume_fuel(&scope, 1)?;
+
+        if let (Expr::List(_), Some(debugger)) = (&*expr, attached_debugger(&scope)) {
+            debugger.borrow_mut().before_eval(&expr, &scope)?;
+        }
+
+        let step = match &expr {
+            Cow::Borrowed(expr) => evaluate_step(expr, &mut scope, depth),
+            Cow::Owned(expr) => evaluate_step(expr, &mut scope, depth).map(Step::into_owned),
+        };
+
+        let step = match step {
+            Ok(step) => step,
+            Err(EvalError::ReturnFrom(block, value)) if blocks.contains(&block) => {
+                forget_error_span(&scope);
+
+                return Ok(value);
+            }
+            Err(err) => {
+                // Functions evaluate copies of their bodies, which are not
+                // part of the program, so the call is located instead
+                record_error_span(&expr, &scope);
+                record_error_span(source, &scope);
+
+                return Err(err);
+            }
+        };
+
+        match step {
+            Step::Value(value) => return Ok(value),
+            Step::Element(index) => {
+                expr = match expr {
+                    Cow::Borrowed(Expr::List(list)) => Cow::Borrowed(&list[index]),
+                    Cow::Owned(Expr::List(mut list)) => Cow::Owned(list.swap_remove(index)),
+                    _ => return Err(EvalError::Unreachable),
+                }
+            }
+            Step::Nested(nested, nested_env) => {
+                expr = nested;
+                scope = nested_env;
+            }
+            Step::Block(name, last) => {
+                if !blocks.contains(&name) {
+                    blocks.push(name);
+                }
+
+                expr = last;
+            }
+            Step::Call(body, function_env) => {
+                expr = Cow::Owned(Expr::List(body));
+                scope = function_env;
+            }
+        }
+    }
+}
+
+/// Remembers the position of the expression an error has been raised in,
+/// unless the error has already been located in an inner expression
+#[inline(never)]
+fn record_error_span(expr: &Expr, env: &PassableScope) {
+    let globals = env.borrow().globals();
+    let mut globals = globals.borrow_mut();
+
+    if globals.error_span.is_none() {
+        globals.error_span = globals.source_map.get(expr);
+    }
+}
+
+/// Forgets the position of an error that has been handled
+pub fn forget_error_span(env: &PassableScope) {
+    env.borrow().globals().borrow_mut().error_span = None;
+}
+
+/// Evaluates the provided expression, leaving expressions in tail position
+/// to `evaluate_expr`
+fn evaluate_step<'a>(
+    expr: &'a Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Step<'a>, EvalError> {
+    let value = match expr {
         Expr::List(list) => match list.first() {
             Some(head_op) => match head_op {
-                Expr::Op(_) => evaluate_binary_op(list, env),
+                Expr::Op(op)
+                    if (op == "and" || op == "or") && truthiness(env) == Truthiness::Lenient =>
+                {
+                    return evaluate_lenient_logic(op, list, env, depth)
+                }
+                Expr::Op(_) => evaluate_binary_op(list, env, depth),
                 Expr::If => {
-                    if list.len() != 4 {
-                        return Err(EvalError::ArgumentCount("if".to_string(), 4));
+                    if list.len() != 3 && list.len() != 4 {
+                        return Err(EvalError::ArgumentCountRange("if".to_string(), 2, 3));
                     }
 
                     let condition = evaluate_expr(list.get(1).unwrap(), env, depth + 1)?;
 
-                    match condition {
-                        Expr::Boolean(true) => evaluate_expr(&list[2], env, depth + 1),
-                        Expr::Boolean(false) => evaluate_expr(&list[3], env, depth + 1),
-                        _ => Err(EvalError::IllegalArgument(
+                    match is_truthy(&condition, env) {
+                        Some(true) => return Ok(Step::Element(2)),
+                        Some(false) if list.len() == 4 => return Ok(Step::Element(3)),
+                        Some(false) => Ok(Expr::Nil),
+                        None => Err(EvalError::IllegalArgument(
                             "if",
                             "Condition must evaluate to bool",
                         )),
                     }
                 }
                 Expr::Keyword(keyword) => match keyword.as_str() {
-                    "def" => evaluate_def(list, env),
+                    "def" => evaluate_def(list, env, false, depth),
+                    "defconst" => evaluate_def(list, env, true, depth),
+                    "set!" => evaluate_set(list, env, depth),
                     "defun" => evaluate_defun(list, env),
-                    "print" => evaluate_print(list, env),
+                    "lambda" => evaluate_lambda(expr, env),
+                    "print" => evaluate_print("print", list, env, depth),
+                    "println" => evaluate_print("println", list, env, depth),
+                    "write" => evaluate_print("write", list, env, depth),
+                    "eprint" => evaluate_print("eprint", list, env, depth),
+                    "eprintln" => evaluate_print("eprintln", list, env, depth),
+                    "import" => evaluate_import(list, env),
+                    "gensym" => evaluate_gensym(list, env),
+                    "argv" => evaluate_argv(list, env),
+                    "getenv" => evaluate_getenv(list, env, depth),
+                    "setenv" => evaluate_setenv(list, env, depth),
+                    "exec" => evaluate_exec(list, env, depth),
+                    "json-parse" => evaluate_json_parse(list, env, depth),
+                    "json-stringify" => evaluate_json_stringify(list, env, depth),
+                    "base64-encode" => evaluate_base64_encode(list, env, depth),
+                    "base64-decode" => evaluate_base64_decode(list, env, depth),
+                    "sha256" => evaluate_hash("sha256", list, env, depth),
+                    "md5" => evaluate_hash("md5", list, env, depth),
+                    "now" => evaluate_now(list),
+                    "format-time" => evaluate_format_time(list, env, depth),
+                    "parse-time" => evaluate_parse_time(list, env, depth),
+                    "values" => evaluate_values(list, env, depth),
+                    "let" => return evaluate_let(list, env, false, depth),
+                    "let*" => return evaluate_let(list, env, true, depth),
+                    "let-values" => evaluate_let_values(list, env, depth),
+                    "divmod" => evaluate_divmod(list, env, depth),
+                    "define-condition" => evaluate_define_condition(list, env),
+                    "signal" => evaluate_signal(list, env, depth),
+                    "handler-case" => evaluate_handler_case(list, env, depth),
+                    "condition-slot" => evaluate_condition_slot(list, env, depth),
+                    "condition-type" => evaluate_condition_type(list, env, depth),
+                    "try" => evaluate_try(list, env, depth),
+                    "throw" => evaluate_throw(list, env, depth),
+                    "error" => evaluate_error(list, env, depth),
+                    "assert" => evaluate_assert(list, env, depth),
+                    "repeat" => evaluate_repeat(list, env, depth),
+                    "range" => evaluate_range(list, env, depth),
+                    "iterate" => evaluate_iterate(list, env, depth),
+                    "unfold" => evaluate_unfold(list, env, depth),
+                    "map" => evaluate_map(list, env, depth),
+                    "filter" => evaluate_filter(list, env, depth),
+                    "any?" => evaluate_quantifier("any?", list, env, depth),
+                    "all?" => evaluate_quantifier("all?", list, env, depth),
+                    "count-if" => evaluate_quantifier("count-if", list, env, depth),
+                    "take-while" => evaluate_take_while("take-while", list, env, depth),
+                    "drop-while" => evaluate_take_while("drop-while", list, env, depth),
+                    "reduce" => evaluate_reduce(list, env, depth),
+                    "fold-left" => evaluate_fold("fold-left", list, env, depth),
+                    "fold-right" => evaluate_fold("fold-right", list, env, depth),
+                    "sort" => evaluate_sort(list, env, depth),
+                    "for/list" => evaluate_for_list(list, env, depth),
+                    "dotimes" => evaluate_dotimes(list, env, depth),
+                    "dolist" => evaluate_dolist(list, env, depth),
+                    "nil?" => evaluate_is_nil(list, env, depth),
+                    "quote" => evaluate_quote(list),
+                    "quasiquote" => evaluate_quasiquote(list, env, depth),
+                    "eval" => evaluate_eval(list, env, depth),
+                    "delay" => evaluate_delay(list, env),
+                    "force" => evaluate_force(list, env, depth),
+                    "stream-cons" => evaluate_stream_cons(list, env, depth),
+                    "stream-car" => evaluate_stream_car(list, env, depth),
+                    "stream-cdr" => evaluate_stream_cdr(list, env, depth),
+                    "stream-take" => evaluate_stream_take(list, env, depth),
+                    "unquote" => Err(EvalError::IllegalArgument(
+                        "unquote",
+                        "Can only be used inside of quasiquote",
+                    )),
+                    "unquote-splicing" => Err(EvalError::IllegalArgument(
+                        "unquote-splicing",
+                        "Can only be used inside of quasiquote",
+                    )),
+                    "case" => return evaluate_case(list, env, depth),
+                    "block" => return evaluate_block(list, env, depth),
+                    "return-from" => evaluate_return_from(list, env, depth),
+                    "match" => return evaluate_match(list, env, depth),
+                    "bytes" => evaluate_bytes(list, env, depth),
+                    "read-bytes" => evaluate_read_bytes(list, env, depth),
+                    "read-file" => evaluate_read_file(list, env, depth),
+                    "write-file" => evaluate_write_file("write-file", list, env, depth),
+                    "append-file" => evaluate_write_file("append-file", list, env, depth),
+                    "write-bytes" => evaluate_write_bytes(list, env, depth),
+                    "list" => evaluate_list(list, env, depth),
+                    "cons" => evaluate_cons(list, env, depth),
+                    "car" => evaluate_car(list, env, depth),
+                    "cdr" => evaluate_cdr(list, env, depth),
+                    "len" => evaluate_len(list, env, depth),
+                    "string-append" => evaluate_string_append(list, env, depth),
+                    "string-length" => evaluate_string_length(list, env, depth),
+                    "substring" => evaluate_substring(list, env, depth),
+                    "string-ref" => evaluate_string_ref(list, env, depth),
+                    "trim" => evaluate_string_transform("trim", list, env, depth),
+                    "upcase" => evaluate_string_transform("upcase", list, env, depth),
+                    "downcase" => evaluate_string_transform("downcase", list, env, depth),
+                    "pad-left" => evaluate_pad("pad-left", list, env, depth),
+                    "pad-right" => evaluate_pad("pad-right", list, env, depth),
+                    "format" => evaluate_format(list, env, depth),
+                    "re-match" => evaluate_re_match(list, env, depth),
+                    "re-find-all" => evaluate_re_find_all(list, env, depth),
+                    "re-replace" => evaluate_re_replace(list, env, depth),
+                    "string->number" => evaluate_string_to_number(list, env, depth),
+                    "number->string" => evaluate_number_to_string(list, env, depth),
+                    "concat" => evaluate_concat(list, env, depth),
+                    "reverse" => evaluate_reverse(list, env, depth),
+                    "nth" => evaluate_nth(list, env, depth),
+                    "take" => evaluate_take("take", list, env, depth),
+                    "drop" => evaluate_take("drop", list, env, depth),
+                    "zip" => evaluate_zip(list, env, depth),
+                    "flatten" => evaluate_flatten(list, env, depth),
+                    "member" => evaluate_member(list, env, depth),
+                    "contains?" => evaluate_contains(list, env, depth),
+                    "assoc" => evaluate_assoc(list, env, depth),
+                    CALL_BUILTIN => evaluate_call_builtin(list, env, depth),
                     _ => Err(EvalError::Unimplemented),
                 },
                 Expr::Symbol(s) => {
@@ -107,22 +519,63 @@ fn evaluate_expr(expr: &Expr, env: &mut PassableScope, depth: usize) -> Result<E
 
                     match function {
                         Expr::Lambda(params, body, function_env) => {
+                            check_argument_count(s, &params, list.len() - 1)?;
+
                             let mut extended_env = Scope::extend(function_env);
+                            let (positional, rest) = split_rest_parameter(&params);
 
-                            for (i, param) in params.iter().enumerate() {
-                                let value = evaluate_expr(&list[i + 1], env, depth + 1)?;
+                            for (param, arg) in positional.iter().zip(&list[1..]) {
+                                let value = evaluate_expr(arg, env, depth + 1)?;
 
                                 extended_env.borrow_mut().set(param.clone(), value);
                             }
 
-                            evaluate_expr(&Expr::List(body), &mut extended_env, depth + 1)
+                            if let Some(rest) = rest {
+                                let values = list[positional.len() + 1..]
+                                    .iter()
+                                    .map(|arg| evaluate_expr(arg, env, depth + 1))
+                                    .collect::<Result<Vec<_>, _>>()?;
+
+                                extended_env
+                                    .borrow_mut()
+                                    .set(rest.clone(), Expr::List(values));
+                            }
+
+                            // The debugger keeps track of every call, so they are not replaced
+                            if attached_debugger(env).is_none() {
+                                return Ok(Step::Call(body, extended_env));
+                            }
+
+                            in_function(s, &mut extended_env, |extended_env| {
+                                evaluate_expr(&Expr::List(body), extended_env, depth + 1)
+                            })
                         }
                         _ => Err(EvalError::UndefinedFunction(s.clone())),
                     }
                 }
+                // The head can evaluate to a function, eg. `((make-adder 3) 4)`
+                Expr::List(_) | Expr::Lambda(_, _, _) => {
+                    let head = evaluate_expr(head_op, env, depth + 1)?;
+                    let args = list[1..]
+                        .iter()
+                        .map(|expr| evaluate_expr(expr, env, depth + 1))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    match head {
+                        function @ Expr::Lambda(_, _, _) if attached_debugger(env).is_none() => {
+                            let (body, extended_env) = bind_arguments(&function, args)?;
+
+                            return Ok(Step::Call(body, extended_env));
+                        }
+                        function @ Expr::Lambda(_, _, _) => apply_function(&function, args, depth),
+                        head => Ok(Expr::List(std::iter::once(head).chain(args).collect())),
+                    }
+                }
                 _ => {
-                    let evaluated_list: Vec<_> =
-                        list.iter().map(|expr| evaluate(expr, env)).collect();
+                    let evaluated_list: Vec<_> = list
+                        .iter()
+                        .map(|expr| evaluate_expr(expr, env, depth + 1))
+                        .collect();
 
                     match evaluated_list.iter().find(|r| r.is_err()) {
                         Some(Err(err)) => Err(err.clone()),
@@ -145,32 +598,109 @@ fn evaluate_expr(expr: &Expr, env: &mut PassableScope, depth: usize) -> Result<E
             None => Ok(Expr::List(Vec::new())),
         },
         Expr::Integer(number) => Ok(Expr::Integer(*number)),
+        Expr::BigInt(number) => Ok(Expr::BigInt(number.clone())),
+        Expr::Rational(numerator, denominator) => Ok(Expr::Rational(*numerator, *denominator)),
+        Expr::BigRational(_, _) => Ok(expr.clone()),
+        Expr::Float(number) => Ok(Expr::Float(*number)),
+        Expr::String(string) => Ok(Expr::String(string.clone())),
+        Expr::Bytes(bytes) => Ok(Expr::Bytes(bytes.clone())),
         Expr::Boolean(boolean) => Ok(Expr::Boolean(*boolean)),
         Expr::Symbol(variable) => {
             if let Some(value) = env.borrow().get(variable) {
-                match value {
-                    Expr::Lambda(_params, _body, _function_env) => Err(EvalError::Unimplemented),
-                    other => Ok(other),
-                }
+                Ok(value)
             } else {
                 Err(EvalError::UndefinedVariable(variable.clone()))
             }
         }
-        Expr::Lambda(_params, _body, _function_env) => Ok(Expr::NoOp),
+        Expr::Lambda(_, _, _) | Expr::Promise(_) => Ok(expr.clone()),
+        // Built-ins used as a value are functions, eg. `(def add +)`
+        Expr::Op(_) | Expr::Keyword(_) => Ok(wrap_builtin(expr, env)),
+        Expr::Nil => Ok(Expr::Nil),
         _ => Err(EvalError::Unimplemented),
         // Expr::If => todo!(),
         // Expr::Op(_) => todo!(),
         // Expr::Keyword(_) => todo!(),
         // Expr::Symbol(_) => todo!(),
-        // Expr::NoOp => todo!(),
+    };
+
+    value.map(Step::Value)
+}
+
+/// Takes `amount` steps from the fuel of the interpreter, failing once it runs out.
+///
+/// Without a fuel limit evaluation can run forever.
+pub fn consume_fuel(env: &PassableScope, amount: u64) -> Result<(), EvalError> {
+    let globals = env.borrow().globals();
+    let mut globals = globals.borrow_mut();
+
+    match globals.fuel.as_mut() {
+        Some(fuel) if *fuel < amount => {
+            *fuel = 0;
+
+            Err(EvalError::FuelExhausted)
+        }
+        Some(fuel) => {
+            *fuel -= amount;
+
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Returns the truthiness mode the interpreter runs in
+fn truthiness(env: &PassableScope) -> Truthiness {
+    env.borrow().globals().borrow().truthiness
+}
+
+/// Decides whether a value counts as true, `None` if it cannot be used as a condition
+pub(crate) fn is_truthy(value: &Expr, env: &PassableScope) -> Option<bool> {
+    match (value, truthiness(env)) {
+        (Expr::Boolean(value), _) => Some(*value),
+        (_, Truthiness::Strict) => None,
+        (Expr::List(list), Truthiness::Lenient) => Some(!list.is_empty()),
+        (Expr::Nil, Truthiness::Lenient) => Some(false),
+        (_, Truthiness::Lenient) => Some(true),
+    }
+}
+
+/// Evaluates `and` and `or` with lenient truthiness, returning the first
+/// argument deciding the result without evaluating the rest. The last
+/// argument is in tail position.
+fn evaluate_lenient_logic<'a>(
+    op: &str,
+    list: &'a [Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Step<'a>, EvalError> {
+    // Check argument count
+    if list.len() < 2 {
+        return Err(EvalError::ArgumentCount(op.to_string(), 2));
     }
+
+    for arg in &list[1..list.len() - 1] {
+        let result = evaluate_expr(arg, env, depth + 1)?;
+
+        if is_truthy(&result, env) == Some(op == "or") {
+            return Ok(Step::Value(result));
+        }
+    }
+
+    Ok(Step::Element(list.len() - 1))
 }
 
 /// Evaluates "binary" operations. They are not really binary because they can take as many arguments as you wish.
-fn evaluate_binary_op(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr, EvalError> {
+fn evaluate_binary_op(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
     let op = list.first().unwrap();
 
-    if list.len() < 2 {
+    // Without arguments, `gcd` and `lcm` return their identities 0 and 1
+    let takes_no_arguments = matches!(op, Expr::Op(op) if op == "gcd" || op == "lcm");
+
+    if list.len() < 2 && !takes_no_arguments {
         let name = match op {
             Expr::Op(operator) => operator.to_string(),
             _ => "function".to_string(),
@@ -183,112 +713,35 @@ fn evaluate_binary_op(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr,
 
     match op {
         Expr::Op(op) => match op.as_str() {
-            "+" => {
-                let mut sum = 0;
-
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
-
-                if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
-                    return Err(err.clone());
-                }
-
-                for arg in evaluated {
-                    match arg {
-                        Ok(Expr::Integer(value)) => sum += value,
-                        _ => {
-                            return Err(EvalError::IllegalArgument(
-                                "+",
-                                "All arguments must be numbers",
-                            ))
-                        }
-                    }
-                }
-
-                Ok(Expr::Integer(sum))
-            }
-            "-" => {
-                let mut result = 0;
-
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
-
-                if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
-                    return Err(err.clone());
-                }
-
-                for (i, arg) in evaluated.iter().enumerate() {
-                    match arg {
-                        Ok(Expr::Integer(value)) => {
-                            if i == 0 {
-                                result = *value;
-                            } else {
-                                result -= *value;
-                            }
-                        }
-                        _ => {
-                            return Err(EvalError::IllegalArgument(
-                                "-",
-                                "All arguments must be numbers",
-                            ))
-                        }
-                    }
-                }
-
-                Ok(Expr::Integer(result))
-            }
-            "*" => {
-                let mut result = 1;
-
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
-
-                if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
-                    return Err(err.clone());
-                }
-
-                for arg in evaluated {
-                    match arg {
-                        Ok(Expr::Integer(value)) => result *= value,
-                        _ => {
-                            return Err(EvalError::IllegalArgument(
-                                "*",
-                                "All arguments must be numbers",
-                            ))
-                        }
-                    }
-                }
-
-                Ok(Expr::Integer(result))
-            }
-            "/" => {
-                let mut result = 0;
-
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
-
-                if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
-                    return Err(err.clone());
-                }
-
-                for (i, arg) in evaluated.iter().enumerate() {
-                    match arg {
-                        Ok(Expr::Integer(value)) => {
-                            if i == 0 {
-                                result = *value;
-                            } else {
-                                result /= *value;
-                            }
-                        }
-                        _ => {
-                            return Err(EvalError::IllegalArgument(
-                                "/",
-                                "All arguments must be numbers",
-                            ))
-                        }
-                    }
-                }
-
-                Ok(Expr::Integer(result))
-            }
+            "+" => evaluate_arithmetic("+", args, env, depth),
+            "-" => evaluate_arithmetic("-", args, env, depth),
+            "*" => evaluate_arithmetic("*", args, env, depth),
+            "/" => evaluate_arithmetic("/", args, env, depth),
+            "mod" => evaluate_remainder("mod", args, env, depth),
+            "rem" => evaluate_remainder("rem", args, env, depth),
+            "expt" => evaluate_expt(args, env, depth),
+            "abs" => evaluate_abs(args, env, depth),
+            "gcd" => evaluate_divisors("gcd", args, env, depth),
+            "lcm" => evaluate_divisors("lcm", args, env, depth),
+            "sqrt" => evaluate_float_function("sqrt", args, env, depth),
+            "sin" => evaluate_float_function("sin", args, env, depth),
+            "cos" => evaluate_float_function("cos", args, env, depth),
+            "tan" => evaluate_float_function("tan", args, env, depth),
+            "log" => evaluate_float_function("log", args, env, depth),
+            "exp" => evaluate_float_function("exp", args, env, depth),
+            "floor" => evaluate_rounding("floor", args, env, depth),
+            "ceiling" => evaluate_rounding("ceiling", args, env, depth),
+            "round" => evaluate_rounding("round", args, env, depth),
+            "truncate" => evaluate_rounding("truncate", args, env, depth),
+            "exact->inexact" => evaluate_exactness("exact->inexact", args, env, depth),
+            "inexact->exact" => evaluate_exactness("inexact->exact", args, env, depth),
+            "min" => evaluate_extremum("min", Ordering::Less, args, env, depth),
+            "max" => evaluate_extremum("max", Ordering::Greater, args, env, depth),
             "=" => {
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
+                let evaluated: Vec<_> = args
+                    .iter()
+                    .map(|expr| evaluate_expr(expr, env, depth + 1))
+                    .collect();
 
                 if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
                     return Err(err.clone());
@@ -300,11 +753,14 @@ fn evaluate_binary_op(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr,
                         .filter_map(|e| e.clone().ok())
                         .collect::<Vec<Expr>>()
                         .windows(2)
-                        .all(|w| w[0] == w[1]),
+                        .all(|w| is_equal(&w[0], &w[1])),
                 ))
             }
             "!=" => {
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
+                let evaluated: Vec<_> = args
+                    .iter()
+                    .map(|expr| evaluate_expr(expr, env, depth + 1))
+                    .collect();
 
                 if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
                     return Err(err.clone());
@@ -315,19 +771,37 @@ fn evaluate_binary_op(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr,
                         !evaluated
                             .iter()
                             .filter_map(|e| e.clone().ok())
-                            .all(|e| e == first.clone().unwrap()),
+                            .all(|e| is_equal(&e, first.as_ref().unwrap())),
                     )),
                     None => Ok(Expr::Boolean(false)),
                 }
             }
-            "<" => compare_integers(args, env, |a, b| a.lt(&b)),
-            "<=" => compare_integers(args, env, |a, b| a.le(&b)),
-            ">" => compare_integers(args, env, |a, b| a.gt(&b)),
-            ">=" => compare_integers(args, env, |a, b| a.ge(&b)),
+            "eq?" | "equal?" => {
+                let evaluated = args
+                    .iter()
+                    .map(|expr| evaluate_expr(expr, env, depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let predicate = match op.as_str() {
+                    "eq?" => is_identical,
+                    _ => |a: &Expr, b: &Expr| a == b,
+                };
+
+                Ok(Expr::Boolean(
+                    evaluated.windows(2).all(|w| predicate(&w[0], &w[1])),
+                ))
+            }
+            "<" => compare_numbers(args, env, depth, Ordering::is_lt),
+            "<=" => compare_numbers(args, env, depth, Ordering::is_le),
+            ">" => compare_numbers(args, env, depth, Ordering::is_gt),
+            ">=" => compare_numbers(args, env, depth, Ordering::is_ge),
             "and" => {
                 let mut result = true;
 
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
+                let evaluated: Vec<_> = args
+                    .iter()
+                    .map(|expr| evaluate_expr(expr, env, depth + 1))
+                    .collect();
 
                 if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
                     return Err(err.clone());
@@ -350,7 +824,10 @@ fn evaluate_binary_op(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr,
             "or" => {
                 let mut result = false;
 
-                let evaluated: Vec<_> = args.iter().map(|expr| evaluate(expr, env)).collect();
+                let evaluated: Vec<_> = args
+                    .iter()
+                    .map(|expr| evaluate_expr(expr, env, depth + 1))
+                    .collect();
 
                 if let Some(Err(err)) = evaluated.iter().find(|r| r.is_err()) {
                     return Err(err.clone());
@@ -375,9 +852,12 @@ fn evaluate_binary_op(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr,
                     return Err(EvalError::ArgumentCount("not".to_string(), 1));
                 }
 
-                match &list[1] {
-                    Expr::Boolean(arg) => Ok(Expr::Boolean(!arg)),
-                    _ => Err(EvalError::IllegalArgument(
+                // Any value can be negated when truthiness is lenient
+                let arg = evaluate_expr(&list[1], env, depth + 1)?;
+
+                match is_truthy(&arg, env) {
+                    Some(value) => Ok(Expr::Boolean(!value)),
+                    None => Err(EvalError::IllegalArgument(
                         "not",
                         "Argument must be a boolean",
                     )),
@@ -389,54 +869,150 @@ fn evaluate_binary_op(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr,
     }
 }
 
-/// Evaluates `def` built-in and sets the scope.
+/// Evaluates `def` and `defconst` built-ins and sets the scope.
 ///
 /// Expected Lisper syntax:
 ///
 /// ```
 /// (def x 10)
 /// (def y 20)
+/// (defconst pi 3)
 /// (+ x y)
 /// ```
-fn evaluate_def(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr, EvalError> {
+fn evaluate_def(
+    list: &[Expr],
+    env: &mut PassableScope,
+    constant: bool,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let keyword = if constant { "defconst" } else { "def" };
+
     // Check argument count
     if list.len() != 3 {
-        return Err(EvalError::ArgumentCount("def".to_string(), 3));
+        return Err(EvalError::ArgumentCount(keyword.to_string(), 3));
     }
 
-    // Check if variable name is a symbol
-    let variable_name = match &list[1] {
-        Expr::Symbol(name) => name.clone(),
-
+    // Check if variable name is a symbol, or a list of them to destructure
+    match &list[1] {
+        Expr::Symbol(name) if env.borrow().is_constant(name) => {
+            // Constants cannot be redefined
+            return Err(EvalError::ConstantReassignment(name.clone()));
+        }
+        Expr::Symbol(_) | Expr::List(_) => {}
         _ => {
             return Err(EvalError::IllegalArgument(
-                "def",
+                keyword,
                 "Variable name must be a symbol",
             ))
         }
-    };
+    }
 
     // Eagerly evaluates expression that will be stored in scope
-    let value = evaluate_expr(&list[2], env, 0)?;
+    let value = evaluate_expr(&list[2], env, depth + 1)?;
+
+    let bindings = destructure(keyword, &list[1], value)?;
+
+    if let Some((name, _)) = bindings
+        .iter()
+        .find(|(name, _)| env.borrow().is_constant(name))
+    {
+        return Err(EvalError::ConstantReassignment(name.clone()));
+    }
 
     // Put it into the environment
-    env.borrow_mut().set(variable_name, value);
+    for (variable_name, value) in bindings {
+        if constant {
+            env.borrow_mut().set_constant(variable_name, value);
+        } else {
+            env.borrow_mut().set(variable_name, value);
+        }
+    }
+
+    Ok(Expr::Nil)
+}
+
+/// Binds a value to a variable, or the elements of a list value to a list of
+/// variables, which can be nested and end with `&rest`
+fn destructure(
+    keyword: &'static str,
+    variables: &Expr,
+    value: Expr,
+) -> Result<Vec<(String, Expr)>, EvalError> {
+    match variables {
+        Expr::Symbol(name) => Ok(vec![(name.clone(), value)]),
+        Expr::List(_) => {
+            let mut bindings = Vec::new();
+
+            if !match_pattern(variables, &value, &mut bindings)? {
+                return Err(EvalError::IllegalArgument(
+                    keyword,
+                    "Value does not match the list of variables",
+                ));
+            }
+
+            Ok(bindings)
+        }
+        _ => Err(EvalError::IllegalArgument(
+            keyword,
+            "Variables must be symbols or lists of them",
+        )),
+    }
+}
+
+/// Evaluates `set!` built-in, changing the value of an existing variable.
+///
+/// Unlike `def`, which always defines the variable in the current scope, the
+/// variable is looked up in the enclosing scopes and changed where it is
+/// defined.
+///
+/// Expected Lisper syntax:
+///
+/// ```(set! counter (+ counter 1))```
+fn evaluate_set(list: &[Expr], env: &mut PassableScope, depth: usize) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("set!".to_string(), 2));
+    }
+
+    let variable_name = match &list[1] {
+        Expr::Symbol(name) => name,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "set!",
+                "Variable name must be a symbol",
+            ))
+        }
+    };
+
+    if env.borrow().is_constant(variable_name) {
+        return Err(EvalError::ConstantReassignment(variable_name.clone()));
+    }
+
+    let value = evaluate_expr(&list[2], env, depth + 1)?;
 
-    Ok(Expr::NoOp)
+    if !env.borrow_mut().update(variable_name, value) {
+        return Err(EvalError::UndefinedVariable(variable_name.clone()));
+    }
+
+    Ok(Expr::Nil)
 }
 
 /// Evaluates `defun` built-in and sets the scope.
 ///
+/// The parameters and the body can be given without `lambda`, a body of
+/// several expressions evaluates them in order like `let`.
+///
 /// Expected Lisper syntax:
 ///
 /// ```
 /// (defun power (lambda (x y) (
 ///     if (= y 0) 1 (* x (power x (- y 1)))
 /// )))
+/// (defun power (x y) (if (= y 0) 1 (* x (power x (- y 1)))))
 /// ```
-fn evaluate_defun(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr, EvalError> {
+fn evaluate_defun(list: &[Expr], env: &mut PassableScope) -> Result<Expr, EvalError> {
     // Check argument count
-    if list.len() != 3 {
+    if list.len() < 3 {
         return Err(EvalError::ArgumentCount("defun".to_string(), 3));
     }
 
@@ -452,20 +1028,208 @@ fn evaluate_defun(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr, Eva
         }
     };
 
-    // Get the lambda object
-    let evaluated_lambda = evaluate_lambda(&list[2], env)?;
+    // Constants cannot be redefined
+    if env.borrow().is_constant(&function_name) {
+        return Err(EvalError::ConstantReassignment(function_name));
+    }
+
+    // Get the lambda object, desugaring `(defun name (params...) body...)`
+    let evaluated_lambda = match &list[2..] {
+        [lambda] => evaluate_lambda(lambda, env)?,
+        [params @ Expr::List(_), body @ ..] => {
+            let body = match body {
+                [body @ Expr::List(_)] => body.clone(),
+                _ => Expr::List(
+                    [Expr::Keyword("let".to_string()), Expr::List(Vec::new())]
+                        .into_iter()
+                        .chain(body.iter().cloned())
+                        .collect(),
+                ),
+            };
+
+            evaluate_lambda(
+                &Expr::List(vec![
+                    Expr::Keyword("lambda".to_string()),
+                    params.clone(),
+                    body,
+                ]),
+                env,
+            )?
+        }
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "defun",
+                "Function parameters must be a list",
+            ))
+        }
+    };
 
     // Put it into the environment
     env.borrow_mut().set(function_name, evaluated_lambda);
 
-    Ok(Expr::NoOp)
+    Ok(Expr::Nil)
+}
+
+/// Evaluates an expression that has to result in a function, eg. a function
+/// passed to a built-in
+pub fn evaluate_function(
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    match expr {
+        Expr::Symbol(name) => match env.borrow().get(name) {
+            Some(function @ Expr::Lambda(_, _, _)) => Ok(function),
+            Some(_) => Err(EvalError::UndefinedFunction(name.clone())),
+            None => Err(EvalError::UndefinedVariable(name.clone())),
+        },
+        Expr::List(list) if list.first() == Some(&Expr::Keyword("lambda".to_string())) => {
+            evaluate_lambda(expr, env)
+        }
+        Expr::Lambda(_, _, _) => Ok(expr.clone()),
+        Expr::Op(_) | Expr::Keyword(_) => Ok(wrap_builtin(expr, env)),
+        _ => match evaluate_expr(expr, env, depth + 1)? {
+            function @ Expr::Lambda(_, _, _) => Ok(function),
+            _ => Err(EvalError::IllegalArgument(
+                "call",
+                "Expression is not a function",
+            )),
+        },
+    }
+}
+
+/// Internal keyword calling a wrapped built-in, it cannot be read from the
+/// source code as it is not lexed as a keyword
+pub(crate) const CALL_BUILTIN: &str = "%call-builtin";
+
+/// Wraps an operator or a keyword built-in into a function passing all of
+/// its arguments to the built-in, so that it can be passed around like any
+/// other function, eg. `(reduce + 0 (list 1 2 3))`. The parameter collecting
+/// the arguments is a generated symbol, so that it does not hide a variable
+/// the built-in evaluates.
+fn wrap_builtin(builtin: &Expr, env: &PassableScope) -> Expr {
+    let arguments = env.borrow().globals().borrow_mut().gensym();
+
+    Expr::Lambda(
+        vec![REST_MARKER.to_string(), arguments.clone()],
+        vec![
+            Expr::Keyword(CALL_BUILTIN.to_string()),
+            builtin.clone(),
+            Expr::Symbol(arguments),
+        ],
+        env.clone(),
+    )
+}
+
+/// Checks whether a function body has been created by `wrap_builtin`
+pub(crate) fn is_wrapped_builtin(body: &[Expr]) -> bool {
+    matches!(body, [Expr::Keyword(keyword), _, _] if keyword == CALL_BUILTIN)
+}
+
+/// Evaluates the body of a function created by `wrap_builtin`, calling the
+/// built-in with the arguments quoted, as they are already evaluated
+fn evaluate_call_builtin(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let (builtin, arguments) = match list {
+        [_, builtin, arguments] => (builtin, evaluate_expr(arguments, env, depth + 1)?),
+        _ => return Err(EvalError::Internal),
+    };
+
+    let arguments = match arguments {
+        Expr::List(arguments) => arguments,
+        _ => return Err(EvalError::Internal),
+    };
+
+    let call = std::iter::once(builtin.clone())
+        .chain(
+            arguments
+                .into_iter()
+                .map(|argument| Expr::List(vec![Expr::Keyword("quote".to_string()), argument])),
+        )
+        .collect();
+
+    evaluate_expr(&Expr::List(call), env, depth + 1)
+}
+
+/// Calls a function with already evaluated arguments
+pub fn apply_function(function: &Expr, args: Vec<Expr>, depth: usize) -> Result<Expr, EvalError> {
+    let (body, mut extended_env) = bind_arguments(function, args)?;
+
+    in_function("lambda", &mut extended_env, |extended_env| {
+        evaluate_expr(&Expr::List(body), extended_env, depth + 1)
+    })
+}
+
+/// Binds already evaluated arguments to the parameters of a function,
+/// returning its body and the scope to evaluate it in
+fn bind_arguments(
+    function: &Expr,
+    args: Vec<Expr>,
+) -> Result<(Vec<Expr>, PassableScope), EvalError> {
+    match function {
+        Expr::Lambda(params, body, function_env) => {
+            check_argument_count("lambda", params, args.len())?;
+
+            let (positional, rest) = split_rest_parameter(params);
+            let extended_env = Scope::extend(function_env.clone());
+            let mut args = args.into_iter();
+
+            for (param, value) in positional.iter().zip(args.by_ref()) {
+                extended_env.borrow_mut().set(param.clone(), value);
+            }
+
+            if let Some(rest) = rest {
+                extended_env
+                    .borrow_mut()
+                    .set(rest.clone(), Expr::List(args.collect()));
+            }
+
+            Ok((body.clone(), extended_env))
+        }
+        _ => Err(EvalError::IllegalArgument(
+            "call",
+            "Expression is not a function",
+        )),
+    }
+}
+
+/// Checks that a function with the parameters can be called with `count`
+/// arguments, at least as many as it has positional parameters and no more
+/// unless it has a rest parameter
+fn check_argument_count(name: &str, params: &[String], count: usize) -> Result<(), EvalError> {
+    let (positional, rest) = split_rest_parameter(params);
+
+    if count < positional.len() || (rest.is_none() && count > positional.len()) {
+        return Err(EvalError::ArgumentCount(name.to_string(), positional.len()));
+    }
+
+    Ok(())
+}
+
+/// Marks the parameter collecting the remaining arguments of a function
+const REST_MARKER: &str = "&rest";
+
+/// Splits parameters of a function into the positional ones and the rest parameter
+fn split_rest_parameter(params: &[String]) -> (&[String], Option<&String>) {
+    match params {
+        [positional @ .., marker, rest] if marker == REST_MARKER => (positional, Some(rest)),
+        _ => (params, None),
+    }
 }
 
 /// Evaluates `lambda` built-in
 ///
+/// A parameter after `&rest` is bound to a list of the remaining arguments.
+///
 /// Expected Lisper syntax:
 ///
-/// ```(lambda (x y) (+ x y))```
+/// ```
+/// (lambda (x y) (+ x y))
+/// (lambda (x &rest xs) (cons x xs))
+/// ```
 fn evaluate_lambda(expr: &Expr, env: &mut PassableScope) -> Result<Expr, EvalError> {
     // The passed expression *has* to be a list.
     let list = match expr {
@@ -500,6 +1264,16 @@ fn evaluate_lambda(expr: &Expr, env: &mut PassableScope) -> Result<Expr, EvalErr
                 }
             }
 
+            // `&rest` can only come before the last parameter
+            if let Some(position) = params.iter().position(|p| p == REST_MARKER) {
+                if position + 2 != params.len() {
+                    return Err(EvalError::IllegalArgument(
+                        "lambda",
+                        "&rest must be followed by exactly one parameter",
+                    ));
+                }
+            }
+
             params
         }
         _ => {
@@ -524,23 +1298,476 @@ fn evaluate_lambda(expr: &Expr, env: &mut PassableScope) -> Result<Expr, EvalErr
     Ok(Expr::Lambda(params, contents, env.clone()))
 }
 
-/// Evaluates `print` built-in.
+/// Evaluates `print`, `println`, `write`, `eprint` and `eprintln` built-ins,
+/// outputting the values separated by spaces. `write` outputs them as they
+/// are written in code, the others output strings without quotes. Only
+/// `println` and `eprintln` end with a newline, `eprint` and `eprintln`
+/// write to the standard error.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (print "x = " 4)
+/// (println "x =" 4)
+/// (write "quoted")
+/// (eprintln "warning:" 4)
+/// ```
+fn evaluate_print(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Evaluates expressions to be printed
+    let values = list[1..]
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut text = values
+        .iter()
+        .map(|value| match name {
+            "write" => value.to_string(),
+            _ => display(value),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if name.ends_with("ln") {
+        text.push('\n');
+    }
+
+    match name {
+        "eprint" | "eprintln" => env.borrow().globals().borrow_mut().eprint(&text),
+        _ => env.borrow().globals().borrow_mut().print(&text),
+    }
+
+    // Returns the last evaluated value
+    Ok(values.into_iter().last().unwrap_or(Expr::Nil))
+}
+
+/// Checks whether a value matches a pattern, collecting the variables it binds.
+///
+/// Symbols bind the matched value, except for `_`, which matches anything.
+/// Quoted data and other atoms match equal values. Lists match lists of the
+/// same length element by element, a pattern after `&rest` matches the list
+/// of remaining elements.
+fn match_pattern(
+    pattern: &Expr,
+    value: &Expr,
+    bindings: &mut Vec<(String, Expr)>,
+) -> Result<bool, EvalError> {
+    match pattern {
+        Expr::Symbol(name) if name == "_" => Ok(true),
+        Expr::Symbol(name) => {
+            bindings.push((name.clone(), value.clone()));
+
+            Ok(true)
+        }
+        Expr::List(patterns) => match patterns.as_slice() {
+            [Expr::Keyword(keyword), datum] if keyword == "quote" => Ok(datum == value),
+            [positional @ .., Expr::Symbol(marker), rest] if marker == REST_MARKER => {
+                let values = match value {
+                    Expr::List(values) if values.len() >= positional.len() => values,
+                    _ => return Ok(false),
+                };
+
+                for (pattern, value) in positional.iter().zip(values) {
+                    if !match_pattern(pattern, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                match_pattern(
+                    rest,
+                    &Expr::List(values[positional.len()..].to_vec()),
+                    bindings,
+                )
+            }
+            _ => {
+                let values = match value {
+                    Expr::List(values) if values.len() == patterns.len() => values,
+                    _ => return Ok(false),
+                };
+
+                for (pattern, value) in patterns.iter().zip(values) {
+                    if !match_pattern(pattern, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+        },
+        Expr::If | Expr::Op(_) | Expr::Keyword(_) => Err(EvalError::IllegalArgument(
+            "match",
+            "Patterns must be atoms, symbols, quoted data or lists of patterns",
+        )),
+        literal => Ok(literal == value),
+    }
+}
+
+/// Evaluates `match` built-in, evaluating the body of the first clause whose
+/// pattern matches the value of the expression, with the variables of the
+/// pattern bound. `nil` is returned when no pattern matches.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (match shape
+///     (('circle r) (* 3 r r))
+///     (('rect w h) (* w h))
+///     (_ 0)
+/// )
+/// ```
+fn evaluate_match<'a>(
+    list: &'a [Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Step<'a>, EvalError> {
+    // Check argument count
+    if list.len() < 2 {
+        return Err(EvalError::ArgumentCount("match".to_string(), 1));
+    }
+
+    let value = evaluate_expr(&list[1], env, depth + 1)?;
+
+    for clause in &list[2..] {
+        let (pattern, body) = match clause {
+            Expr::List(clause) if !clause.is_empty() => (&clause[0], &clause[1..]),
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "match",
+                    "Clauses must be lists starting with their pattern",
+                ))
+            }
+        };
+
+        let mut bindings = Vec::new();
+
+        if !match_pattern(pattern, &value, &mut bindings)? {
+            continue;
+        }
+
+        let extended_env = Scope::extend(env.clone());
+
+        for (name, value) in bindings {
+            extended_env.borrow_mut().set(name, value);
+        }
+
+        return evaluate_body(body, extended_env, depth);
+    }
+
+    Ok(Step::Value(Expr::Nil))
+}
+
+/// Evaluates `quote` built-in, returning its argument without evaluating it.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (quote (a b c))
+/// '(a b c)
+/// ```
+fn evaluate_quote(list: &[Expr]) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("quote".to_string(), 1));
+    }
+
+    Ok(list[1].clone())
+}
+
+/// Evaluates `eval` built-in, evaluating the value of its argument as code in
+/// the current scope.
 ///
 /// Expected Lisper syntax:
 ///
-/// ```(print 4)```
-fn evaluate_print(list: &Vec<Expr>, env: &mut PassableScope) -> Result<Expr, EvalError> {
+/// ```(eval '(+ 1 2))```
+fn evaluate_eval(list: &[Expr], env: &mut PassableScope, depth: usize) -> Result<Expr, EvalError> {
     // Check argument count
     if list.len() != 2 {
-        return Err(EvalError::ArgumentCount("print".to_string(), 1));
+        return Err(EvalError::ArgumentCount("eval".to_string(), 1));
+    }
+
+    let code = evaluate_expr(&list[1], env, depth + 1)?;
+
+    evaluate_expr(&code, env, depth + 1)
+}
+
+/// Evaluates `nil?` built-in, checking whether a value is `nil`.
+///
+/// Expected Lisper syntax:
+///
+/// ```(nil? x)```
+fn evaluate_is_nil(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("nil?".to_string(), 1));
+    }
+
+    let value = evaluate_expr(&list[1], env, depth + 1)?;
+
+    Ok(Expr::Boolean(value == Expr::Nil))
+}
+
+/// Evaluates `gensym` built-in, returning a fresh symbol.
+///
+/// Expected Lisper syntax:
+///
+/// ```(gensym)```
+fn evaluate_gensym(list: &[Expr], env: &mut PassableScope) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 1 {
+        return Err(EvalError::ArgumentCount("gensym".to_string(), 0));
+    }
+
+    let symbol = env.borrow().globals().borrow_mut().gensym();
+
+    Ok(Expr::Symbol(symbol))
+}
+
+/// Evaluates `values` built-in, returning all of its arguments at once.
+///
+/// Expected Lisper syntax:
+///
+/// ```(values 1 2)```
+fn evaluate_values(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let values = list[1..]
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Expr::Values(values))
+}
+
+/// Evaluates `let` and `let*` built-ins, binding local variables in a new scope.
+///
+/// Values bound by `let` are evaluated in the outer scope, `let*` evaluates them
+/// one after another, so that they can use the previous bindings.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (let ((x 1) (y 2)) (+ x y))
+/// (let* ((x 1) (y (+ x 1))) (* x y))
+/// ```
+fn evaluate_let<'a>(
+    list: &'a [Expr],
+    env: &mut PassableScope,
+    sequential: bool,
+    depth: usize,
+) -> Result<Step<'a>, EvalError> {
+    let name = if sequential { "let*" } else { "let" };
+
+    // Check argument count
+    if list.len() < 3 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 2));
+    }
+
+    let bindings = match &list[1] {
+        Expr::List(bindings) => bindings,
+        _ => return Err(EvalError::IllegalArgument(name, "Bindings must be a list")),
+    };
+
+    let mut extended_env = Scope::extend(env.clone());
+
+    for binding in bindings {
+        let (variables, expr) = match binding {
+            Expr::List(binding) => match binding.as_slice() {
+                [variables @ (Expr::Symbol(_) | Expr::List(_)), expr] => (variables, expr),
+                _ => {
+                    return Err(EvalError::IllegalArgument(
+                        name,
+                        "Each binding must be a symbol and an expression",
+                    ))
+                }
+            },
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    name,
+                    "Each binding must be a list",
+                ))
+            }
+        };
+
+        let value = if sequential {
+            evaluate_expr(expr, &mut extended_env, depth + 1)?
+        } else {
+            evaluate_expr(expr, env, depth + 1)?
+        };
+
+        for (variable, value) in destructure(name, variables, value)? {
+            extended_env.borrow_mut().set(variable, value);
+        }
+    }
+
+    evaluate_body(&list[2..], extended_env, depth)
+}
+
+/// Evaluates a body of expressions in order, leaving the last one in tail
+/// position. An empty body evaluates to `nil`.
+pub(crate) fn evaluate_body<'a>(
+    body: &'a [Expr],
+    mut env: PassableScope,
+    depth: usize,
+) -> Result<Step<'a>, EvalError> {
+    let (last, rest) = match body.split_last() {
+        Some(split) => split,
+        None => return Ok(Step::Value(Expr::Nil)),
+    };
+
+    for expr in rest {
+        evaluate_expr(expr, &mut env, depth + 1)?;
     }
 
-    // Evaluates expression to be printed
-    let to_print = evaluate_expr(&list[1], env, 0)?;
+    Ok(Step::Nested(Cow::Borrowed(last), env))
+}
+
+/// Evaluates `let-values` built-in, binding multiple values in a new scope.
+///
+/// A single (non-`values`) result is treated as one value.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (let-values (((q r) (divmod 17 5)))
+///     (println q)
+///     (println r)
+/// )
+/// ```
+fn evaluate_let_values(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 3 {
+        return Err(EvalError::ArgumentCount("let-values".to_string(), 2));
+    }
+
+    let bindings = match &list[1] {
+        Expr::List(bindings) => bindings,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "let-values",
+                "Bindings must be a list",
+            ))
+        }
+    };
+
+    let mut extended_env = Scope::extend(env.clone());
 
-    // Outputs it
-    println!("{to_print}");
+    for binding in bindings {
+        let (names, expr) = match binding {
+            Expr::List(binding) if binding.len() == 2 => match &binding[0] {
+                Expr::List(names) => (names, &binding[1]),
+                _ => {
+                    return Err(EvalError::IllegalArgument(
+                        "let-values",
+                        "Bound names must be a list of symbols",
+                    ))
+                }
+            },
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "let-values",
+                    "Each binding must be a list of names and an expression",
+                ))
+            }
+        };
 
-    // Returns the evaluated code
-    Ok(to_print)
+        // Bindings are evaluated in the outer scope
+        let values = match evaluate_expr(expr, env, depth + 1)? {
+            Expr::Values(values) => values,
+            value => vec![value],
+        };
+
+        if names.len() != values.len() {
+            return Err(EvalError::IllegalArgument(
+                "let-values",
+                "Number of values does not match the bound names",
+            ));
+        }
+
+        for (name, value) in names.iter().zip(values) {
+            match name {
+                Expr::Symbol(name) => extended_env.borrow_mut().set(name.clone(), value),
+                _ => {
+                    return Err(EvalError::IllegalArgument(
+                        "let-values",
+                        "Bound names must be a list of symbols",
+                    ))
+                }
+            }
+        }
+    }
+
+    let mut result = Expr::Nil;
+
+    for expr in &list[2..] {
+        result = evaluate_expr(expr, &mut extended_env, depth + 1)?;
+    }
+
+    Ok(result)
+}
+
+/// Evaluates `divmod` built-in, returning the quotient and the remainder.
+/// The quotient is rounded down, so that the remainder is the same as the
+/// one of `mod`, and promoted to a big integer when needed.
+///
+/// Expected Lisper syntax:
+///
+/// ```(divmod 17 5)```
+fn evaluate_divmod(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("divmod".to_string(), 2));
+    }
+
+    let dividend = evaluate_expr(&list[1], env, depth + 1)?;
+    let divisor = evaluate_expr(&list[2], env, depth + 1)?;
+
+    let (a, b) = match (as_bigint(dividend), as_bigint(divisor)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "divmod",
+                "All arguments must be integers",
+            ))
+        }
+    };
+
+    let (quotient, remainder) = a.div_rem(&b).ok_or(EvalError::DivisionByZero("divmod"))?;
+    let zero = BigInt::default();
+
+    // Truncated division is rounded down when the signs differ
+    let (quotient, remainder) = if remainder != zero && (remainder < zero) != (b < zero) {
+        (&quotient - &BigInt::from_i128(1), &remainder + &b)
+    } else {
+        (quotient, remainder)
+    };
+
+    Ok(Expr::Values(vec![integer(quotient), integer(remainder)]))
+}
+
+/// Converts an integer to a big integer, `None` for other values
+fn as_bigint(value: Expr) -> Option<BigInt> {
+    match value {
+        Expr::Integer(value) => Some(BigInt::from_i128(value as i128)),
+        Expr::BigInt(value) => Some(value),
+        _ => None,
+    }
 }
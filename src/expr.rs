@@ -1,10 +1,20 @@
 //! Expression object used for evaluation
 
-use crate::scope::PassableScope;
+use crate::{bigint::BigInt, eval::is_wrapped_builtin, promise::Promise, scope::PassableScope};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Integer(i64),
+    /// Integer that does not fit into `i64`
+    BigInt(BigInt),
+    /// Normalized fraction with a denominator greater than one
+    Rational(i64, i64),
+    /// Normalized fraction whose numerator or denominator does not fit into
+    /// `i64`, boxed so that it does not make every expression larger
+    BigRational(Box<BigInt>, Box<BigInt>),
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
     Boolean(bool),
 
     If,
@@ -16,17 +26,77 @@ pub enum Expr {
 
     Lambda(Vec<String>, Vec<Expr>, PassableScope),
 
-    NoOp,
+    /// Multiple values returned at once by `values`
+    Values(Vec<Expr>),
+
+    /// A signalled condition, its type and values of its fields
+    Condition(String, Vec<(String, Expr)>),
+
+    /// A lazily evaluated value created by `delay`
+    Promise(Promise),
+
+    /// Absence of a value, returned by forms evaluated only for their effect
+    Nil,
 }
 
+/// Prints expressions as Lisper code that is parsed back into the same
+/// expression. Functions are printed as the `lambda` expression they were
+/// created from. Conditions and promises cannot be read back.
 impl std::fmt::Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Expr::Integer(num) => write!(f, "{num}"),
+            Expr::BigInt(num) => write!(f, "{num}"),
+            Expr::Rational(numerator, denominator) => write!(f, "{numerator}/{denominator}"),
+            Expr::BigRational(numerator, denominator) => write!(f, "{numerator}/{denominator}"),
+            // Floats always have a dot, so that they are not read back as integers
+            Expr::Float(num) if num.is_finite() => {
+                let printed = format!("{num:?}");
+
+                match printed.split_once('e') {
+                    Some((mantissa, exponent)) if !mantissa.contains('.') => {
+                        write!(f, "{mantissa}.0e{exponent}")
+                    }
+                    _ => write!(f, "{printed}"),
+                }
+            }
+            Expr::Float(num) => write!(f, "{num}"),
+            Expr::String(string) => {
+                write!(f, "\"")?;
+
+                for c in string.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\t' => write!(f, "\\t")?,
+                        '\r' => write!(f, "\\r")?,
+                        '\0' => write!(f, "\\0")?,
+                        _ => write!(f, "{c}")?,
+                    }
+                }
+
+                write!(f, "\"")
+            }
+            Expr::Bytes(bytes) => {
+                write!(f, "b\"")?;
+
+                for byte in bytes {
+                    match byte {
+                        b'"' => write!(f, "\\\"")?,
+                        b'\\' => write!(f, "\\\\")?,
+                        b'\n' => write!(f, "\\n")?,
+                        b' '..=b'~' => write!(f, "{}", *byte as char)?,
+                        _ => write!(f, "\\x{byte:02x}")?,
+                    }
+                }
+
+                write!(f, "\"")
+            }
             Expr::Boolean(bool) => write!(f, "{bool}"),
-            Expr::If => write!(f, "-=-"),
-            Expr::Op(op) => write!(f, "Binary op {op}"),
-            Expr::Keyword(kwd) => write!(f, "[{kwd}]"),
+            Expr::If => write!(f, "if"),
+            Expr::Op(op) => write!(f, "{op}"),
+            Expr::Keyword(kwd) => write!(f, "{kwd}"),
             Expr::Symbol(sym) => write!(f, "{sym}"),
             Expr::List(list) => {
                 let mut output = "(".to_string();
@@ -43,8 +113,33 @@ impl std::fmt::Display for Expr {
 
                 write!(f, "{output}")
             }
-            Expr::Lambda(_, _, _) => write!(f, "-=-"),
-            Expr::NoOp => write!(f, "-=-"),
+            // Wrapped built-ins are printed as the built-in, which evaluates
+            // to the same function
+            Expr::Lambda(_, body, _) if is_wrapped_builtin(body) => write!(f, "{}", body[1]),
+            Expr::Lambda(params, body, _) => {
+                write!(
+                    f,
+                    "(lambda ({}) {})",
+                    params.join(" "),
+                    Expr::List(body.clone())
+                )
+            }
+            Expr::Values(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+
+                write!(f, "(values {})", values.join(" "))
+            }
+            Expr::Condition(name, fields) => {
+                write!(f, "#<{name}")?;
+
+                for (field, value) in fields {
+                    write!(f, " {field}: {value}")?;
+                }
+
+                write!(f, ">")
+            }
+            Expr::Promise(_) => write!(f, "#<promise>"),
+            Expr::Nil => write!(f, "nil"),
         }
     }
 }
@@ -0,0 +1,734 @@
+//! Iteration helpers implemented natively, so loops do not hit the recursion limit
+
+use std::cmp::Ordering;
+
+use crate::{
+    arithmetic::compare,
+    eval::{apply_function, consume_fuel, evaluate_expr, evaluate_function, is_truthy, EvalError},
+    expr::Expr,
+    scope::{PassableScope, Scope},
+};
+
+/// Largest number of elements of a list created at once by `repeat` or `range`
+const MAX_LIST_LENGTH: usize = 1 << 24;
+
+/// Evaluates `repeat` built-in, creating a list of `n` copies of a value.
+///
+/// Expected Lisper syntax:
+///
+/// ```(repeat 3 0)```
+pub fn evaluate_repeat(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("repeat".to_string(), 2));
+    }
+
+    let count = evaluate_count("repeat", &list[1], env, depth)?;
+    consume_fuel(env, count as u64)?;
+
+    if count > MAX_LIST_LENGTH {
+        return Err(EvalError::IllegalArgument("repeat", "Count is too large"));
+    }
+
+    let value = evaluate_expr(&list[2], env, depth + 1)?;
+
+    Ok(Expr::List(vec![value; count]))
+}
+
+/// Evaluates `range` built-in, creating a list of integers from `start` up to
+/// but excluding `end`, increasing by `step`. `start` defaults to `0` and
+/// `step` to `1`, a negative step counts down.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (range 5)
+/// (range 1 10 2)
+/// ```
+pub fn evaluate_range(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 2 || list.len() > 4 {
+        return Err(EvalError::ArgumentCount("range".to_string(), 3));
+    }
+
+    let bounds = list[1..]
+        .iter()
+        .map(|expr| match evaluate_expr(expr, env, depth + 1)? {
+            Expr::Integer(bound) => Ok(bound),
+            _ => Err(EvalError::IllegalArgument(
+                "range",
+                "Arguments must be integers",
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (start, end, step) = match bounds.as_slice() {
+        [end] => (0, *end, 1),
+        [start, end] => (*start, *end, 1),
+        [start, end, step] => (*start, *end, *step),
+        _ => return Err(EvalError::Unreachable),
+    };
+
+    if step == 0 {
+        return Err(EvalError::IllegalArgument("range", "Step must not be zero"));
+    }
+
+    // Computed in 128 bits, so that neither the distance nor the count overflow
+    let distance = end as i128 - start as i128;
+    let count = if distance.signum() == (step as i128).signum() {
+        (distance.abs() + step.unsigned_abs() as i128 - 1) / step.unsigned_abs() as i128
+    } else {
+        0
+    };
+
+    consume_fuel(env, count as u64)?;
+
+    if count > MAX_LIST_LENGTH as i128 {
+        return Err(EvalError::IllegalArgument("range", "Range is too large"));
+    }
+
+    let elements = (0..count)
+        .map(|i| Expr::Integer((start as i128 + i * step as i128) as i64))
+        .collect();
+
+    Ok(Expr::List(elements))
+}
+
+/// Evaluates `iterate` built-in, creating a list of `n` successive applications
+/// of a function, starting with the initial value.
+///
+/// Expected Lisper syntax:
+///
+/// ```(iterate double 1 5)```
+pub fn evaluate_iterate(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 4 {
+        return Err(EvalError::ArgumentCount("iterate".to_string(), 3));
+    }
+
+    let function = evaluate_function(&list[1], env, depth)?;
+    let mut value = evaluate_expr(&list[2], env, depth + 1)?;
+    let count = evaluate_count("iterate", &list[3], env, depth)?;
+
+    let mut result = Vec::new();
+
+    for i in 0..count {
+        consume_fuel(env, 1)?;
+
+        if i > 0 {
+            value = apply_function(&function, vec![value], depth)?;
+        }
+
+        result.push(value.clone());
+    }
+
+    Ok(Expr::List(result))
+}
+
+/// Evaluates `unfold` built-in.
+///
+/// Starting with the seed, the mapped seed is collected and the seed is
+/// replaced by its successor, until the stop predicate holds for the seed.
+///
+/// Expected Lisper syntax:
+///
+/// ```(unfold stop? mapper successor seed)```
+pub fn evaluate_unfold(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 5 {
+        return Err(EvalError::ArgumentCount("unfold".to_string(), 4));
+    }
+
+    let stop = evaluate_function(&list[1], env, depth)?;
+    let mapper = evaluate_function(&list[2], env, depth)?;
+    let successor = evaluate_function(&list[3], env, depth)?;
+    let mut seed = evaluate_expr(&list[4], env, depth + 1)?;
+
+    let mut result = Vec::new();
+
+    loop {
+        consume_fuel(env, 1)?;
+
+        match apply_function(&stop, vec![seed.clone()], depth)? {
+            Expr::Boolean(true) => return Ok(Expr::List(result)),
+            Expr::Boolean(false) => {}
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "unfold",
+                    "Stop predicate must return a bool",
+                ))
+            }
+        }
+
+        result.push(apply_function(&mapper, vec![seed.clone()], depth)?);
+        seed = apply_function(&successor, vec![seed], depth)?;
+    }
+}
+
+/// Evaluates `map` built-in, applying a function to the elements of lists.
+/// With multiple lists, the function takes an element of each of them and
+/// the result is as long as the shortest list.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (map double (list 1 2 3))
+/// (map + (list 1 2) (list 10 20))
+/// ```
+pub fn evaluate_map(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 3 {
+        return Err(EvalError::ArgumentCount("map".to_string(), 2));
+    }
+
+    let function = evaluate_function(&list[1], env, depth)?;
+
+    let mut lists = list[2..]
+        .iter()
+        .map(|expr| evaluate_elements("map", expr, env, depth).map(Vec::into_iter))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut result = Vec::new();
+
+    loop {
+        let args = match lists
+            .iter_mut()
+            .map(Iterator::next)
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(args) => args,
+            None => return Ok(Expr::List(result)),
+        };
+
+        consume_fuel(env, 1)?;
+
+        result.push(apply_function(&function, args, depth)?);
+    }
+}
+
+/// Evaluates `filter` built-in, keeping the elements of a list for which
+/// the predicate holds.
+///
+/// Expected Lisper syntax:
+///
+/// ```(filter (lambda (x) (> x 1)) (list 1 2 3))```
+pub fn evaluate_filter(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("filter".to_string(), 2));
+    }
+
+    let predicate = evaluate_function(&list[1], env, depth)?;
+    let elements = evaluate_elements("filter", &list[2], env, depth)?;
+
+    let mut result = Vec::new();
+
+    for element in elements {
+        if apply_predicate("filter", &predicate, &element, env, depth)? {
+            result.push(element);
+        }
+    }
+
+    Ok(Expr::List(result))
+}
+
+/// Evaluates `any?`, `all?` and `count-if` built-ins, checking whether the
+/// predicate holds for some or for all elements of a list, or counting the
+/// elements it holds for. `any?` and `all?` stop at the first element
+/// deciding the result.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (any? (lambda (x) (> x 2)) (list 1 2 3))
+/// (all? (lambda (x) (> x 2)) (list 1 2 3))
+/// (count-if (lambda (x) (> x 1)) (list 1 2 3))
+/// ```
+pub fn evaluate_quantifier(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 2));
+    }
+
+    let predicate = evaluate_function(&list[1], env, depth)?;
+    let elements = evaluate_elements(name, &list[2], env, depth)?;
+
+    let mut count = 0;
+
+    for element in elements {
+        let holds = apply_predicate(name, &predicate, &element, env, depth)?;
+
+        match (name, holds) {
+            ("any?", true) => return Ok(Expr::Boolean(true)),
+            ("all?", false) => return Ok(Expr::Boolean(false)),
+            (_, true) => count += 1,
+            (_, false) => {}
+        }
+    }
+
+    match name {
+        "any?" => Ok(Expr::Boolean(false)),
+        "all?" => Ok(Expr::Boolean(true)),
+        _ => Ok(Expr::Integer(count)),
+    }
+}
+
+/// Evaluates `take-while` and `drop-while` built-ins, returning the leading
+/// elements of a list for which the predicate holds or the elements after them.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (take-while (lambda (x) (< x 3)) (list 1 2 3 1))
+/// (drop-while (lambda (x) (< x 3)) (list 1 2 3 1))
+/// ```
+pub fn evaluate_take_while(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 2));
+    }
+
+    let predicate = evaluate_function(&list[1], env, depth)?;
+    let mut elements = evaluate_elements(name, &list[2], env, depth)?;
+
+    let mut count = 0;
+
+    while let Some(element) = elements.get(count) {
+        if !apply_predicate(name, &predicate, element, env, depth)? {
+            break;
+        }
+
+        count += 1;
+    }
+
+    let rest = elements.split_off(count);
+
+    match name {
+        "take-while" => Ok(Expr::List(elements)),
+        _ => Ok(Expr::List(rest)),
+    }
+}
+
+/// Evaluates `reduce` built-in, combining the elements of a list from the
+/// left. Without an initial value, the first element is used instead and
+/// the list must not be empty.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (reduce + 0 (list 1 2 3))
+/// (reduce max (list 3 1 2))
+/// ```
+pub fn evaluate_reduce(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 && list.len() != 4 {
+        return Err(EvalError::ArgumentCount("reduce".to_string(), 3));
+    }
+
+    let function = evaluate_function(&list[1], env, depth)?;
+
+    let (initial, elements) = match list {
+        [_, _, initial, elements] => {
+            let initial = evaluate_expr(initial, env, depth + 1)?;
+
+            (
+                Some(initial),
+                evaluate_elements("reduce", elements, env, depth)?,
+            )
+        }
+        _ => (None, evaluate_elements("reduce", &list[2], env, depth)?),
+    };
+
+    let mut elements = elements.into_iter();
+
+    let mut accumulator = match initial.or_else(|| elements.next()) {
+        Some(accumulator) => accumulator,
+        None => {
+            return Err(EvalError::IllegalArgument(
+                "reduce",
+                "List must not be empty without an initial value",
+            ))
+        }
+    };
+
+    for element in elements {
+        consume_fuel(env, 1)?;
+
+        accumulator = apply_function(&function, vec![accumulator, element], depth)?;
+    }
+
+    Ok(accumulator)
+}
+
+/// Evaluates `fold-left` and `fold-right` built-ins, combining the elements of
+/// a list with an accumulator starting with the initial value. `fold-left`
+/// calls `(f acc x)` from the first element on, `fold-right` calls `(f x acc)`
+/// from the last element on.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (fold-left (lambda (acc x) (cons x acc)) nil (list 1 2 3))
+/// (fold-right (lambda (x acc) (cons x acc)) nil (list 1 2 3))
+/// ```
+pub fn evaluate_fold(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 4 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 3));
+    }
+
+    let function = evaluate_function(&list[1], env, depth)?;
+    let mut accumulator = evaluate_expr(&list[2], env, depth + 1)?;
+    let elements = evaluate_elements(name, &list[3], env, depth)?;
+
+    if name == "fold-left" {
+        for element in elements {
+            consume_fuel(env, 1)?;
+
+            accumulator = apply_function(&function, vec![accumulator, element], depth)?;
+        }
+    } else {
+        // Walking the list backwards instead of recursing keeps long lists
+        // within the recursion limit
+        for element in elements.into_iter().rev() {
+            consume_fuel(env, 1)?;
+
+            accumulator = apply_function(&function, vec![element, accumulator], depth)?;
+        }
+    }
+
+    Ok(accumulator)
+}
+
+/// Evaluates `sort` built-in, sorting a list of numbers or of strings in
+/// ascending order. With a comparison function, which holds when its first
+/// argument goes before the second one, any values can be sorted. The sort
+/// is stable, so equal elements keep their order.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (sort (list 3 1 2))
+/// (sort (lambda (a b) (> a b)) (list 3 1 2))
+/// ```
+pub fn evaluate_sort(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 && list.len() != 3 {
+        return Err(EvalError::ArgumentCount("sort".to_string(), 1));
+    }
+
+    let function = match list {
+        [_, function, _] => Some(evaluate_function(function, env, depth)?),
+        _ => None,
+    };
+
+    let elements = evaluate_elements("sort", &list[list.len() - 1], env, depth)?;
+
+    let sorted = merge_sort(elements, &mut |a, b| {
+        consume_fuel(env, 1)?;
+
+        let function = match &function {
+            Some(function) => function,
+            None => return Ok(default_order(a, b)? == Ordering::Less),
+        };
+
+        let holds = apply_function(function, vec![a.clone(), b.clone()], depth)?;
+
+        match is_truthy(&holds, env) {
+            Some(holds) => Ok(holds),
+            None => Err(EvalError::IllegalArgument(
+                "sort",
+                "Comparison must return a bool",
+            )),
+        }
+    })?;
+
+    Ok(Expr::List(sorted))
+}
+
+/// Orders numbers by their value and strings lexicographically
+fn default_order(a: &Expr, b: &Expr) -> Result<Ordering, EvalError> {
+    match (a, b) {
+        (Expr::String(a), Expr::String(b)) => Ok(a.cmp(b)),
+        _ => compare(a, b).ok_or(EvalError::IllegalArgument(
+            "sort",
+            "Elements must be numbers or strings",
+        )),
+    }
+}
+
+/// Sorts elements with a fallible comparison, which holds when the first
+/// element goes before the second one. Equal elements keep their order.
+fn merge_sort(
+    mut elements: Vec<Expr>,
+    less: &mut impl FnMut(&Expr, &Expr) -> Result<bool, EvalError>,
+) -> Result<Vec<Expr>, EvalError> {
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+
+    let right = elements.split_off(elements.len() / 2);
+    let mut left = merge_sort(elements, less)?.into_iter().peekable();
+    let mut right = merge_sort(right, less)?.into_iter().peekable();
+
+    let mut result = Vec::with_capacity(left.len() + right.len());
+
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Elements of the right half only go first when they are strictly smaller
+        let next = if less(b, a)? {
+            right.next()
+        } else {
+            left.next()
+        };
+
+        result.extend(next);
+    }
+
+    result.extend(left);
+    result.extend(right);
+
+    Ok(result)
+}
+
+/// Evaluates `for/list` built-in, collecting the value of the body for every
+/// element of a list. An integer `n` iterates over `0` to `n - 1`.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (for/list (x (1 2 3)) (* x x))
+/// (for/list (i 10) (* i 2))
+/// ```
+pub fn evaluate_for_list(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 3 {
+        return Err(EvalError::ArgumentCount("for/list".to_string(), 2));
+    }
+
+    let (variable, sequence) = loop_binding("for/list", &list[1])?;
+
+    // Number ranges are not collected up front, they can be arbitrarily long
+    let elements: Box<dyn Iterator<Item = Expr>> = match evaluate_expr(sequence, env, depth + 1)? {
+        Expr::List(elements) => Box::new(elements.into_iter()),
+        Expr::Integer(count) => Box::new((0..count).map(Expr::Integer)),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "for/list",
+                "Sequence must be a list or a number",
+            ))
+        }
+    };
+
+    let mut result = Vec::new();
+
+    for element in elements {
+        result.push(evaluate_loop_body(
+            &list[2..],
+            variable,
+            element,
+            env,
+            depth,
+        )?);
+    }
+
+    Ok(Expr::List(result))
+}
+
+/// Evaluates `dotimes` built-in, evaluating the body for every number from
+/// `0` to `n - 1`.
+///
+/// Expected Lisper syntax:
+///
+/// ```(dotimes (i 10) (println i))```
+pub fn evaluate_dotimes(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 3 {
+        return Err(EvalError::ArgumentCount("dotimes".to_string(), 2));
+    }
+
+    let (variable, count) = loop_binding("dotimes", &list[1])?;
+    let count = evaluate_count("dotimes", count, env, depth)?;
+
+    for i in 0..count {
+        evaluate_loop_body(&list[2..], variable, Expr::Integer(i as i64), env, depth)?;
+    }
+
+    Ok(Expr::Nil)
+}
+
+/// Evaluates `dolist` built-in, evaluating the body for every element of a list.
+///
+/// Expected Lisper syntax:
+///
+/// ```(dolist (x (list 1 2 3)) (println x))```
+pub fn evaluate_dolist(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 3 {
+        return Err(EvalError::ArgumentCount("dolist".to_string(), 2));
+    }
+
+    let (variable, elements) = loop_binding("dolist", &list[1])?;
+
+    let elements = match evaluate_expr(elements, env, depth + 1)? {
+        Expr::List(elements) => elements,
+        Expr::Nil => Vec::new(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "dolist",
+                "Argument must be a list",
+            ))
+        }
+    };
+
+    for element in elements {
+        evaluate_loop_body(&list[2..], variable, element, env, depth)?;
+    }
+
+    Ok(Expr::Nil)
+}
+
+/// Splits the binding of a loop, `(variable expression)`
+fn loop_binding<'a>(
+    name: &'static str,
+    binding: &'a Expr,
+) -> Result<(&'a String, &'a Expr), EvalError> {
+    match binding {
+        Expr::List(binding) => match binding.as_slice() {
+            [Expr::Symbol(variable), expr] => Ok((variable, expr)),
+            _ => Err(EvalError::IllegalArgument(
+                name,
+                "Binding must be a symbol and an expression",
+            )),
+        },
+        _ => Err(EvalError::IllegalArgument(name, "Binding must be a list")),
+    }
+}
+
+/// Evaluates the body of a loop in a new scope with the variable bound to the value
+fn evaluate_loop_body(
+    body: &[Expr],
+    variable: &str,
+    value: Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    consume_fuel(env, 1)?;
+
+    let mut extended_env = Scope::extend(env.clone());
+    extended_env.borrow_mut().set(variable.to_string(), value);
+
+    let mut result = Expr::Nil;
+
+    for expr in body {
+        result = evaluate_expr(expr, &mut extended_env, depth + 1)?;
+    }
+
+    Ok(result)
+}
+
+/// Applies a predicate to an element, which must return a bool
+fn apply_predicate(
+    name: &'static str,
+    predicate: &Expr,
+    element: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<bool, EvalError> {
+    consume_fuel(env, 1)?;
+
+    let holds = apply_function(predicate, vec![element.clone()], depth)?;
+
+    is_truthy(&holds, env).ok_or(EvalError::IllegalArgument(
+        name,
+        "Predicate must return a bool",
+    ))
+}
+
+/// Evaluates the elements of a list argument, `nil` is taken as the empty list
+fn evaluate_elements(
+    name: &'static str,
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Vec<Expr>, EvalError> {
+    match evaluate_expr(expr, env, depth + 1)? {
+        Expr::List(elements) => Ok(elements),
+        Expr::Nil => Ok(Vec::new()),
+        _ => Err(EvalError::IllegalArgument(name, "Argument must be a list")),
+    }
+}
+
+/// Evaluates the number of iterations, which must be a non-negative integer
+fn evaluate_count(
+    name: &'static str,
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<usize, EvalError> {
+    match evaluate_expr(expr, env, depth + 1)? {
+        Expr::Integer(count) if count >= 0 => Ok(count as usize),
+        _ => Err(EvalError::IllegalArgument(
+            name,
+            "Count must be a non-negative number",
+        )),
+    }
+}
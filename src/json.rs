@@ -0,0 +1,531 @@
+//! Minimal JSON values used by the editor and notebook protocols and by the
+//! `json-parse` and `json-stringify` built-ins
+
+use std::{iter::Peekable, str::Chars};
+
+use crate::{
+    arithmetic::as_float,
+    bigint::BigInt,
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+    string::evaluate_string,
+};
+
+/// Deepest nesting of arrays and objects allowed in a JSON text
+const MAX_NESTING_DEPTH: usize = 1024;
+
+/// Symbol starting the lists that stand for JSON objects, symbols cannot be
+/// written in JSON so these are never confused with arrays
+const OBJECT_TAG: &str = "object";
+
+/// A JSON value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// Number without a fraction or an exponent, kept exactly
+    Integer(BigInt),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members are kept in their original order
+    Object(Vec<(String, Json)>),
+}
+
+/// Returned when a text is not valid JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError(pub &'static str);
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid JSON: {}", self.0)
+    }
+}
+
+impl Json {
+    /// Parses a complete JSON text
+    pub fn parse(input: &str) -> Result<Json, JsonError> {
+        let mut chars = input.chars().peekable();
+
+        let value = parse_value(&mut chars, 0)?;
+
+        skip_whitespace(&mut chars);
+
+        match chars.next() {
+            None => Ok(value),
+            Some(_) => Err(JsonError("Trailing characters")),
+        }
+    }
+
+    /// Creates an object from its members
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Gets a member of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(member, _)| member == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Follows a path of object members
+    pub fn pointer(&self, path: &[&str]) -> Option<&Json> {
+        path.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Integer(number) => number.to_i64(),
+            Json::Number(number) if number.fract() == 0.0 => Some(*number as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(string: &str) -> Self {
+        Json::String(string.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(string: String) -> Self {
+        Json::String(string)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(number: i64) -> Self {
+        Json::Integer(BigInt::from_i128(number as i128))
+    }
+}
+
+impl From<bool> for Json {
+    fn from(boolean: bool) -> Self {
+        Json::Bool(boolean)
+    }
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(boolean) => write!(f, "{boolean}"),
+            Json::Integer(number) => write!(f, "{number}"),
+            // Floats keep a fraction or an exponent, so they are read back as floats
+            Json::Number(number) if number.is_finite() => write!(f, "{number:?}"),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(string) => write_string(f, string),
+            Json::Array(values) => {
+                write!(f, "[")?;
+
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write!(f, "{value}")?;
+                }
+
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Writes a string literal, escaping characters where needed
+fn write_string(f: &mut std::fmt::Formatter<'_>, string: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+
+    for c in string.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_value(chars: &mut Peekable<Chars>, depth: usize) -> Result<Json, JsonError> {
+    skip_whitespace(chars);
+
+    if matches!(chars.peek(), Some('{' | '[')) && depth >= MAX_NESTING_DEPTH {
+        return Err(JsonError("Nested too deeply"));
+    }
+
+    match chars.peek() {
+        Some('{') => {
+            chars.next();
+
+            let mut members = Vec::new();
+
+            skip_whitespace(chars);
+
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(Json::Object(members));
+            }
+
+            loop {
+                skip_whitespace(chars);
+
+                if chars.next() != Some('"') {
+                    return Err(JsonError("Expected a member name"));
+                }
+
+                let key = parse_string(chars)?;
+
+                skip_whitespace(chars);
+
+                if chars.next() != Some(':') {
+                    return Err(JsonError("Expected ':'"));
+                }
+
+                members.push((key, parse_value(chars, depth + 1)?));
+
+                skip_whitespace(chars);
+
+                match chars.next() {
+                    Some(',') => continue,
+                    Some('}') => return Ok(Json::Object(members)),
+                    _ => return Err(JsonError("Expected ',' or '}'")),
+                }
+            }
+        }
+        Some('[') => {
+            chars.next();
+
+            let mut values = Vec::new();
+
+            skip_whitespace(chars);
+
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Json::Array(values));
+            }
+
+            loop {
+                values.push(parse_value(chars, depth + 1)?);
+
+                skip_whitespace(chars);
+
+                match chars.next() {
+                    Some(',') => continue,
+                    Some(']') => return Ok(Json::Array(values)),
+                    _ => return Err(JsonError("Expected ',' or ']'")),
+                }
+            }
+        }
+        Some('"') => {
+            chars.next();
+
+            parse_string(chars).map(Json::String)
+        }
+        Some('t') => parse_literal(chars, "true", Json::Bool(true)),
+        Some('f') => parse_literal(chars, "false", Json::Bool(false)),
+        Some('n') => parse_literal(chars, "null", Json::Null),
+        Some(c) if *c == '-' || c.is_ascii_digit() => parse_number(chars),
+        Some(_) => Err(JsonError("Unexpected character")),
+        None => Err(JsonError("Unexpected end of input")),
+    }
+}
+
+/// Parses a number, which is `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE][+-]?[0-9]+)?`
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<Json, JsonError> {
+    let mut number = String::new();
+
+    if let Some(sign) = chars.next_if_eq(&'-') {
+        number.push(sign);
+    }
+
+    match chars.next_if(char::is_ascii_digit) {
+        // Leading zeros are not allowed
+        Some('0') => number.push('0'),
+        Some(digit) => {
+            number.push(digit);
+            push_digits(chars, &mut number);
+        }
+        None => return Err(JsonError("Invalid number")),
+    }
+
+    let integral = !matches!(chars.peek(), Some('.' | 'e' | 'E'));
+
+    if let Some(dot) = chars.next_if_eq(&'.') {
+        number.push(dot);
+
+        if push_digits(chars, &mut number) == 0 {
+            return Err(JsonError("Invalid number"));
+        }
+    }
+
+    if let Some(e) = chars.next_if(|c| matches!(c, 'e' | 'E')) {
+        number.push(e);
+
+        if let Some(sign) = chars.next_if(|c| matches!(c, '+' | '-')) {
+            number.push(sign);
+        }
+
+        if push_digits(chars, &mut number) == 0 {
+            return Err(JsonError("Invalid number"));
+        }
+    }
+
+    if integral {
+        return BigInt::parse(&number)
+            .map(Json::Integer)
+            .ok_or(JsonError("Invalid number"));
+    }
+
+    number
+        .parse::<f64>()
+        .map(Json::Number)
+        .map_err(|_| JsonError("Invalid number"))
+}
+
+/// Moves digits from the input to the number, returning how many there were
+fn push_digits(chars: &mut Peekable<Chars>, number: &mut String) -> usize {
+    let mut count = 0;
+
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        number.push(digit);
+        count += 1;
+    }
+
+    count
+}
+
+fn parse_literal(
+    chars: &mut Peekable<Chars>,
+    literal: &str,
+    value: Json,
+) -> Result<Json, JsonError> {
+    for expected in literal.chars() {
+        if chars.next() != Some(expected) {
+            return Err(JsonError("Invalid literal"));
+        }
+    }
+
+    Ok(value)
+}
+
+/// Parses the rest of a string literal, after the opening quote
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, JsonError> {
+    let mut string = String::new();
+
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(string),
+            Some('\\') => match chars.next() {
+                Some('"') => string.push('"'),
+                Some('\\') => string.push('\\'),
+                Some('/') => string.push('/'),
+                Some('b') => string.push('\u{8}'),
+                Some('f') => string.push('\u{c}'),
+                Some('n') => string.push('\n'),
+                Some('r') => string.push('\r'),
+                Some('t') => string.push('\t'),
+                Some('u') => {
+                    let high = parse_hex(chars)?;
+
+                    // Characters outside the basic plane are encoded as surrogate pairs
+                    let code = if (0xD800..0xDC00).contains(&high) {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            return Err(JsonError("Invalid surrogate pair"));
+                        }
+
+                        let low = parse_hex(chars)?;
+
+                        0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+                    } else {
+                        high
+                    };
+
+                    string.push(char::from_u32(code).ok_or(JsonError("Invalid escape"))?);
+                }
+                _ => return Err(JsonError("Invalid escape")),
+            },
+            Some(c) if c < ' ' => return Err(JsonError("Control character in string")),
+            Some(c) => string.push(c),
+            None => return Err(JsonError("Unterminated string")),
+        }
+    }
+}
+
+fn parse_hex(chars: &mut Peekable<Chars>) -> Result<u32, JsonError> {
+    let digits: String = chars.take(4).collect();
+
+    if digits.len() != 4 {
+        return Err(JsonError("Invalid escape"));
+    }
+
+    u32::from_str_radix(&digits, 16).map_err(|_| JsonError("Invalid escape"))
+}
+
+/// Evaluates `json-parse` built-in, converting a JSON text to Lisper values.
+/// Objects become lists of key and value pairs starting with the symbol
+/// `object`, arrays become lists and `null` becomes nil. Numbers written
+/// without a fraction or an exponent become exact integers, other numbers
+/// become floats.
+///
+/// Expected Lisper syntax:
+///
+/// ```(json-parse "{\"name\": \"lisper\", \"tags\": [1, 2]}")```
+pub fn evaluate_json_parse(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("json-parse".to_string(), 1));
+    }
+
+    let text = evaluate_string("json-parse", &list[1], env, depth)?;
+
+    Json::parse(&text)
+        .map(|json| to_expr(&json))
+        .map_err(|err| EvalError::IllegalArgument("json-parse", err.0))
+}
+
+/// Evaluates `json-stringify` built-in, converting a Lisper value to a JSON
+/// text. Lists starting with the symbol `object` followed by pairs with string
+/// keys become objects, other lists become arrays.
+///
+/// Expected Lisper syntax:
+///
+/// ```(json-stringify '(object ("name" "lisper") ("tags" (1 2))))```
+pub fn evaluate_json_stringify(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("json-stringify".to_string(), 1));
+    }
+
+    let value = evaluate_expr(&list[1], env, depth + 1)?;
+
+    Ok(Expr::String(to_json(&value)?.to_string()))
+}
+
+fn to_expr(json: &Json) -> Expr {
+    match json {
+        Json::Null => Expr::Nil,
+        Json::Bool(boolean) => Expr::Boolean(*boolean),
+        Json::Integer(number) => match number.to_i64() {
+            Some(number) => Expr::Integer(number),
+            None => Expr::BigInt(number.clone()),
+        },
+        Json::Number(number) => Expr::Float(*number),
+        Json::String(string) => Expr::String(string.clone()),
+        Json::Array(values) => Expr::List(values.iter().map(to_expr).collect()),
+        Json::Object(members) => Expr::List(
+            std::iter::once(Expr::Symbol(OBJECT_TAG.to_string()))
+                .chain(members.iter().map(|(key, value)| {
+                    Expr::List(vec![Expr::String(key.clone()), to_expr(value)])
+                }))
+                .collect(),
+        ),
+    }
+}
+
+fn to_json(expr: &Expr) -> Result<Json, EvalError> {
+    Ok(match expr {
+        Expr::Nil => Json::Null,
+        Expr::Boolean(boolean) => Json::Bool(*boolean),
+        Expr::Integer(number) => Json::from(*number),
+        Expr::BigInt(number) => Json::Integer(number.clone()),
+        Expr::Rational(_, _) | Expr::BigRational(_, _) => match as_float(expr) {
+            Some(number) if number.is_finite() => Json::Number(number),
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "json-stringify",
+                    "Value cannot be represented in JSON",
+                ))
+            }
+        },
+        Expr::Float(number) if number.is_finite() => Json::Number(*number),
+        Expr::String(string) => Json::String(string.clone()),
+        Expr::List(values) => match values.split_first() {
+            Some((Expr::Symbol(tag), members)) if tag == OBJECT_TAG => Json::Object(
+                members
+                    .iter()
+                    .map(|member| {
+                        let (key, value) = as_member(member).ok_or(EvalError::IllegalArgument(
+                            "json-stringify",
+                            "Members of an object must be pairs with a string key",
+                        ))?;
+
+                        Ok((key.clone(), to_json(value)?))
+                    })
+                    .collect::<Result<_, EvalError>>()?,
+            ),
+            _ => Json::Array(values.iter().map(to_json).collect::<Result<_, _>>()?),
+        },
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "json-stringify",
+                "Value cannot be represented in JSON",
+            ))
+        }
+    })
+}
+
+/// Returns the key and value of a pair that is a member of an object
+fn as_member(expr: &Expr) -> Option<(&String, &Expr)> {
+    match expr {
+        Expr::List(pair) => match &pair[..] {
+            [Expr::String(key), value] => Some((key, value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,136 @@
+//! Notebook kernel, a JSON-RPC loop evaluating code cells in one shared scope
+//!
+//! Every line of the input is a JSON-RPC 2.0 request, every line of the
+//! output is a response to one of them:
+//!
+//! ```text
+//! {"jsonrpc": "2.0", "id": 1, "method": "execute", "params": {"code": "(+ 1 2)"}}
+//! {"jsonrpc":"2.0","id":1,"result":{"stdout":"","value":"3"}}
+//! ```
+//!
+//! Errors carry the output printed before they occurred in `error.data.stdout`.
+
+use std::io::{BufRead, Write};
+
+use crate::{
+    eval::evaluate_source,
+    json::Json,
+    lexer::lex,
+    parser::parse_program_with_spans,
+    scope::{Output, PassableScope},
+};
+
+/// Error codes of failed cells
+const PARSE_ERROR: i64 = 1;
+const EVAL_ERROR: i64 = 2;
+
+/// Error codes defined by JSON-RPC
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Evaluates a code cell, returning its printed output and either its value or an error
+fn execute(code: &str, env: &mut PassableScope) -> (String, Result<String, (i64, String)>) {
+    let globals = env.borrow().globals();
+
+    let result = match lex(code) {
+        Err(lexer_error) => Err((PARSE_ERROR, format!("LEXER ERROR: {lexer_error}"))),
+        Ok(tokens) => match parse_program_with_spans(&mut tokens.into_iter().peekable()) {
+            Err(parser_error) => Err((PARSE_ERROR, format!("PARSER ERROR: {parser_error}"))),
+            Ok((program, source_map)) => match evaluate_source(&program, source_map, env) {
+                Ok(value) => Ok(value.to_string()),
+                Err(err) => Err((EVAL_ERROR, format!("EVAL ERROR: {err}"))),
+            },
+        },
+    };
+
+    let stdout = globals.borrow_mut().take_output();
+
+    (stdout, result)
+}
+
+fn error(id: Json, code: i64, message: String, data: Json) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Json::object([
+                ("code", code.into()),
+                ("message", message.into()),
+                ("data", data),
+            ]),
+        ),
+    ])
+}
+
+/// Runs the kernel until the input ends or `shutdown` is requested
+pub fn run_kernel(mut env: PassableScope) -> std::io::Result<()> {
+    let globals = env.borrow().globals();
+    globals.borrow_mut().output = Output::Captured(String::new());
+
+    let mut output = std::io::stdout().lock();
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = match Json::parse(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                let response = error(Json::Null, INVALID_REQUEST, err.to_string(), Json::Null);
+                writeln!(output, "{response}")?;
+                continue;
+            }
+        };
+
+        let id = request.get("id").cloned().unwrap_or(Json::Null);
+        let method = request.get("method").and_then(Json::as_str).unwrap_or("");
+
+        let response = match method {
+            "execute" => {
+                let code = request
+                    .pointer(&["params", "code"])
+                    .and_then(Json::as_str)
+                    .unwrap_or("");
+
+                match execute(code, &mut env) {
+                    (stdout, Ok(value)) => Json::object([
+                        ("jsonrpc", "2.0".into()),
+                        ("id", id),
+                        (
+                            "result",
+                            Json::object([("stdout", stdout.into()), ("value", value.into())]),
+                        ),
+                    ]),
+                    (stdout, Err((code, message))) => {
+                        error(id, code, message, Json::object([("stdout", stdout.into())]))
+                    }
+                }
+            }
+            "shutdown" => {
+                let response = Json::object([
+                    ("jsonrpc", "2.0".into()),
+                    ("id", id),
+                    ("result", Json::Null),
+                ]);
+                writeln!(output, "{response}")?;
+
+                return Ok(());
+            }
+            _ => error(
+                id,
+                METHOD_NOT_FOUND,
+                "Method not found".to_string(),
+                Json::Null,
+            ),
+        };
+
+        writeln!(output, "{response}")?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
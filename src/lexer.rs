@@ -1,17 +1,48 @@
 //! Lexer
 
+use std::{iter::Peekable, str::Chars};
+
+use crate::{
+    bigint::BigInt,
+    parser::READER_MACROS,
+    span::{Span, Spanned},
+};
+
 #[derive(Debug, Clone)]
 pub enum Token {
-    // S-expression delimiters
+    // S-expression delimiters, lists can be written in square brackets too
     OpenParen,
     CloseParen,
+    OpenBracket,
+    CloseBracket,
 
     // Number types
     Integer(i64),
+    BigInt(BigInt),
+    /// Boxed so that it does not make every token larger
+    Rational(Box<BigInt>, Box<BigInt>),
+    Float(f64),
+
+    // Text types
+    String(String),
+    /// A character literal like `#\a` or `#\space`
+    Char(char),
+    Bytes(Vec<u8>),
+    /// `#;`, comments out the expression following it
+    DatumComment,
+    /// `.` in front of the tail of a dotted pair, like `(a . b)`
+    Dot,
 
     // Binary types
     Boolean(bool),
 
+    // Absence of a value
+    Nil,
+
+    /// Prefix of a reader macro changing how the expression following it is
+    /// read, like `'` for `quote`
+    ReaderMacro(&'static str),
+
     // Built-ins
     If,
     BinaryOp(String),
@@ -19,34 +50,314 @@ pub enum Token {
     Symbol(String),
 }
 
-pub fn lex(input: &str) -> Vec<Token> {
-    let mut tokens: Vec<Token> = Vec::new();
+/// When the input cannot be split into tokens, `LexError` is returned with the
+/// position where the problem starts
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    /// A string or byte string literal is missing its closing quote
+    UnterminatedString(Span),
+
+    /// A block comment is missing its closing `|#`
+    UnterminatedComment(Span),
+
+    /// An escape sequence in a string literal is not known
+    InvalidEscape(String, Span),
+
+    /// A word starting like a number is not a valid number
+    InvalidNumber(String, Span),
+
+    /// A character literal names no known character
+    InvalidCharacter(String, Span),
+
+    /// A symbol starts with `#:`, which is reserved for generated symbols
+    ReservedSymbol(String, Span),
+}
 
-    let mut chars = input.chars().peekable();
+impl LexError {
+    /// Returns the position of the problem in the input
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnterminatedString(span)
+            | LexError::UnterminatedComment(span)
+            | LexError::InvalidEscape(_, span)
+            | LexError::InvalidNumber(_, span)
+            | LexError::InvalidCharacter(_, span)
+            | LexError::ReservedSymbol(_, span) => *span,
+        }
+    }
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexError::UnterminatedString(span) => {
+                write!(f, "Closing quote of string starting at {span} expected")
+            }
+            LexError::UnterminatedComment(span) => {
+                write!(f, "End of block comment starting at {span} expected")
+            }
+            LexError::InvalidEscape(escape, span) => {
+                write!(f, "Invalid escape sequence {escape} at {span}")
+            }
+            LexError::InvalidNumber(word, span) => write!(f, "Invalid number {word} at {span}"),
+            LexError::InvalidCharacter(name, span) => {
+                write!(f, "Invalid character literal #\\{name} at {span}")
+            }
+            LexError::ReservedSymbol(name, span) => {
+                write!(
+                    f,
+                    "Symbol {name} at {span} is reserved for generated symbols"
+                )
+            }
+        }
+    }
+}
+
+/// Start of the names of generated symbols, which cannot be read
+pub const GENSYM_PREFIX: &str = "#:";
+
+/// Built-in operators, lexed as `Token::BinaryOp`
+pub const OPERATORS: &[&str] = &[
+    "+",
+    "-",
+    "*",
+    "/",
+    "=",
+    "!=",
+    "eq?",
+    "equal?",
+    "<",
+    "<=",
+    ">",
+    ">=",
+    "and",
+    "or",
+    "not",
+    "mod",
+    "rem",
+    "expt",
+    "abs",
+    "min",
+    "max",
+    "floor",
+    "ceiling",
+    "round",
+    "truncate",
+    "exact->inexact",
+    "inexact->exact",
+    "gcd",
+    "lcm",
+    "sqrt",
+    "sin",
+    "cos",
+    "tan",
+    "log",
+    "exp",
+];
+
+/// Built-in keywords, lexed as `Token::Keyword`
+pub const KEYWORDS: &[&str] = &[
+    "print",
+    "def",
+    "defconst",
+    "defun",
+    "lambda",
+    "import",
+    "gensym",
+    "values",
+    "let-values",
+    "divmod",
+    "define-condition",
+    "signal",
+    "handler-case",
+    "condition-slot",
+    "condition-type",
+    "repeat",
+    "iterate",
+    "unfold",
+    "for/list",
+    "nil?",
+    "list",
+    "cons",
+    "car",
+    "cdr",
+    "quote",
+    "bytes",
+    "read-bytes",
+    "write-bytes",
+    "let",
+    "let*",
+    "dotimes",
+    "dolist",
+    "case",
+    "set!",
+    "quasiquote",
+    "unquote",
+    "unquote-splicing",
+    "eval",
+    "try",
+    "throw",
+    "error",
+    "assert",
+    "match",
+    "delay",
+    "force",
+    "stream-cons",
+    "stream-car",
+    "stream-cdr",
+    "stream-take",
+    "block",
+    "return-from",
+    "map",
+    "filter",
+    "reduce",
+    "len",
+    "concat",
+    "reverse",
+    "sort",
+    "nth",
+    "range",
+    "take",
+    "drop",
+    "take-while",
+    "drop-while",
+    "zip",
+    "flatten",
+    "member",
+    "contains?",
+    "assoc",
+    "fold-left",
+    "fold-right",
+    "any?",
+    "all?",
+    "count-if",
+    "string-append",
+    "string-length",
+    "substring",
+    "string-ref",
+    "string->number",
+    "number->string",
+    "trim",
+    "upcase",
+    "downcase",
+    "pad-left",
+    "pad-right",
+    "format",
+    "re-match",
+    "re-find-all",
+    "re-replace",
+    "read-file",
+    "write-file",
+    "append-file",
+    "println",
+    "write",
+    "eprint",
+    "eprintln",
+    "argv",
+    "getenv",
+    "setenv",
+    "exec",
+    "json-parse",
+    "json-stringify",
+    "base64-encode",
+    "base64-decode",
+    "sha256",
+    "md5",
+    "now",
+    "format-time",
+    "parse-time",
+];
+
+pub fn lex(input: &str) -> Result<Vec<Spanned<Token>>, LexError> {
+    lex_with_ends(input).map(|tokens| tokens.into_iter().map(|(token, _)| token).collect())
+}
+
+/// Lexes the input like `lex`, returning the offset in bytes where each token
+/// ends too
+pub fn lex_with_ends(input: &str) -> Result<Vec<(Spanned<Token>, usize)>, LexError> {
+    let mut tokens: Vec<Spanned<Token>> = Vec::new();
+    let mut ends: Vec<usize> = Vec::new();
+
+    let mut chars = Cursor::new(input);
 
     while let Some(&c) = chars.peek() {
+        // Tokens other than words end where the next one is looked for
+        ends.resize(tokens.len(), chars.span.offset);
+
+        let span = chars.span;
+
+        // Lexing prefixes of reader macros, like the quote shorthand
+        if let Some(prefix) = reader_macro(&mut chars) {
+            for _ in prefix.chars() {
+                chars.next();
+            }
+
+            tokens.push(Spanned::new(Token::ReaderMacro(prefix), span));
+            continue;
+        }
+
         match c {
             // Lexing S-expression delimiters
             '(' => {
                 chars.next();
-                tokens.push(Token::OpenParen)
+                tokens.push(Spanned::new(Token::OpenParen, span))
             }
             ')' => {
                 chars.next();
-                tokens.push(Token::CloseParen)
+                tokens.push(Spanned::new(Token::CloseParen, span))
+            }
+            '[' => {
+                chars.next();
+                tokens.push(Spanned::new(Token::OpenBracket, span))
+            }
+            ']' => {
+                chars.next();
+                tokens.push(Spanned::new(Token::CloseBracket, span))
+            }
+
+            // Lexing comments, block comments `#| ... |#` can be nested
+            '#' if matches!(chars.peek_second(), Some('|' | ';')) => {
+                chars.next();
+
+                if chars.next() == Some(';') {
+                    tokens.push(Spanned::new(Token::DatumComment, span));
+                } else if !skip_block_comment(&mut chars) {
+                    return Err(LexError::UnterminatedComment(span));
+                }
+            }
+
+            // Lexing character literals
+            '#' if chars.peek_second() == Some('\\') => {
+                chars.next();
+                chars.next();
+                tokens.push(Spanned::new(lex_char(&mut chars, span)?, span))
+            }
+
+            // Lexing string literals
+            '"' => {
+                chars.next();
+                tokens.push(Spanned::new(lex_string(&mut chars, span)?, span))
             }
 
             // Lex everything else
             _ => {
                 let mut word = String::new();
+                let mut byte_string = None;
 
                 while let Some(&c) = chars.peek() {
+                    // Byte strings are written as `b"..."`
+                    if c == '"' && word == "b" {
+                        chars.next();
+                        byte_string = Some(lex_bytes(&mut chars, span)?);
+                        break;
+                    }
+
                     if c.is_whitespace() {
                         chars.next();
                         break;
                     }
 
-                    if c == '(' || c == ')' {
+                    if matches!(c, '(' | ')' | '[' | ']') {
                         break;
                     }
 
@@ -54,30 +365,334 @@ pub fn lex(input: &str) -> Vec<Token> {
                     word.push(c);
                 }
 
+                if let Some(token) = byte_string {
+                    tokens.push(Spanned::new(token, span));
+                    continue;
+                }
+
                 if word.is_empty() {
                     continue;
                 }
 
+                // Words end before the whitespace that has been skipped after them
+                ends.push(span.offset + word.len());
+
                 let parsed_token: Token = match word.as_str() {
                     "if" => Token::If,
-                    "+" | "-" | "*" | "/" | "=" | "!=" | "<" | "<=" | ">" | ">=" | "and" | "or"
-                    | "not" => Token::BinaryOp(word),
+                    op if OPERATORS.contains(&op) => Token::BinaryOp(word),
                     "true" => Token::Boolean(true),
                     "false" => Token::Boolean(false),
-                    "print" | "def" | "defun" | "lambda" => Token::Keyword(word),
+                    "nil" => Token::Nil,
+                    "." => Token::Dot,
+                    keyword if KEYWORDS.contains(&keyword) => Token::Keyword(word),
                     _ => {
-                        if let Ok(int) = word.parse::<i64>() {
-                            Token::Integer(int)
+                        if let Some(int) = parse_integer(&word) {
+                            match int.to_i64() {
+                                Some(int) => Token::Integer(int),
+                                None => Token::BigInt(int),
+                            }
+                        } else if let Some((numerator, denominator)) = parse_rational(&word) {
+                            Token::Rational(Box::new(numerator), Box::new(denominator))
+                        } else if let Some(float) = parse_float(&word) {
+                            Token::Float(float)
+                        } else if starts_like_number(&word) {
+                            return Err(LexError::InvalidNumber(word, span));
+                        } else if word.starts_with(GENSYM_PREFIX) {
+                            return Err(LexError::ReservedSymbol(word, span));
                         } else {
                             Token::Symbol(word)
                         }
                     }
                 };
 
-                tokens.push(parsed_token);
+                tokens.push(Spanned::new(parsed_token, span));
+            }
+        }
+    }
+
+    ends.resize(tokens.len(), chars.span.offset);
+
+    Ok(tokens.into_iter().zip(ends).collect())
+}
+
+/// Characters of the source code, keeping track of the position of the next one
+struct Cursor<'a> {
+    chars: Peekable<Chars<'a>>,
+    span: Span,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Cursor {
+            chars: input.chars().peekable(),
+            span: Span {
+                line: 1,
+                col: 1,
+                offset: 0,
+            },
+        }
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    /// Returns the character after the next one
+    fn peek_second(&self) -> Option<char> {
+        let mut lookahead = self.chars.clone();
+        lookahead.next();
+        lookahead.next()
+    }
+
+    fn next_if(&mut self, func: impl FnOnce(&char) -> bool) -> Option<char> {
+        match self.chars.peek() {
+            Some(c) if func(c) => self.next(),
+            _ => None,
+        }
+    }
+
+    fn next_if_eq(&mut self, expected: &char) -> Option<char> {
+        self.next_if(|c| c == expected)
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+
+        self.span.offset += c.len_utf8();
+
+        if c == '\n' {
+            self.span.line += 1;
+            self.span.col = 1;
+        } else {
+            self.span.col += 1;
+        }
+
+        Some(c)
+    }
+}
+
+/// Skips a block comment after its opening `#|`, including the comments
+/// nested in it. Returns whether the comment was closed.
+fn skip_block_comment(chars: &mut Cursor) -> bool {
+    let mut depth = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' if chars.next_if_eq(&'|').is_some() => depth += 1,
+            '|' if chars.next_if_eq(&'#').is_some() => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return true;
+                }
             }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Returns the prefix of the reader macro the input continues with
+fn reader_macro(chars: &mut Cursor) -> Option<&'static str> {
+    let next = [chars.peek().copied(), chars.peek_second()];
+
+    READER_MACROS
+        .iter()
+        .map(|(prefix, _)| *prefix)
+        .find(|prefix| prefix.chars().zip(next).all(|(c, next)| Some(c) == next))
+}
+
+/// Lexes a byte string literal after its opening quote. Besides the escape
+/// sequences of strings, any byte can be written as `\xHH`.
+fn lex_bytes(chars: &mut Cursor, start: Span) -> Result<Token, LexError> {
+    let mut bytes = Vec::new();
+
+    loop {
+        let position = chars.span;
+
+        match chars.next() {
+            Some('"') => return Ok(Token::Bytes(bytes)),
+            Some('\\') => match chars.next() {
+                Some('x') => {
+                    let digits: String = chars.take(2).collect();
+
+                    match u8::from_str_radix(&digits, 16) {
+                        Ok(byte) if digits.len() == 2 => bytes.push(byte),
+                        _ => return Err(LexError::InvalidEscape(format!("\\x{digits}"), position)),
+                    }
+                }
+                Some(escaped) => {
+                    let c = lex_escape(escaped, chars)
+                        .map_err(|escape| LexError::InvalidEscape(escape, position))?;
+
+                    bytes.extend_from_slice(c.to_string().as_bytes())
+                }
+                None => break,
+            },
+            Some(c) => bytes.extend_from_slice(c.to_string().as_bytes()),
+            None => break,
+        }
+    }
+
+    Err(LexError::UnterminatedString(start))
+}
+
+/// Parses an integer literal with an optional sign, written in decimal or
+/// with a `0x`, `0o` or `0b` prefix in hexadecimal, octal or binary. Digits
+/// can be separated by underscores, like `1_000_000`.
+fn parse_integer(word: &str) -> Option<BigInt> {
+    let (sign, unsigned) = match word.strip_prefix(['-', '+']) {
+        Some(unsigned) => (&word[..1], unsigned),
+        None => ("", word),
+    };
+
+    let (radix, digits) = match unsigned.get(..2) {
+        Some("0x") => (16, &unsigned[2..]),
+        Some("0o") => (8, &unsigned[2..]),
+        Some("0b") => (2, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+
+    // Separators can only be placed between digits
+    if !digits
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+    {
+        return None;
+    }
+
+    BigInt::parse_radix(&format!("{sign}{}", digits.replace('_', "")), radix)
+}
+
+/// Whether a word starts like a number, with a digit after an optional sign
+/// or dot
+fn starts_like_number(word: &str) -> bool {
+    let unsigned = word.strip_prefix(['-', '+']).unwrap_or(word);
+    let unsigned = unsigned.strip_prefix('.').unwrap_or(unsigned);
+
+    unsigned.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Parses a rational literal like `1/3` or `-2/4`, the denominator must be positive
+fn parse_rational(word: &str) -> Option<(BigInt, BigInt)> {
+    let (numerator, denominator) = word.split_once('/')?;
+
+    if !denominator.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let numerator = BigInt::parse(numerator)?;
+    let denominator = BigInt::parse(denominator)?;
+
+    (!denominator.is_zero()).then_some((numerator, denominator))
+}
+
+/// Parses a decimal literal like `1.5`, `-.5`, `1e9` or `-2.5e-3`, which must
+/// contain a dot or an exponent
+fn parse_float(word: &str) -> Option<f64> {
+    let is_decimal = word.contains(['.', 'e', 'E'])
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))
+        && word.chars().any(|c| c.is_ascii_digit());
+
+    is_decimal.then(|| word.parse().ok()).flatten()
+}
+
+/// Lexes a character literal after its `#\`. Characters are written as
+/// themselves, or by their name when they are not visible.
+fn lex_char(chars: &mut Cursor, start: Span) -> Result<Token, LexError> {
+    let Some(first) = chars.next() else {
+        return Err(LexError::InvalidCharacter(String::new(), start));
+    };
+
+    let mut name = first.to_string();
+
+    if first.is_alphabetic() {
+        while let Some(c) = chars.next_if(|c| c.is_alphanumeric()) {
+            name.push(c);
         }
     }
 
-    tokens
+    match name.as_str() {
+        "space" => Ok(Token::Char(' ')),
+        "newline" => Ok(Token::Char('\n')),
+        "tab" => Ok(Token::Char('\t')),
+        "return" => Ok(Token::Char('\r')),
+        "nul" => Ok(Token::Char('\0')),
+        _ if name.chars().count() == 1 => Ok(Token::Char(first)),
+        _ => Err(LexError::InvalidCharacter(name, start)),
+    }
+}
+
+/// Lexes a string literal starting at `start` after its opening quote,
+/// resolving escape sequences
+fn lex_string(chars: &mut Cursor, start: Span) -> Result<Token, LexError> {
+    let mut string = String::new();
+
+    loop {
+        let position = chars.span;
+
+        match chars.next() {
+            Some('"') => return Ok(Token::String(string)),
+            Some('\\') => match chars.next() {
+                Some(escaped) => string.push(
+                    lex_escape(escaped, chars)
+                        .map_err(|escape| LexError::InvalidEscape(escape, position))?,
+                ),
+                None => break,
+            },
+            Some(c) => string.push(c),
+            None => break,
+        }
+    }
+
+    Err(LexError::UnterminatedString(start))
+}
+
+/// Resolves an escape sequence starting with `escaped` after the backslash:
+/// `\n`, `\t`, `\r`, `\0`, `\"`, `\\` or a Unicode code point in hexadecimal
+/// written as `\u{...}`. Returns the text of the sequence if it is unknown.
+fn lex_escape(escaped: char, chars: &mut Cursor) -> Result<char, String> {
+    match escaped {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '0' => Ok('\0'),
+        '"' => Ok('"'),
+        '\\' => Ok('\\'),
+        'u' => {
+            let mut text = "\\u".to_string();
+
+            if chars.next_if_eq(&'{').is_none() {
+                return Err(text);
+            }
+
+            text.push('{');
+
+            while let Some(digit) = chars.next_if(|c| c.is_ascii_hexdigit() && text.len() < 9) {
+                text.push(digit);
+            }
+
+            if chars.next_if_eq(&'}').is_none() {
+                return Err(text);
+            }
+
+            text.push('}');
+
+            u32::from_str_radix(&text[3..text.len() - 1], 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or(text)
+        }
+        _ => Err(format!("\\{escaped}")),
+    }
 }
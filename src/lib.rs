@@ -0,0 +1,1175 @@
+//! # Lisper
+//!
+//! A barebones LISP-family programming langauge interpreter.
+//!
+//! Created as a project for the Programming 1 course at MFF CUNI.
+//!
+//! ## Features
+//!
+//! ### Comments
+//!
+//! Block comments are written between `#|` and `|#` and can be nested, so
+//! code that already contains comments can be commented out. `#;` comments
+//! out the single expression following it.
+//!
+//! Usage:
+//! ```
+//! #| A block comment
+//!    #| with a nested one |# |#
+//! (list 1 #;(expensive 2) 3)
+//! ```
+//!
+//! ### Square brackets
+//!
+//! Lists can be written in square brackets as well as in parentheses, which
+//! makes binding forms like `let` easier to read. A list is closed by the
+//! same kind of delimiter it was opened with.
+//!
+//! Usage:
+//! ```
+//! (let [(x 1) (y 2)] (+ x y))
+//! ```
+//!
+//! ### Programs
+//!
+//! A file holds any number of top-level expressions, lists or atoms like
+//! `5` or `x`, which are evaluated in order. Likewise, every expression typed
+//! on a line of the REPL is evaluated and its value printed. Errors of a file
+//! are reported with the line and column of the innermost expression they are
+//! raised in, or of the call of the function they are raised in.
+//!
+//! Usage:
+//! ```
+//! (def x 1)
+//! (print x)
+//! ```
+//!
+//! ### Numbers
+//!
+//! Lisper handles integers, exact rational numbers and floating-point numbers.
+//! Integers have arbitrary precision, they never overflow. Besides decimal,
+//! they are written in hexadecimal, octal or binary with the prefixes `0x`,
+//! `0o` and `0b`, and their digits can be separated by underscores.
+//! Rationals are written as `1/3` and are always kept in lowest terms, their
+//! numerators and denominators have arbitrary precision too. Floats
+//! are written with a decimal dot or an exponent, like `1.5`, `.5`, `1e9` or
+//! `-2.5e-3`. When numbers of different types are mixed in arithmetic,
+//! integers are promoted to rationals and rationals to floats.
+//!
+//! Usage:
+//! ```
+//! (+ 1 2)
+//! (* 1 2 3 4)
+//! (+ 1/3 1/6)
+//! (+ 1 2.5)
+//! (+ 0xff 0b1010 1_000_000)
+//! ```
+//!
+//! ### Byte strings
+//!
+//! Binary data is held in byte strings, written as `b"..."`. Besides the
+//! escape sequences of strings, any byte can be written as `\xHH`. `bytes`
+//! creates a byte string from numbers between 0 and 255 or from the UTF-8
+//! encoding of a string. Files are read and written using `read-bytes` and
+//! `write-bytes`, failures are signalled as `io-error`.
+//!
+//! Usage:
+//! ```
+//! > (bytes 72 105 0)
+//! b"Hi\x00"
+//! > (write-bytes "data.bin" b"\x89PNG")
+//! nil
+//! > (read-bytes "data.bin")
+//! b"\x89PNG"
+//! ```
+//!
+//! `base64-encode` encodes a byte string, or a string as UTF-8, in base64 and
+//! `base64-decode` decodes it back into a byte string.
+//!
+//! Usage:
+//! ```
+//! > (base64-encode b"Hi\x00")
+//! "SGkA"
+//! > (base64-decode "SGkA")
+//! b"Hi\x00"
+//! ```
+//!
+//! `sha256` and `md5` hash a byte string, or a string as UTF-8, and return the
+//! digest as a hexadecimal string.
+//!
+//! Usage:
+//! ```
+//! > (sha256 "abc")
+//! "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+//! > (md5 (read-bytes "data.bin"))
+//! "3bdaf5969285188ac756c339f69f5c79"
+//! ```
+//!
+//! ### Nil
+//!
+//! `nil` is the absence of a value. It is returned by forms that are only
+//! evaluated for their effect, like `def` or `import`, and can be stored in
+//! variables like any other value. `nil?` checks whether a value is `nil`.
+//!
+//! Usage:
+//! ```
+//! > (def nothing nil)
+//! nil
+//! > (nil? nothing)
+//! true
+//! > (nil? ())
+//! false
+//! ```
+//!
+//! ### Strings
+//!
+//! Strings are written in double quotes. A newline, a tab, a carriage return,
+//! a null character, a quote and a backslash are written as `\n`, `\t`, `\r`,
+//! `\0`, `\"` and `\\`, any character can be written as its code point in
+//! hexadecimal like `\u{1F600}`. Other escape sequences are an error.
+//! `println` outputs strings without quotes, strings are compared with `=`.
+//!
+//! Characters are strings of length one. They can also be written as `#\a`,
+//! or by their name as `#\space`, `#\newline`, `#\tab`, `#\return` and
+//! `#\nul`.
+//!
+//! Usage:
+//! ```
+//! > (println "Hello, \"world\"!")
+//! Hello, "world"!
+//! > (= "abc" "abc")
+//! true
+//! > (= (string-ref "abc" 0) #\a)
+//! true
+//! ```
+//!
+//! `string-append` joins strings and `string-length` returns the number of
+//! characters of a string. `substring` returns the characters from a start
+//! index up to an end index (excluded, the end of the string by default) and
+//! `string-ref` the character at an index, as a string. Indices count
+//! characters rather than bytes, starting from zero.
+//!
+//! Usage:
+//! ```
+//! > (string-append "Hello, " "world")
+//! "Hello, world"
+//! > (string-length "héllo")
+//! 5
+//! > (substring "Hello, world" 0 5)
+//! "Hello"
+//! > (string-ref "héllo" 1)
+//! "é"
+//! ```
+//!
+//! `trim` removes whitespace around a string, `upcase` and `downcase` convert
+//! it to upper or lower case. `pad-left` and `pad-right` extend a string to a
+//! width by adding a padding character (a space by default) before or after it.
+//!
+//! Usage:
+//! ```
+//! > (trim "  padded  ")
+//! "padded"
+//! > (upcase "Hello")
+//! "HELLO"
+//! > (pad-left "42" 5 "0")
+//! "00042"
+//! ```
+//!
+//! `format` fills the directives of a template with its arguments. `~a`
+//! inserts a value as `print` outputs it, `~s` as it is written in code with
+//! strings quoted, `~%` inserts a newline and `~~` a tilde. With `true` before
+//! the template, the result is printed instead of returned.
+//!
+//! Usage:
+//! ```
+//! > (format "x=~a y=~s" 1 "two")
+//! "x=1 y=\"two\""
+//! > (format true "~a apples~%" 3)
+//! 3 apples
+//! nil
+//! ```
+//!
+//! Regular expressions are matched using `re-match`, which returns the first
+//! match and its groups (`nil` for groups that did not participate) or
+//! `false`. `re-find-all` returns all matches, as strings when the pattern has
+//! no groups. `re-replace` replaces all matches, the replacement refers to
+//! the groups as `$1` to `$9` and to the whole match as `$0`. Patterns support
+//! classes like `[a-z]` and `\d`, `\w`, `\s`, the anchors `^`, `$` and `\b`,
+//! groups, alternation and the (optionally lazy) quantifiers `*`, `+`, `?`
+//! and `{n,m}`. Matching takes time linear in the length of the string.
+//! Backslashes of patterns are doubled in string literals, like `"\\d+"`.
+//!
+//! Usage:
+//! ```
+//! > (re-match "(\\w+)@(\\w+)" "mail user@example")
+//! ("user@example" "user" "example")
+//! > (re-find-all "\\d+" "1 apple, 20 pears")
+//! ("1" "20")
+//! > (re-replace "(\\w+)@(\\w+)" "user@example" "$2 at $1")
+//! "example at user"
+//! ```
+//!
+//! Text files are read into a string using `read-file`. `write-file` replaces
+//! the content of a file with a string and `append-file` adds a string to its
+//! end. Failures, including files that are not valid UTF-8, are signalled as
+//! `io-error`.
+//!
+//! Usage:
+//! ```
+//! > (write-file "notes.txt" "first\n")
+//! nil
+//! > (append-file "notes.txt" "second\n")
+//! nil
+//! > (read-file "notes.txt")
+//! "first\nsecond\n"
+//! ```
+//!
+//! `string->number` parses a number written as in Lisper code and
+//! `number->string` writes one. Both take an optional radix between 2 and 36
+//! for integers written in other bases. Strings that are not numbers are
+//! signalled as `illegal-argument`.
+//!
+//! Usage:
+//! ```
+//! > (string->number "1/3")
+//! 1/3
+//! > (string->number "ff" 16)
+//! 255
+//! > (number->string 255 2)
+//! "11111111"
+//! ```
+//!
+//! `json-parse` reads a JSON text into Lisper values and `json-stringify`
+//! writes one. Arrays become lists, `null` becomes `nil` and objects become
+//! lists starting with the symbol `object` followed by key and value pairs,
+//! which can be looked up using `assoc` on the `cdr`. Numbers with a fraction
+//! or an exponent become floats and other numbers exact integers. Only lists
+//! starting with `object` are written as objects, so every JSON text is read
+//! back unchanged.
+//!
+//! Usage:
+//! ```
+//! > (json-parse "{\"name\": \"lisper\", \"tags\": [1, null]}")
+//! (object ("name" "lisper") ("tags" (1 nil)))
+//! > (assoc "name" (cdr (json-parse "{\"name\": \"lisper\"}")))
+//! ("name" "lisper")
+//! > (json-stringify '(object ("id" 7) ("ok" true)))
+//! "{\"id\":7,\"ok\":true}"
+//! ```
+//!
+//! ### Built-in functions
+//!
+//! #### Arithmetic operations
+//!
+//! ##### `+`
+//!
+//! Addition on numbers
+//!
+//! Usage:
+//! ```
+//! > (+ 1 2)
+//! 3
+//!
+//! > (+ 2 4 6)
+//! 12
+//!
+//! > (+ 5 -5)
+//! 0
+//! ```
+//!
+//! ##### `-`
+//!
+//! Subtraction on numbers, a single number is negated
+//!
+//! Usage:
+//! ```
+//! > (- 1 2)
+//! -1
+//!
+//! > (- 5 5)
+//! 0
+//!
+//! > (- 5)
+//! -5
+//! ```
+//!
+//! ##### `*`
+//!
+//! Multiplication on numbers
+//!
+//! Usage:
+//! ```
+//! > (* 1 2)
+//! 2
+//!
+//! > (* 0 3 4 6)
+//! 0
+//! ```
+//!
+//! ##### `/`
+//!
+//! Exact division on integers and rationals, resulting in a rational when the
+//! result is not whole. Division of floats when any of the numbers is a float.
+//! A single number results in its reciprocal.
+//!
+//! Usage:
+//! ```
+//! > (/ 1 2)
+//! 1/2
+//!
+//! > (/ 12 6 2)
+//! 1
+//!
+//! > (/ 1 2.0)
+//! 0.5
+//!
+//! > (/ 4)
+//! 1/4
+//! ```
+//!
+//! ##### `mod` and `rem`
+//!
+//! Remainder after division of two numbers, which is exact unless one of them
+//! is a float. The result of `mod` has the sign of the divisor, the result of
+//! `rem` the sign of the dividend.
+//!
+//! Usage:
+//! ```
+//! > (mod -7 3)
+//! 2
+//!
+//! > (rem -7 3)
+//! -1
+//!
+//! > (mod 7/2 1)
+//! 1/2
+//! ```
+//!
+//! ##### `expt`
+//!
+//! Raises a number to a power. Exact numbers raised to an integer stay exact,
+//! other powers are computed with floats.
+//!
+//! Usage:
+//! ```
+//! > (expt 2 100)
+//! 1267650600228229401496703205376
+//!
+//! > (expt 2 -2)
+//! 1/4
+//!
+//! > (expt 2 0.5)
+//! 1.4142135623730951
+//! ```
+//!
+//! ##### `abs`, `min` and `max`
+//!
+//! Absolute value of a number, and the smallest or the largest of any number
+//! of numbers.
+//!
+//! Usage:
+//! ```
+//! > (abs -5)
+//! 5
+//!
+//! > (min 3 1/2 2.5)
+//! 1/2
+//!
+//! > (max 3 1/2 2.5)
+//! 3
+//! ```
+//!
+//! ##### `floor`, `ceiling`, `round` and `truncate`
+//!
+//! Rounding of a number to an integer, down, up, to the nearest one (halves
+//! to the even one) and towards zero. `exact->inexact` converts a number to a
+//! float, `inexact->exact` converts a float to the exact number it represents.
+//!
+//! Usage:
+//! ```
+//! > (floor -7/2)
+//! -4
+//!
+//! > (round 2.5)
+//! 2
+//!
+//! > (inexact->exact 0.25)
+//! 1/4
+//! ```
+//!
+//! ##### `gcd` and `lcm`
+//!
+//! Greatest common divisor and least common multiple of any number of integers.
+//! Without arguments, `gcd` returns 0 and `lcm` returns 1.
+//!
+//! Usage:
+//! ```
+//! > (gcd 12 18 8)
+//! 2
+//!
+//! > (lcm 4 6 10)
+//! 60
+//! ```
+//!
+//! ##### `sqrt`, `sin`, `cos`, `tan`, `log` and `exp`
+//!
+//! Square root, trigonometric functions, natural logarithm and exponential of
+//! a number, always resulting in a float.
+//!
+//! Usage:
+//! ```
+//! > (sqrt 16)
+//! 4.0
+//!
+//! > (log (exp 2))
+//! 2.0
+//! ```
+//!
+//! #### Logic operations and comparison
+//!
+//! ##### `and`
+//!
+//! Logic `and`
+//!
+//! Usage:
+//! ```
+//! > (and true false)
+//! false
+//!
+//! > (and false)
+//! false
+//! ```
+//!
+//! ##### `or`
+//!
+//! Logic `or`
+//!
+//! Usage:
+//! ```
+//! > (or false false)
+//! false
+//!
+//! > (or false)
+//! false
+//! ```
+//!
+//! ##### `not`
+//!
+//! Logical negation
+//!
+//! Usage:
+//! ```
+//! > (not true)
+//! false
+//!
+//! > (not (= 1 2))
+//! true
+//! ```
+//!
+//! ##### `=`
+//!
+//! Equals
+//!
+//! Usage:
+//! ```
+//! > (= 2 2 (+ 2 0) (- 4 2))
+//! true
+//! ```
+//!
+//! `=` compares values structurally, like `equal?`, but numbers are equal when
+//! they have the same value, so `(= 2 2.0)` is true while `(equal? 2 2.0)` is not.
+//!
+//! ##### `eq?` and `equal?`
+//!
+//! `equal?` compares values structurally, two lists are equal when all of
+//! their elements are equal. `eq?` compares values by identity. Numbers,
+//! booleans and symbols are identical when they are equal, but lists are only
+//! identical when both are empty.
+//!
+//! Usage:
+//! ```
+//! > (equal? (1 2) (1 2))
+//! true
+//! > (eq? (1 2) (1 2))
+//! false
+//! > (eq? 4 (+ 2 2))
+//! true
+//! ```
+//!
+//! ##### `!=`
+//!
+//! Not equals
+//!
+//! Usage:
+//! ```
+//! > (!= 2 (+ 1 2))
+//! false
+//! ```
+//!
+//! ##### `<`, `<=`, `>`, `>=`
+//!
+//! Comparison operators on numbers
+//!
+//! Usage:
+//! ```
+//! > (< 1 2 3)
+//! true
+//!
+//! > (< 2 5 3)
+//! false
+//!
+//! > (>= 8 6 (+ 3 3) 2)
+//! true
+//! ```
+//!
+//! #### Control flow
+//!
+//! ##### If
+//!
+//! Conditional execution of expression. Without the `<if-false>` branch,
+//! `nil` is returned when the condition is false.
+//!
+//! `(if <condition> <if-true> <if-false>)`
+//!
+//! Usage:
+//! ```
+//! > (if (= 4 (+ 2 2)) 42 0)
+//! 42
+//! > (if (= 4 5) 42)
+//! nil
+//! ```
+//!
+//! ##### Case
+//!
+//! Chooses a clause by the value of an expression. Keys are not evaluated,
+//! they can be numbers, symbols, strings, booleans or `nil`, and a clause can
+//! have a list of several keys. The `else` clause is used when no key matches,
+//! `nil` is returned without it.
+//!
+//! `(case <expr> (<key> <body>...) ((<key> <key>...) <body>...) (else <body>...))`
+//!
+//! Usage:
+//! ```
+//! > (case (* 2 3) (1 'one) ((2 4 6) 'even) (else 'odd))
+//! even
+//! ```
+//!
+//! ##### Match
+//!
+//! Evaluates the first clause whose pattern matches the value of an
+//! expression. Symbols in patterns are bound to the matched values, `_`
+//! matches anything without binding it. Atoms and quoted data match equal
+//! values and lists match lists of the same length, a pattern after `&rest`
+//! matches the remaining elements. `nil` is returned when nothing matches.
+//!
+//! `(match <expr> (<pattern> <body>...)...)`
+//!
+//! Usage:
+//! ```
+//! > (match '(rect 3 4) (('circle r) (* 3 r r)) (('rect w h) (* w h)) (_ 0))
+//! 12
+//! > (match '(1 2 3) ((first &rest others) others))
+//! (2 3)
+//! ```
+//!
+//! ##### Block
+//!
+//! `(block <name> <body>...)` evaluates its body and returns the value of the
+//! last expression. `(return-from <name> <value>)` leaves the innermost block
+//! with the name early, even from functions called inside of it.
+//!
+//! Usage:
+//! ```
+//! > (block search (dolist (x '(1 12 3)) (if (> x 10) (return-from search x) nil)) 'none)
+//! 12
+//! ```
+//!
+//! ##### Truthiness
+//!
+//! By default, conditions of `if` and arguments of `and`, `or` and `not` have
+//! to be booleans. When Lisper is started with `--truthy`, `false`, the empty
+//! list and `nil` are false and everything else is true, like
+//! in most Lisps. `and` and `or` then evaluate only as many arguments as
+//! needed and return the last evaluated one.
+//!
+//! Usage (with `--truthy`):
+//! ```
+//! > (if () 1 2)
+//! 2
+//! > (or false 5)
+//! 5
+//! > (not nil)
+//! true
+//! ```
+//!
+//! #### Variables
+//!
+//! Variable definition is available using the `def` keyword.
+//!
+//! Usage:
+//! ```
+//! > (def x 10)
+//! nil
+//! > (def y 20)
+//! nil
+//! > (+ x y)
+//! 30
+//! ```
+//!
+//! Constants are defined using the `defconst` keyword and cannot be redefined.
+//!
+//! Usage:
+//! ```
+//! > (defconst pi 3)
+//! nil
+//! > (def pi 4)
+//! EVAL ERROR: Cannot redefine constant: pi
+//! ```
+//!
+//! An existing variable is changed using `set!`. While `def` always defines
+//! the variable in the current scope, `set!` changes it in the scope where it
+//! was defined, eg. from inside of a function. Undefined variables cannot be set.
+//!
+//! Usage:
+//! ```
+//! > (def count 0)
+//! nil
+//! > (defun bump (lambda () (set! count (+ count 1))))
+//! nil
+//! > (bump)
+//! nil
+//! > (println count)
+//! 1
+//! 1
+//! ```
+//!
+//! Local variables are bound using `let`, they are only visible inside of its
+//! body. The values are evaluated before any of them is bound. `let*` binds
+//! them one after another, so that later values can use the earlier ones.
+//!
+//! Usage:
+//! ```
+//! > (let ((x 1) (y 2)) (+ x y))
+//! 3
+//! > (let* ((x 1) (y (+ x 1))) (* x y))
+//! 2
+//! ```
+//!
+//! Both `def` and `let` can take a list of variables instead of a single one,
+//! binding them to the elements of a list value. The lists can be nested and
+//! the last variable can be preceded by `&rest`, like parameters of functions.
+//!
+//! Usage:
+//! ```
+//! > (def (a b) '(1 2))
+//! nil
+//! > (let (((x &rest others) '(1 2 3))) others)
+//! (2 3)
+//! ```
+//!
+//! #### Functions
+//!
+//! Functions can be defined using the `defun` and `lambda` keywords.
+//!
+//! Usage:
+//! ```
+//! > (defun double (lambda (x) (* x 2)))
+//! nil
+//! > (double 4)
+//! 8
+//! ```
+//!
+//! The parameters and the body can also follow the name directly. A body of
+//! several expressions evaluates them in order and returns the last value.
+//!
+//! Usage:
+//! ```
+//! > (defun square (x) (* x x))
+//! nil
+//! > (defun greet (name) (print "Hello") (println name))
+//! nil
+//! ```
+//!
+//! Functions are first-class values. They can be passed as arguments, stored
+//! in variables and called through the name of the parameter or variable.
+//!
+//! Usage:
+//! ```
+//! > (defun twice (lambda (f x) (f (f x))))
+//! nil
+//! > (twice double 3)
+//! 12
+//! > (twice (lambda (x) (+ x 1)) 3)
+//! 5
+//! ```
+//!
+//! Functions can also be returned from functions. They keep access to the
+//! variables of the scope they were created in. Any expression evaluating to
+//! a function can be called.
+//!
+//! Usage:
+//! ```
+//! > (defun make-adder (lambda (x) (lambda (y) (+ x y))))
+//! nil
+//! > ((make-adder 3) 4)
+//! 7
+//! > ((lambda (x) (* x 2)) 5)
+//! 10
+//! ```
+//!
+//! The last parameter can be preceded by `&rest`, it is then bound to a list
+//! of all remaining arguments.
+//!
+//! Usage:
+//! ```
+//! > (defun tag (lambda (name &rest fields) (cons name fields)))
+//! nil
+//! > (tag 'point 1 2)
+//! (point 1 2)
+//! ```
+//!
+//! Calls in tail position do not count towards the recursion limit, so
+//! tail-recursive functions can run for any number of iterations. The body of
+//! a function is in tail position and so are the branches of `if`, the last
+//! expression of `let`, `let*`, `block` and of the bodies of `case` and
+//! `match` clauses, and the last argument of `and` and `or` with `--truthy`.
+//! Without it, `and` and `or` check that every argument is a boolean, so
+//! their arguments are not in tail position.
+//!
+//! Usage:
+//! ```
+//! > (defun count-down (lambda (n) (if (= n 0) 'done (count-down (- n 1)))))
+//! nil
+//! > (count-down 100000)
+//! done
+//! ```
+//!
+//! #### Lists
+//!
+//! Lists are built at runtime using `list` and `cons`, which prepends a value
+//! to a list (or to `nil`). `car` returns the first element of a non-empty
+//! list and `cdr` the rest of it. `len` returns the number of elements of a
+//! list, characters of a string or bytes of a byte string. `concat` joins
+//! any number of lists, or of strings, into a new one, and `reverse` returns
+//! a list or a string in reverse order. `nth` returns the element at an index
+//! counted from zero and signals `index-out-of-range` outside of the list.
+//! `take` returns the first `n` elements of a list and `drop` the rest of it.
+//! `zip` pairs up the elements of lists at the same index, stopping at the
+//! end of the shortest one. `flatten` splices nested lists into the
+//! surrounding one, optionally only up to a given depth.
+//!
+//! `member` returns the rest of a list starting with the first element equal
+//! to a value, or `false` if there is none. `contains?` checks whether a list
+//! has such an element, or whether a string contains a substring. `assoc`
+//! looks up a key in an association list, a list of pairs starting with their
+//! key, and returns the first matching pair or `false`.
+//!
+//! Usage:
+//! ```
+//! > (list 1 (+ 1 1) 3)
+//! (1 2 3)
+//! > (cons 0 (list 1 2))
+//! (0 1 2)
+//! > (car (list 1 2 3))
+//! 1
+//! > (cdr (list 1 2 3))
+//! (2 3)
+//! > (len (list 1 2 3))
+//! 3
+//! > (len "héllo")
+//! 5
+//! > (concat (list 1 2) (list 3) nil)
+//! (1 2 3)
+//! > (concat "Hello, " "world")
+//! "Hello, world"
+//! > (reverse (list 1 2 3))
+//! (3 2 1)
+//! > (nth (list 'a 'b 'c) 1)
+//! b
+//! > (take 2 (list 1 2 3))
+//! (1 2)
+//! > (drop 2 (list 1 2 3))
+//! (3)
+//! > (zip (list 1 2 3) (list 'a 'b))
+//! ((1 a) (2 b))
+//! > (flatten '(1 (2 (3 4)) ()))
+//! (1 2 3 4)
+//! > (flatten '(1 (2 (3 4))) 1)
+//! (1 2 (3 4))
+//! > (member 2 (list 1 2 3))
+//! (2 3)
+//! > (contains? "haystack" "st")
+//! true
+//! > (assoc 'b '((a 1) (b 2)))
+//! (b 2)
+//! ```
+//!
+//! #### Quoting
+//!
+//! `quote` returns its argument without evaluating it, so that symbols and
+//! lists can be used as data. `'x` is a shorthand for `(quote x)`.
+//!
+//! Usage:
+//! ```
+//! > (quote (a b c))
+//! (a b c)
+//! > '(1 (+ 1 1))
+//! (1 (+ 1 1))
+//! > (car '(first second))
+//! first
+//! ```
+//!
+//! `quasiquote` (written as a backquote) also returns a template without
+//! evaluating it, except for the parts marked with `unquote` (`,`), which are
+//! replaced by their values. `unquote-splicing` (`,@`) inserts the elements of
+//! a list into the surrounding one.
+//!
+//! Usage:
+//! ```
+//! > (def xs '(2 3))
+//! nil
+//! > `(1 ,(+ 2 2) ,@xs)
+//! (1 4 2 3)
+//! ```
+//!
+//! Quoted code is evaluated in the current scope using `eval`.
+//!
+//! Usage:
+//! ```
+//! > (eval '(+ 1 2))
+//! 3
+//! > (eval `(* ,(car xs) 10))
+//! 20
+//! ```
+//!
+//! #### Iteration
+//!
+//! Common loops are available as built-ins, so that they do not hit the
+//! recursion limit. Functions are passed either by their name or as a
+//! `lambda` expression.
+//!
+//! - `(repeat n x)` creates a list of `n` copies of `x`
+//! - `(range start end step)` creates a list of integers from `start` up to
+//!   `end` (excluded), `start` defaults to `0` and `step` to `1`
+//! - `(iterate f x n)` creates a list of `x`, `(f x)`, `(f (f x))`, ... with `n` elements
+//! - `(unfold stop? f next seed)` collects `(f seed)` and continues with
+//!   `(next seed)` until `(stop? seed)` holds
+//! - `(for/list (x list) body)` collects the values of `body` for every `x`
+//!   in `list`, a number `n` iterates from `0` to `n - 1`
+//! - `(dotimes (i n) body)` evaluates `body` for every `i` from `0` to `n - 1`
+//! - `(dolist (x list) body)` evaluates `body` for every `x` in `list`
+//! - `(map f list ...)` applies `f` to every element, with multiple lists `f`
+//!   takes an element of each and the result is as long as the shortest list
+//! - `(filter pred? list)` keeps the elements for which `pred?` holds
+//! - `(any? pred? list)` and `(all? pred? list)` check whether `pred?` holds
+//!   for some or for all elements, `(count-if pred? list)` counts the elements
+//!   it holds for
+//! - `(take-while pred? list)` returns the leading elements for which `pred?`
+//!   holds and `(drop-while pred? list)` the elements after them
+//! - `(reduce f init list)` combines the elements from the left starting with
+//!   `init`, without `init` the first element is used
+//! - `(fold-left f init list)` calls `(f acc x)` for every `x` from the first
+//!   one on and `(fold-right f init list)` calls `(f x acc)` from the last one on,
+//!   starting with `init` as `acc`
+//! - `(sort list)` sorts numbers or strings in ascending order, `(sort less? list)`
+//!   sorts using a comparison that holds when its first argument goes first.
+//!   Equal elements keep their order.
+//!
+//! Operators and other built-ins can be passed as the function too. They are
+//! functions wherever they are used as a value, so they can also be stored in
+//! variables, eg. `(def add +)`.
+//!
+//! Usage:
+//! ```
+//! > (repeat 3 0)
+//! (0 0 0)
+//! > (range 5)
+//! (0 1 2 3 4)
+//! > (range 10 0 -3)
+//! (10 7 4 1)
+//! > (iterate double 1 5)
+//! (1 2 4 8 16)
+//! > (unfold (lambda (x) (> x 3)) double (lambda (x) (+ x 1)) 1)
+//! (2 4 6)
+//! > (for/list (x (1 2 3)) (* x x))
+//! (1 4 9)
+//! > (dolist (x '(a b)) (println x))
+//! a
+//! b
+//! nil
+//! > (map (lambda (x y) (+ x y)) '(1 2 3) '(10 20))
+//! (11 22)
+//! > (filter (lambda (x) (> x 1)) '(1 2 3))
+//! (2 3)
+//! > (any? (lambda (x) (> x 2)) '(1 2 3))
+//! true
+//! > (count-if (lambda (x) (> x 1)) '(1 2 3))
+//! 2
+//! > (reduce (lambda (acc x) (* acc x)) 1 '(1 2 3 4))
+//! 24
+//! > (fold-right (lambda (x acc) (cons (* x 10) acc)) nil '(1 2 3))
+//! (10 20 30)
+//! > (sort '(3 1 2))
+//! (1 2 3)
+//! > (sort (lambda (a b) (> a b)) '(3 1 2))
+//! (3 2 1)
+//! > (reduce + 0 '(1 2 3))
+//! 6
+//! ```
+//!
+//! #### Lazy evaluation
+//!
+//! `(delay <expr>)` creates a promise, which evaluates the expression only
+//! once its value is needed. `force` evaluates it the first time and remembers
+//! the value, forcing it again returns the same value. Other values are
+//! returned by `force` as they are.
+//!
+//! Usage:
+//! ```
+//! > (def p (delay (println "evaluated")))
+//! nil
+//! > (force p)
+//! evaluated
+//! "evaluated"
+//! > (force p)
+//! "evaluated"
+//! ```
+//!
+//! Streams are lazy lists, possibly infinite. `(stream-cons <first> <rest>)`
+//! evaluates only the first element, the rest of the stream is evaluated once
+//! `stream-cdr` needs it. `stream-car` returns the first element,
+//! `(stream-take <stream> <n>)` a list with at most `n` first elements. `nil`
+//! is the empty stream.
+//!
+//! Usage:
+//! ```
+//! > (defun naturals (lambda (n) (stream-cons n (naturals (+ n 1)))))
+//! nil
+//! > (stream-take (naturals 0) 5)
+//! (0 1 2 3 4)
+//! > (stream-car (stream-cdr (naturals 0)))
+//! 1
+//! ```
+//!
+//! #### Modules
+//!
+//! Code from other files can be loaded using the `import` keyword. A module is
+//! either a path string or a bare name, which is resolved to `<name>.lpr` (or
+//! `<name>.lisp`) next to the importing file. Every module is evaluated only
+//! once, importing it again does nothing.
+//!
+//! Usage:
+//! ```
+//! > (import "utils.lpr")
+//! nil
+//! > (import utils)
+//! nil
+//! ```
+//!
+//! To avoid name collisions, a module can be imported under a namespace using
+//! `:as`. Its definitions are then accessible as `<namespace>/<name>`.
+//!
+//! Usage:
+//! ```
+//! > (import math :as m)
+//! nil
+//! > (m/mean 2 4)
+//! 3
+//! ```
+//!
+//! Modules imported by their bare name that are not found next to the
+//! importing file are searched for in the directories passed with
+//! `-L <dir>` (or `--path <dir>`), then in the directories listed in the
+//! `LISPER_PATH` environment variable and finally in the `lib` directory of
+//! the project.
+//!
+//! #### Conditions
+//!
+//! Errors are represented by typed conditions. New condition types with their
+//! fields are defined using `define-condition`, optionally with a parent type.
+//! Every condition type is derived from `error`. `signal` raises a condition
+//! and `handler-case` handles it with the first clause matching its type (or
+//! any of its parents). Errors of the interpreter itself are signalled as
+//! `undefined-variable`, `undefined-function`, `argument-count`,
+//! `illegal-argument`, `division-by-zero`, `index-out-of-range`,
+//! `recursion-limit`, `constant-reassignment`, `import-error`, `io-error` and
+//! `internal-error`,
+//! all of them having a `message` field.
+//!
+//! `condition-slot` reads a field of a condition and `condition-type` returns
+//! its type.
+//!
+//! Usage:
+//! ```
+//! > (define-condition file-error (path))
+//! nil
+//! > (handler-case (signal file-error 404) (file-error (e) (condition-slot e path)))
+//! 404
+//! > (handler-case (+ 1 x) (error (e) (condition-type e)))
+//! undefined-variable
+//! ```
+//!
+//! For simpler error handling, `(error "message")` raises a `simple-error`
+//! with the message and `throw` raises any value, wrapped in a `thrown`
+//! condition with a `value` field unless it is a condition already.
+//! `(try <expr> (catch <var> <body>...))` catches every error, binding the
+//! thrown value or the condition to the variable.
+//!
+//! Usage:
+//! ```
+//! > (try (throw 42) (catch e (+ e 1)))
+//! 43
+//! > (try (error "Failed") (catch e (condition-slot e message)))
+//! "Failed"
+//! ```
+//!
+//! `(assert <condition> <message>)` raises an `assertion-failed` condition
+//! when the condition is false. Its message contains the code of the
+//! condition and the optional message.
+//!
+//! Usage:
+//! ```
+//! > (assert (= 1 2) "Math is broken")
+//! EVAL ERROR: Unhandled condition #<assertion-failed message: "Assertion failed: (= 1 2), Math is broken">
+//! ```
+//!
+//! #### Multiple values
+//!
+//! A function can return more than one value using `values`. The values are
+//! bound to names using `let-values`, whose bindings are only visible inside
+//! of its body. `divmod` returns both the quotient, rounded down, and the
+//! remainder, which is the same as the one of `mod`.
+//!
+//! Usage:
+//! ```
+//! > (values 1 2)
+//! (values 1 2)
+//! > (let-values (((q r) (divmod 17 5))) (+ (* q 10) r))
+//! 32
+//! ```
+//!
+//! #### Generating symbols
+//!
+//! `gensym` returns a fresh symbol that is different from every other symbol
+//! generated before. Names starting with `#:` are reserved for generated symbols,
+//! they cannot be written in the source code.
+//!
+//! Usage:
+//! ```
+//! > (gensym)
+//! #:g1
+//! > (= (gensym) (gensym))
+//! false
+//! ```
+//!
+//! #### Printing to output
+//!
+//! `println` outputs its arguments separated by spaces and ends the line,
+//! `print` does the same without a newline. Both output strings without
+//! quotes, `write` outputs values the way they are written in code so that
+//! they can be read back. All of them return the last value they are given.
+//! `eprint` and `eprintln` output to the standard error instead, so that
+//! diagnostics stay apart from the output of a program.
+//!
+//! Usage:
+//! ```
+//! > (defun power (lambda (x y) (if (= y 0) 1 (* x (println (power x (- y 1)))))))
+//! nil
+//! > (power 2 3)
+//! 1
+//! 2
+//! 4
+//! 8
+//! > (print "no" "newline")
+//! no newline"newline"
+//! > (write "quoted")
+//! "quoted""quoted"
+//! ```
+//!
+//! #### Environment
+//!
+//! Arguments given after the file when running `lisper <file> [args]...` are
+//! returned by `argv` as a list of strings.
+//!
+//! Usage (with `lisper script.lpr input.txt --verbose`):
+//! ```
+//! > (argv)
+//! ("input.txt" "--verbose")
+//! ```
+//!
+//! `getenv` returns the value of an environment variable, `nil` if it is not
+//! set, and `setenv` sets one for the interpreter and the processes it starts.
+//!
+//! Usage:
+//! ```
+//! > (setenv "GREETING" "hello")
+//! nil
+//! > (getenv "GREETING")
+//! "hello"
+//! > (getenv "UNSET_VARIABLE")
+//! nil
+//! ```
+//!
+//! `exec` runs a program with the given arguments and waits for it to exit.
+//! It returns a list of the exit code, `nil` if the program was killed by a
+//! signal, and the standard output and error of the program. Programs that
+//! cannot be started are signalled as `io-error`.
+//!
+//! When Lisper is started with `--sandbox`, `exec`, `getenv`, `setenv`,
+//! `import` and the built-ins reading and writing files raise an error
+//! instead, so untrusted code cannot reach the system.
+//!
+//! Usage:
+//! ```
+//! > (exec "echo" "hello")
+//! (0 "hello\n" "")
+//! > (exec "sh" "-c" "echo oops >&2; exit 3")
+//! (3 "" "oops\n")
+//! ```
+//!
+//! `now` returns the current time as seconds since the Unix epoch.
+//! `format-time` writes such a timestamp in UTC as described by a format and
+//! `parse-time` reads it back. Formats contain `%Y`, `%m`, `%d`, `%H`, `%M`
+//! and `%S` for the year, month, day, hours, minutes and seconds, `%b` and
+//! `%B` for the short and full name of the month, `%a` and `%A` for the name
+//! of the day of the week and `%%` for a percent sign.
+//!
+//! Usage:
+//! ```
+//! > (format-time 1709209800 "%a %d %b %Y %H:%M")
+//! "Thu 29 Feb 2024 12:30"
+//! > (parse-time "2024-02-29" "%Y-%m-%d")
+//! 1709164800
+//! ```
+//!
+
+pub mod dap;
+pub mod debugger;
+pub mod eval;
+pub mod expr;
+pub mod iteration;
+pub mod json;
+pub mod kernel;
+pub mod lexer;
+pub mod list;
+pub mod lsp;
+pub mod module;
+pub mod parser;
+pub mod quasiquote;
+pub mod repl;
+pub mod scope;
+pub mod span;
+
+pub mod arithmetic;
+pub mod bigint;
+pub mod block;
+pub mod bytes;
+pub mod comparison;
+pub mod condition;
+pub mod dispatch;
+pub mod hash;
+pub mod promise;
+pub mod regex;
+pub mod string;
+pub mod system;
+pub mod time;
@@ -0,0 +1,550 @@
+//! Primitives constructing and deconstructing lists at runtime
+
+use crate::{
+    comparison::is_equal,
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+/// Evaluates `list` built-in, creating a list of the evaluated arguments.
+///
+/// Expected Lisper syntax:
+///
+/// ```(list 1 (+ 1 1) 3)```
+pub fn evaluate_list(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let elements = list[1..]
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Expr::List(elements))
+}
+
+/// Evaluates `cons` built-in, prepending a value to a list. `nil` is taken as
+/// the empty list.
+///
+/// Expected Lisper syntax:
+///
+/// ```(cons 1 (list 2 3))```
+pub fn evaluate_cons(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("cons".to_string(), 2));
+    }
+
+    let head = evaluate_expr(&list[1], env, depth + 1)?;
+
+    let mut elements = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::List(elements) => elements,
+        Expr::Nil => Vec::new(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "cons",
+                "Second argument must be a list",
+            ))
+        }
+    };
+
+    elements.insert(0, head);
+
+    Ok(Expr::List(elements))
+}
+
+/// Evaluates `car` built-in, returning the first element of a list.
+///
+/// Expected Lisper syntax:
+///
+/// ```(car (list 1 2 3))```
+pub fn evaluate_car(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let mut elements = evaluate_non_empty_list("car", list, env, depth)?;
+
+    Ok(elements.swap_remove(0))
+}
+
+/// Evaluates `cdr` built-in, returning a list without its first element.
+///
+/// Expected Lisper syntax:
+///
+/// ```(cdr (list 1 2 3))```
+pub fn evaluate_cdr(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let mut elements = evaluate_non_empty_list("cdr", list, env, depth)?;
+    elements.remove(0);
+
+    Ok(Expr::List(elements))
+}
+
+/// Evaluates `len` built-in, returning the number of elements of a list, the
+/// number of characters of a string or the number of bytes of a byte string.
+/// `nil` is taken as the empty list.
+///
+/// Expected Lisper syntax:
+///
+/// ```(len (list 1 2 3))```
+pub fn evaluate_len(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("len".to_string(), 1));
+    }
+
+    let length = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::List(elements) => elements.len(),
+        Expr::String(string) => string.chars().count(),
+        Expr::Bytes(bytes) => bytes.len(),
+        Expr::Nil => 0,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "len",
+                "Argument must be a list or a string",
+            ))
+        }
+    };
+
+    Ok(Expr::Integer(length as i64))
+}
+
+/// Evaluates `concat` built-in, joining lists into a new list or strings into
+/// a new string. `nil` is taken as the empty list.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (concat (list 1 2) (list 3) nil)
+/// (concat "Hello, " "world")
+/// ```
+pub fn evaluate_concat(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let values = list[1..]
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(Expr::String(_)) = values.first() {
+        let mut result = String::new();
+
+        for value in values {
+            match value {
+                Expr::String(string) => result.push_str(&string),
+                _ => {
+                    return Err(EvalError::IllegalArgument(
+                        "concat",
+                        "Strings can only be joined with strings",
+                    ))
+                }
+            }
+        }
+
+        return Ok(Expr::String(result));
+    }
+
+    let mut result = Vec::new();
+
+    for value in values {
+        match value {
+            Expr::List(elements) => result.extend(elements),
+            Expr::Nil => {}
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "concat",
+                    "Arguments must be lists or strings",
+                ))
+            }
+        }
+    }
+
+    Ok(Expr::List(result))
+}
+
+/// Evaluates `reverse` built-in, returning the elements of a list, the
+/// characters of a string or the bytes of a byte string in reverse order.
+///
+/// Expected Lisper syntax:
+///
+/// ```(reverse (list 1 2 3))```
+pub fn evaluate_reverse(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("reverse".to_string(), 1));
+    }
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::List(mut elements) => {
+            elements.reverse();
+
+            Ok(Expr::List(elements))
+        }
+        Expr::String(string) => Ok(Expr::String(string.chars().rev().collect())),
+        Expr::Bytes(mut bytes) => {
+            bytes.reverse();
+
+            Ok(Expr::Bytes(bytes))
+        }
+        Expr::Nil => Ok(Expr::Nil),
+        _ => Err(EvalError::IllegalArgument(
+            "reverse",
+            "Argument must be a list or a string",
+        )),
+    }
+}
+
+/// Evaluates `nth` built-in, returning the element of a list at an index
+/// counted from zero.
+///
+/// Expected Lisper syntax:
+///
+/// ```(nth (list 1 2 3) 0)```
+pub fn evaluate_nth(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("nth".to_string(), 2));
+    }
+
+    let mut elements = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::List(elements) => elements,
+        Expr::Nil => Vec::new(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "nth",
+                "First argument must be a list",
+            ))
+        }
+    };
+
+    let index = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::Integer(index) => index,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "nth",
+                "Index must be an integer",
+            ))
+        }
+    };
+
+    match usize::try_from(index) {
+        Ok(position) if position < elements.len() => Ok(elements.swap_remove(position)),
+        _ => Err(EvalError::IndexOutOfRange("nth", index, elements.len())),
+    }
+}
+
+/// Evaluates `take` and `drop` built-ins, returning the first `n` elements
+/// of a list or the elements after them. A list shorter than `n` is returned
+/// whole by `take` and as the empty list by `drop`.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (take 2 (list 1 2 3))
+/// (drop 2 (list 1 2 3))
+/// ```
+pub fn evaluate_take(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 2));
+    }
+
+    let count = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::Integer(count) if count >= 0 => count as usize,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                name,
+                "Count must be a non-negative number",
+            ))
+        }
+    };
+
+    let mut elements = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::List(elements) => elements,
+        Expr::Nil => Vec::new(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                name,
+                "Second argument must be a list",
+            ))
+        }
+    };
+
+    let rest = elements.split_off(count.min(elements.len()));
+
+    match name {
+        "take" => Ok(Expr::List(elements)),
+        _ => Ok(Expr::List(rest)),
+    }
+}
+
+/// Evaluates `zip` built-in, creating a list of lists holding the elements of
+/// every argument at the same index. The result is as long as the shortest list.
+///
+/// Expected Lisper syntax:
+///
+/// ```(zip (list 1 2 3) (list 'a 'b))```
+pub fn evaluate_zip(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 2 {
+        return Err(EvalError::ArgumentCount("zip".to_string(), 1));
+    }
+
+    let mut lists = list[1..]
+        .iter()
+        .map(|expr| match evaluate_expr(expr, env, depth + 1)? {
+            Expr::List(elements) => Ok(elements.into_iter()),
+            Expr::Nil => Ok(Vec::new().into_iter()),
+            _ => Err(EvalError::IllegalArgument("zip", "Arguments must be lists")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut result = Vec::new();
+
+    while let Some(tuple) = lists.iter_mut().map(Iterator::next).collect() {
+        result.push(Expr::List(tuple));
+    }
+
+    Ok(Expr::List(result))
+}
+
+/// Evaluates `flatten` built-in, splicing the elements of nested lists into
+/// the surrounding list. Without a depth, all levels of nesting are removed.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (flatten '(1 (2 (3))))
+/// (flatten '(1 (2 (3))) 1)
+/// ```
+pub fn evaluate_flatten(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 && list.len() != 3 {
+        return Err(EvalError::ArgumentCount("flatten".to_string(), 1));
+    }
+
+    let elements = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::List(elements) => elements,
+        Expr::Nil => Vec::new(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "flatten",
+                "First argument must be a list",
+            ))
+        }
+    };
+
+    let levels = match list.get(2) {
+        Some(levels) => match evaluate_expr(levels, env, depth + 1)? {
+            Expr::Integer(levels) if levels >= 0 => levels as usize,
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "flatten",
+                    "Depth must be a non-negative number",
+                ))
+            }
+        },
+        None => usize::MAX,
+    };
+
+    // Nested lists are walked using an explicit stack, as they can be nested
+    // deeper than the recursion limit allows
+    let mut result = Vec::new();
+    let mut stack = vec![elements.into_iter()];
+
+    while let Some(elements) = stack.last_mut() {
+        match elements.next() {
+            Some(Expr::List(nested)) if stack.len() <= levels => stack.push(nested.into_iter()),
+            Some(element) => result.push(element),
+            None => {
+                stack.pop();
+            }
+        }
+    }
+
+    Ok(Expr::List(result))
+}
+
+/// Evaluates `member` built-in, returning the rest of a list starting with the
+/// first element equal to the value, `false` if there is none.
+///
+/// Expected Lisper syntax:
+///
+/// ```(member 2 (list 1 2 3))```
+pub fn evaluate_member(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("member".to_string(), 2));
+    }
+
+    let value = evaluate_expr(&list[1], env, depth + 1)?;
+
+    let mut elements = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::List(elements) => elements,
+        Expr::Nil => Vec::new(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "member",
+                "Second argument must be a list",
+            ))
+        }
+    };
+
+    match elements
+        .iter()
+        .position(|element| is_equal(element, &value))
+    {
+        Some(position) => Ok(Expr::List(elements.split_off(position))),
+        None => Ok(Expr::Boolean(false)),
+    }
+}
+
+/// Evaluates `assoc` built-in, looking up a key in an association list, a list
+/// of pairs whose first element is the key. Returns the first pair with an
+/// equal key, `false` if there is none.
+///
+/// Expected Lisper syntax:
+///
+/// ```(assoc 'b '((a 1) (b 2)))```
+pub fn evaluate_assoc(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("assoc".to_string(), 2));
+    }
+
+    let key = evaluate_expr(&list[1], env, depth + 1)?;
+
+    let pairs = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::List(pairs) => pairs,
+        Expr::Nil => Vec::new(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "assoc",
+                "Second argument must be a list",
+            ))
+        }
+    };
+
+    for pair in pairs {
+        match &pair {
+            Expr::List(elements) if !elements.is_empty() => {
+                if is_equal(&elements[0], &key) {
+                    return Ok(pair);
+                }
+            }
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "assoc",
+                    "Elements must be non-empty lists",
+                ))
+            }
+        }
+    }
+
+    Ok(Expr::Boolean(false))
+}
+
+/// Evaluates `contains?` built-in, checking whether a list has an element
+/// equal to the value or a string contains a substring.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (contains? (list 1 2 3) 2)
+/// (contains? "haystack" "st")
+/// ```
+pub fn evaluate_contains(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("contains?".to_string(), 2));
+    }
+
+    let collection = evaluate_expr(&list[1], env, depth + 1)?;
+    let value = evaluate_expr(&list[2], env, depth + 1)?;
+
+    match (collection, value) {
+        (Expr::List(elements), value) => Ok(Expr::Boolean(
+            elements.iter().any(|element| is_equal(element, &value)),
+        )),
+        (Expr::Nil, _) => Ok(Expr::Boolean(false)),
+        (Expr::String(string), Expr::String(substring)) => {
+            Ok(Expr::Boolean(string.contains(substring.as_str())))
+        }
+        (Expr::String(_), _) => Err(EvalError::IllegalArgument(
+            "contains?",
+            "Strings can only contain strings",
+        )),
+        _ => Err(EvalError::IllegalArgument(
+            "contains?",
+            "First argument must be a list or a string",
+        )),
+    }
+}
+
+/// Evaluates the only argument of `name`, which must be a non-empty list
+fn evaluate_non_empty_list(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Vec<Expr>, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 1));
+    }
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::List(elements) if !elements.is_empty() => Ok(elements),
+        Expr::List(_) => Err(EvalError::IllegalArgument(name, "List must not be empty")),
+        _ => Err(EvalError::IllegalArgument(name, "Argument must be a list")),
+    }
+}
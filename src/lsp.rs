@@ -0,0 +1,579 @@
+//! Language server speaking the Language Server Protocol over stdio
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+use crate::{
+    json::Json,
+    lexer::{lex, lex_with_ends, Token, KEYWORDS, OPERATORS},
+    parser::{parse_program_recovering, ParseError},
+    span::Spanned,
+};
+
+/// Forms whose second element is the name of a new definition
+const DEFINING_FORMS: [&str; 3] = ["def", "defconst", "defun"];
+
+/// Built-ins that are not keywords or operators
+const LITERALS: [&str; 3] = ["if", "true", "false"];
+
+/// Severity of diagnostics as defined by the protocol
+const SEVERITY_ERROR: i64 = 1;
+const SEVERITY_WARNING: i64 = 2;
+
+/// Kinds of completion items as defined by the protocol
+const COMPLETION_FUNCTION: i64 = 3;
+const COMPLETION_VARIABLE: i64 = 6;
+const COMPLETION_KEYWORD: i64 = 14;
+
+/// Position in a document, `character` counts UTF-16 code units like the protocol does
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Position {
+    line: usize,
+    character: usize,
+}
+
+impl Position {
+    fn to_json(self) -> Json {
+        Json::object([
+            ("line", (self.line as i64).into()),
+            ("character", (self.character as i64).into()),
+        ])
+    }
+
+    fn from_json(json: &Json) -> Option<Position> {
+        Some(Position {
+            line: json.get("line")?.as_i64()? as usize,
+            character: json.get("character")?.as_i64()? as usize,
+        })
+    }
+}
+
+/// A parenthesis or a word of a document
+#[derive(Debug)]
+struct Word {
+    text: String,
+    start: Position,
+    end: Position,
+    /// Byte offsets into the document
+    start_offset: usize,
+    end_offset: usize,
+    /// Whether the word is in an expression commented out by `#;`
+    commented: bool,
+}
+
+/// A name introduced by `def`, `defconst` or `defun`
+#[derive(Debug)]
+struct Definition<'a> {
+    keyword: &'a str,
+    name: &'a Word,
+    /// Source code of the whole defining form
+    source: &'a str,
+}
+
+fn range(start: Position, end: Position) -> Json {
+    Json::object([("start", start.to_json()), ("end", end.to_json())])
+}
+
+/// Splits a document into parentheses and words using the lexer, leaving out
+/// the expressions commented out by `#;`
+fn scan(text: &str) -> Vec<Word> {
+    scan_all(text)
+        .into_iter()
+        .filter(|word| !word.commented)
+        .collect()
+}
+
+/// Splits a document into parentheses and words using the lexer. String
+/// literals are a single word including their quotes and comments are left
+/// out, the words of expressions commented out by `#;` are marked. After a
+/// lexer error only the words in front of it are found.
+fn scan_all(text: &str) -> Vec<Word> {
+    let mut lexed = lex_with_ends(text);
+
+    while let Err(err) = lexed {
+        lexed = lex_with_ends(&text[..err.span().offset]);
+    }
+
+    let tokens = lexed.unwrap_or_default();
+    let mut words = Vec::new();
+    let mut position = PositionTracker::new(text);
+    // Index of the first token after the expressions commented out so far
+    let mut commented_until = 0;
+
+    for (i, (token, end)) in tokens.iter().enumerate() {
+        if matches!(token.value, Token::DatumComment) {
+            commented_until = commented_until.max(skip_datum(&tokens, i + 1));
+            continue;
+        }
+
+        words.push(Word {
+            text: text[token.span.offset..*end].to_string(),
+            start: position.at(token.span.offset),
+            end: position.at(*end),
+            start_offset: token.span.offset,
+            end_offset: *end,
+            commented: i < commented_until,
+        });
+    }
+
+    words
+}
+
+/// Returns the index of the token following the expression starting at `i`
+fn skip_datum(tokens: &[(Spanned<Token>, usize)], i: usize) -> usize {
+    match tokens.get(i).map(|(token, _)| &token.value) {
+        Some(Token::OpenParen | Token::OpenBracket) => {
+            let mut i = i + 1;
+
+            while let Some((token, _)) = tokens.get(i) {
+                if matches!(token.value, Token::CloseParen | Token::CloseBracket) {
+                    return i + 1;
+                }
+
+                i = skip_datum(tokens, i);
+            }
+
+            i
+        }
+        // Prefixes belong to the expression following them
+        Some(Token::ReaderMacro(_)) => skip_datum(tokens, i + 1),
+        Some(Token::DatumComment) => skip_datum(tokens, skip_datum(tokens, i + 1)),
+        Some(_) => i + 1,
+        None => i,
+    }
+}
+
+/// Converts increasing byte offsets of a document into positions
+struct PositionTracker<'a> {
+    text: &'a str,
+    offset: usize,
+    position: Position,
+}
+
+impl<'a> PositionTracker<'a> {
+    fn new(text: &'a str) -> Self {
+        PositionTracker {
+            text,
+            offset: 0,
+            position: Position {
+                line: 0,
+                character: 0,
+            },
+        }
+    }
+
+    fn at(&mut self, offset: usize) -> Position {
+        for c in self.text[self.offset..offset].chars() {
+            if c == '\n' {
+                self.position.line += 1;
+                self.position.character = 0;
+            } else {
+                self.position.character += c.len_utf16();
+            }
+        }
+
+        self.offset = offset;
+
+        self.position
+    }
+}
+
+/// Whether a word opens a list, with a parenthesis or a bracket
+fn is_open(word: &Word) -> bool {
+    word.text == "(" || word.text == "["
+}
+
+/// Whether a word closes a list, with a parenthesis or a bracket
+fn is_close(word: &Word) -> bool {
+    word.text == ")" || word.text == "]"
+}
+
+/// Finds the index of the parenthesis closing the one at `open`
+fn matching_paren(words: &[Word], open: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, word) in words.iter().enumerate().skip(open) {
+        match word.text.as_str() {
+            "(" | "[" => depth += 1,
+            ")" | "]" => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Finds all definitions of a document
+fn definitions<'a>(text: &'a str, words: &'a [Word]) -> Vec<Definition<'a>> {
+    let mut definitions = Vec::new();
+
+    for (i, window) in words.windows(3).enumerate() {
+        if let [open, keyword, name] = window {
+            let keyword = match DEFINING_FORMS.iter().find(|form| **form == keyword.text) {
+                Some(keyword) => *keyword,
+                None => continue,
+            };
+
+            if !is_open(open) || is_open(name) || is_close(name) {
+                continue;
+            }
+
+            let end_offset = matching_paren(words, i)
+                .map(|close| words[close].end_offset)
+                .unwrap_or(text.len());
+
+            definitions.push(Definition {
+                keyword,
+                name,
+                source: &text[open.start_offset..end_offset],
+            });
+        }
+    }
+
+    definitions
+}
+
+/// Finds the word under the cursor
+fn word_at(words: &[Word], position: Position) -> Option<&Word> {
+    words
+        .iter()
+        .find(|word| word.start <= position && position <= word.end && !is_open(word))
+        .filter(|word| !is_close(word))
+}
+
+/// Computes diagnostics, parse errors and problems found by simple lints
+fn diagnostics(text: &str) -> Vec<Json> {
+    let words = scan_all(text);
+    let mut diagnostics = Vec::new();
+
+    let mut diagnostic = |word: &Word, severity: i64, message: &str| {
+        diagnostics.push(Json::object([
+            ("range", range(word.start, word.end)),
+            ("severity", severity.into()),
+            ("source", "lisper".into()),
+            ("message", message.into()),
+        ]));
+    };
+
+    // Check that parentheses are balanced
+    let mut open_parens = Vec::new();
+
+    for word in &words {
+        if is_open(word) {
+            open_parens.push(word);
+        } else if is_close(word) && open_parens.pop().is_none() {
+            diagnostic(word, SEVERITY_ERROR, "Unexpected closing parenthesis");
+        }
+    }
+
+    for word in open_parens {
+        diagnostic(word, SEVERITY_ERROR, "Unclosed parenthesis");
+    }
+
+    // Errors are reported at the word they are found in
+    let word_at = |offset: usize| words.iter().rev().find(|word| word.start_offset <= offset);
+
+    match lex(text) {
+        // Words are only found in front of lexer errors, so the rest of the line is reported
+        Err(err) => {
+            let start = err.span().offset;
+            let end = text[start..]
+                .find('\n')
+                .map_or(text.len(), |end| start + end);
+            let mut position = PositionTracker::new(text);
+
+            let rest_of_line = Word {
+                text: text[start..end].to_string(),
+                start: position.at(start),
+                end: position.at(end),
+                start_offset: start,
+                end_offset: end,
+                commented: false,
+            };
+
+            diagnostic(&rest_of_line, SEVERITY_ERROR, &err.to_string());
+        }
+        Ok(tokens) => {
+            let (_, errors) = parse_program_recovering(&mut tokens.into_iter().peekable());
+
+            for err in errors {
+                // Unbalanced delimiters are already reported where they are
+                if let ParseError::UnexpectedEof(..) | ParseError::UnbalancedParen(..) = err.value {
+                    continue;
+                }
+
+                if let Some(word) = word_at(err.span.offset) {
+                    diagnostic(word, SEVERITY_ERROR, &err.value.to_string());
+                }
+            }
+        }
+    }
+
+    // Definitions of names that the lexer treats as built-ins have no effect
+    let words = scan(text);
+
+    for definition in definitions(text, &words) {
+        let name = definition.name.text.as_str();
+
+        if KEYWORDS.contains(&name) || OPERATORS.contains(&name) || LITERALS.contains(&name) {
+            diagnostic(
+                definition.name,
+                SEVERITY_WARNING,
+                "Built-ins cannot be redefined",
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Handles a request, returning its result
+fn handle_request(
+    method: &str,
+    params: &Json,
+    documents: &HashMap<String, String>,
+) -> Result<Json, (i64, &'static str)> {
+    let document = params
+        .pointer(&["textDocument", "uri"])
+        .and_then(Json::as_str)
+        .map(|uri| (uri, documents.get(uri).map(String::as_str).unwrap_or("")));
+
+    let position = params.get("position").and_then(Position::from_json);
+
+    match method {
+        "initialize" => Ok(Json::object([
+            (
+                "capabilities",
+                Json::object([
+                    // Documents are always sent whole
+                    ("textDocumentSync", 1.into()),
+                    ("definitionProvider", true.into()),
+                    ("hoverProvider", true.into()),
+                    ("completionProvider", Json::object([])),
+                ]),
+            ),
+            (
+                "serverInfo",
+                Json::object([
+                    ("name", "lisper".into()),
+                    ("version", env!("CARGO_PKG_VERSION").into()),
+                ]),
+            ),
+        ])),
+        "shutdown" => Ok(Json::Null),
+        "textDocument/definition" => {
+            let ((uri, text), position) = document.zip(position).ok_or(INVALID_PARAMS)?;
+            let words = scan(text);
+
+            let location = word_at(&words, position).and_then(|word| {
+                definitions(text, &words)
+                    .into_iter()
+                    .find(|definition| definition.name.text == word.text)
+                    .map(|definition| {
+                        Json::object([
+                            ("uri", uri.into()),
+                            ("range", range(definition.name.start, definition.name.end)),
+                        ])
+                    })
+            });
+
+            Ok(location.unwrap_or(Json::Null))
+        }
+        "textDocument/hover" => {
+            let ((_, text), position) = document.zip(position).ok_or(INVALID_PARAMS)?;
+            let words = scan(text);
+
+            let word = match word_at(&words, position) {
+                Some(word) => word,
+                None => return Ok(Json::Null),
+            };
+
+            let contents = match definitions(text, &words)
+                .into_iter()
+                .find(|definition| definition.name.text == word.text)
+            {
+                Some(definition) => format!("```lisper\n{}\n```", definition.source),
+                None if KEYWORDS.contains(&word.text.as_str())
+                    || OPERATORS.contains(&word.text.as_str())
+                    || LITERALS.contains(&word.text.as_str()) =>
+                {
+                    format!("Built-in `{}`", word.text)
+                }
+                None => return Ok(Json::Null),
+            };
+
+            Ok(Json::object([
+                (
+                    "contents",
+                    Json::object([("kind", "markdown".into()), ("value", contents.into())]),
+                ),
+                ("range", range(word.start, word.end)),
+            ]))
+        }
+        "textDocument/completion" => {
+            let (_, text) = document.ok_or(INVALID_PARAMS)?;
+            let words = scan(text);
+
+            let builtins = KEYWORDS
+                .iter()
+                .chain(OPERATORS)
+                .chain(&LITERALS)
+                .map(|name| (name.to_string(), COMPLETION_KEYWORD));
+
+            let defined = definitions(text, &words).into_iter().map(|definition| {
+                let kind = match definition.keyword {
+                    "defun" => COMPLETION_FUNCTION,
+                    _ => COMPLETION_VARIABLE,
+                };
+
+                (definition.name.text.clone(), kind)
+            });
+
+            let items = builtins
+                .chain(defined)
+                .map(|(label, kind)| Json::object([("label", label.into()), ("kind", kind.into())]))
+                .collect();
+
+            Ok(Json::Array(items))
+        }
+        _ => Err((-32601, "Method not found")),
+    }
+}
+
+const INVALID_PARAMS: (i64, &str) = (-32602, "Invalid params");
+
+/// Reads a single message of the editor protocols, `None` when the input has ended
+pub fn read_message(input: &mut impl BufRead) -> std::io::Result<Option<Json>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap_or(0)];
+    input.read_exact(&mut content)?;
+
+    // Malformed messages are ignored
+    Ok(Some(
+        Json::parse(&String::from_utf8_lossy(&content)).unwrap_or(Json::Null),
+    ))
+}
+
+/// Writes a single message, with the header used by the editor protocols
+pub fn write_message(output: &mut impl Write, message: Json) -> std::io::Result<()> {
+    let content = message.to_string();
+
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
+
+fn publish_diagnostics(output: &mut impl Write, uri: &str, text: &str) -> std::io::Result<()> {
+    write_message(
+        output,
+        Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", "textDocument/publishDiagnostics".into()),
+            (
+                "params",
+                Json::object([
+                    ("uri", uri.into()),
+                    ("diagnostics", Json::Array(diagnostics(text))),
+                ]),
+            ),
+        ]),
+    )
+}
+
+/// Runs the language server until the client sends `exit`
+pub fn run_lsp() -> std::io::Result<()> {
+    let mut input = std::io::stdin().lock();
+    let mut output = std::io::stdout().lock();
+
+    let mut documents: HashMap<String, String> = HashMap::new();
+
+    while let Some(message) = read_message(&mut input)? {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let uri = params
+            .pointer(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .map(str::to_string);
+
+        // Requests have an id and expect a response, notifications do not
+        if let Some(id) = message.get("id") {
+            let response = match handle_request(method, &params, &documents) {
+                Ok(result) => Json::object([
+                    ("jsonrpc", "2.0".into()),
+                    ("id", id.clone()),
+                    ("result", result),
+                ]),
+                Err((code, error)) => Json::object([
+                    ("jsonrpc", "2.0".into()),
+                    ("id", id.clone()),
+                    (
+                        "error",
+                        Json::object([("code", code.into()), ("message", error.into())]),
+                    ),
+                ]),
+            };
+
+            write_message(&mut output, response)?;
+            continue;
+        }
+
+        match (method, uri) {
+            ("exit", _) => return Ok(()),
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = params
+                    .pointer(&["textDocument", "text"])
+                    .and_then(Json::as_str)
+                    .unwrap_or("")
+                    .to_string();
+
+                publish_diagnostics(&mut output, &uri, &text)?;
+                documents.insert(uri, text);
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+
+                // The whole document is sent with every change
+                if let Some(text) = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str)
+                {
+                    publish_diagnostics(&mut output, &uri, text)?;
+                    documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                documents.remove(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -1,253 +1,168 @@
-//! # Lisper
-//!
-//! A barebones LISP-family programming langauge interpreter.
-//!
-//! Created as a project for the Programming 1 course at MFF CUNI.
-//!
-//! ## Features
-//!
-//! ### Numbers
-//!
-//! Lisper can only handle integers now. It won't be complicated to implement
-//! floating-point numbers, strings or other data types.
-//!
-//! Usage:
-//! ```
-//! (+ 1 2)
-//! (* 1 2 3 4)
-//! ```
-//!
-//! ### Built-in functions
-//!
-//! #### Arithmetic operations
-//!
-//! ##### `+`
-//!
-//! Addition on numbers
-//!
-//! Usage:
-//! ```
-//! > (+ 1 2)
-//! 3
-//!
-//! > (+ 2 4 6)
-//! 12
-//!
-//! > (+ 5 -5)
-//! 0
-//! ```
-//!
-//! ##### `-`
-//!
-//! Subtraction on numbers
-//!
-//! Usage:
-//! ```
-//! > (- 1 2)
-//! -1
-//!
-//! > (- 5 5)
-//! 0
-//! ```
-//!
-//! ##### `*`
-//!
-//! Multiplication on numbers
-//!
-//! Usage:
-//! ```
-//! > (* 1 2)
-//! 2
-//!
-//! > (* 0 3 4 6)
-//! 0
-//! ```
-//!
-//! ##### `/`
-//!
-//! **Integer** division on numbers
-//!
-//! Usage:
-//! ```
-//! > (/ 1 2)
-//! 0
-//!
-//! > (/ 12 6 2)
-//! 1
-//! ```
-//!
-//! #### Logic operations and comparison
-//!
-//! ##### `and`
-//!
-//! Logic `and`
-//!
-//! Usage:
-//! ```
-//! > (and true false)
-//! false
-//!
-//! > (and false)
-//! false
-//! ```
-//!
-//! ##### `or`
-//!
-//! Logic `or`
-//!
-//! Usage:
-//! ```
-//! > (or false false)
-//! false
-//!
-//! > (or false)
-//! false
-//! ```
-//!
-//! ##### `not`
-//!
-//! Logical negation
-//!
-//! Usage:
-//! ```
-//! > (not true)
-//! false
-//! ```
-//!
-//! ##### `=`
-//!
-//! Equals
-//!
-//! Usage:
-//! ```
-//! > (= 2 2 (+ 2 0) (- 4 2))
-//! true
-//! ```
-//!
-//! ##### `!=`
-//!
-//! Not equals
-//!
-//! Usage:
-//! ```
-//! > (!= 2 (+ 1 2))
-//! false
-//! ```
-//!
-//! ##### `<`, `<=`, `>`, `>=`
-//!
-//! Comparison operators on numbers
-//!
-//! Usage:
-//! ```
-//! > (< 1 2 3)
-//! true
-//!
-//! > (< 2 5 3)
-//! false
-//!
-//! > (>= 8 6 (+ 3 3) 2)
-//! true
-//! ```
-//!
-//! #### Control flow
-//!
-//! ##### If
-//!
-//! Conditional execution of expression
-//!
-//! `(if <condition> <if-true> <if-false>)`
-//!
-//! Usage:
-//! ```
-//! > (if (= 4 (+ 2 2)) 42 0)
-//! 42
-//! ```
-//!
-//! #### Variables
-//!
-//! Variable definition is available using the `def` keyword.
-//!
-//! Usage:
-//! ```
-//! > (def x 10)
-//! -=-
-//! > (def y 20)
-//! -=-
-//! > (+ x y)
-//! 30
-//! ```
-//!
-//! #### Functions
-//!
-//! Functions can be defined using the `defun` and `lambda` keywords. The reason for the lambda keyword is to allow functions to be first-class citizens in the future.
-//!
-//! Usage:
-//! ```
-//! > (defun double (lambda (x) (* x 2)))
-//! -=-
-//! > (double 4)
-//! 8
-//! ```
-//!
-//! #### Printing to output
-//!
-//! To print something to the output, the `print` expression is available. It returns whatever it is given.
-//!
-//! Usage:
-//! ```
-//! > (defun power (lambda (x y) (if (= y 0) 1 (* x (print (power x (- y 1)))))))
-//! -=-
-//! > (power 2 3)
-//! 1
-//! 2
-//! 4
-//! 8
-//! ```
-//!
+//! Command line interface of Lisper
 
-mod eval;
-mod expr;
-mod lexer;
-mod parser;
-mod repl;
-mod scope;
+use std::path::{Path, PathBuf};
 
-mod comparison;
+use lisper::{
+    dap::run_dap,
+    eval::{evaluate_source, Truthiness},
+    kernel::run_kernel,
+    lexer::lex,
+    lsp::run_lsp,
+    module::search_path,
+    parser::parse_program_with_spans,
+    repl::run_repl,
+    scope::{PassableScope, Scope},
+};
 
-use eval::evaluate;
-use lexer::lex;
-use parser::parse;
-use repl::run_repl;
-use scope::Scope;
+/// Modes Lisper can run in other than evaluating code
+#[doc(hidden)]
+enum Subcommand {
+    /// Language server, `lisper lsp`
+    Lsp,
+    /// Notebook kernel, `lisper kernel`
+    Kernel,
+    /// Debug adapter, `lisper dap`
+    Dap,
+}
+
+/// Options passed on the command line
+#[doc(hidden)]
+struct Options {
+    /// Mode to run in instead of evaluating code
+    subcommand: Option<Subcommand>,
+    /// File to evaluate, the REPL is started if missing
+    file: Option<String>,
+    /// Arguments after the file, passed to the evaluated script
+    script_args: Vec<String>,
+    /// Additional module directories passed with `-L` or `--path`
+    module_dirs: Vec<PathBuf>,
+    /// Which values count as true in conditional forms, changed by `--truthy`
+    truthiness: Truthiness,
+    /// Untrusted code is evaluated, set by `--sandbox`
+    sandboxed: bool,
+}
+
+/// Parses the command line arguments,
+/// `lisper [lsp | kernel | dap] [--truthy] [--sandbox] [-L <dir>]... [file [args]...]`.
+/// Everything after the file is passed to the script.
+#[doc(hidden)]
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        subcommand: None,
+        file: None,
+        script_args: Vec::new(),
+        module_dirs: Vec::new(),
+        truthiness: Truthiness::Strict,
+        sandboxed: false,
+    };
+
+    let mut first = true;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "lsp" if first => options.subcommand = Some(Subcommand::Lsp),
+            "kernel" if first => options.subcommand = Some(Subcommand::Kernel),
+            "dap" if first => options.subcommand = Some(Subcommand::Dap),
+            "-L" | "--path" => match args.next() {
+                Some(dir) => options.module_dirs.push(PathBuf::from(dir)),
+                None => return Err(format!("Missing directory after {arg}")),
+            },
+            "--truthy" => options.truthiness = Truthiness::Lenient,
+            "--sandbox" => options.sandboxed = true,
+            _ => {
+                options.file = Some(arg);
+                options.script_args = args.collect();
+
+                break;
+            }
+        }
+
+        first = false;
+    }
+
+    Ok(options)
+}
+
+/// Stack size of the thread evaluating code, enough for the deepest nesting
+/// allowed by the parser and evaluator even in debug builds
+#[doc(hidden)]
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
 #[doc(hidden)]
 fn main() -> rustyline::Result<()> {
-    let filepath = std::env::args().collect::<Vec<String>>().get(1).cloned();
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)?
+        .join()
+        .expect("Evaluation thread panicked")
+}
+
+#[doc(hidden)]
+fn run() -> rustyline::Result<()> {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            println!("ARGUMENT ERROR: {err}");
+
+            return rustyline::Result::Ok(());
+        }
+    };
 
-    match filepath {
+    if let Some(Subcommand::Lsp) = options.subcommand {
+        return Ok(run_lsp()?);
+    }
+
+    // Modules of the project live next to the evaluated file or in the working directory
+    let project_root = options
+        .file
+        .as_ref()
+        .and_then(|file| std::fs::canonicalize(file).ok())
+        .and_then(|file| file.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+
+    let env = Scope::new().wrap();
+    let globals = env.borrow().globals();
+    globals.borrow_mut().search_path = search_path(options.module_dirs, &project_root);
+    globals.borrow_mut().truthiness = options.truthiness;
+    globals.borrow_mut().sandboxed = options.sandboxed;
+    globals.borrow_mut().args = options.script_args;
+
+    match options.subcommand {
+        Some(Subcommand::Kernel) => return Ok(run_kernel(env)?),
+        Some(Subcommand::Dap) => return Ok(run_dap(env)?),
+        _ => {}
+    }
+
+    match options.file {
         Some(path) => {
-            run_from_file(path);
+            run_from_file(path, env);
 
             rustyline::Result::Ok(())
         }
-        None => run_repl(),
+        None => run_repl(env),
     }
 }
 
 /// Evaluates a file with Lisper code
-fn run_from_file(path: String) {
-    match std::fs::read_to_string(path) {
+fn run_from_file(path: String, mut env: PassableScope) {
+    match std::fs::read_to_string(&path) {
         Ok(content) => {
-            let mut env = Scope::new().wrap();
-            let tokens = lex(&content);
+            env.borrow().globals().borrow_mut().current_file = std::fs::canonicalize(&path).ok();
+
+            let tokens = match lex(&content) {
+                Ok(tokens) => tokens,
+                Err(lexer_error) => {
+                    println!("LEXER ERROR: {lexer_error}");
+                    return;
+                }
+            };
 
-            match parse(&mut tokens.into_iter().peekable()) {
+            match parse_program_with_spans(&mut tokens.into_iter().peekable()) {
                 Err(parser_error) => {
                     println!("PARSER ERROR: {parser_error}");
                 }
-                Ok(parsed) => {
-                    let evaluated = evaluate(&parsed, &mut env);
+                Ok((program, source_map)) => {
+                    let evaluated = evaluate_source(&program, source_map, &mut env);
 
                     if let Err(err) = evaluated {
                         println!("EVAL ERROR: {err}");
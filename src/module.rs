@@ -0,0 +1,186 @@
+//! Module loading for the `import` built-in
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    eval::{evaluate_program, EvalError},
+    expr::Expr,
+    lexer::lex,
+    parser::parse_program,
+    scope::{PassableScope, Scope},
+};
+
+/// File extensions tried, in order, when a module is imported by its bare name
+const MODULE_EXTENSIONS: [&str; 2] = ["lpr", "lisp"];
+
+/// Environment variable holding additional module directories
+const SEARCH_PATH_VARIABLE: &str = "LISPER_PATH";
+
+/// Directory of the project searched for modules when all else fails
+const DEFAULT_MODULE_DIR: &str = "lib";
+
+/// Builds the list of directories searched for bare-module imports.
+///
+/// Directories passed on the command line take precedence over the ones in
+/// `LISPER_PATH`, the `lib` directory of the project is searched last.
+pub fn search_path(from_cli: Vec<PathBuf>, project_root: &Path) -> Vec<PathBuf> {
+    let mut directories = from_cli;
+
+    if let Some(from_env) = std::env::var_os(SEARCH_PATH_VARIABLE) {
+        directories.extend(std::env::split_paths(&from_env));
+    }
+
+    directories.push(project_root.join(DEFAULT_MODULE_DIR));
+
+    directories
+}
+
+/// Evaluates `import` built-in.
+///
+/// Without a namespace, the imported file is evaluated into the current scope
+/// only once, any further imports of the same file are no-ops. An import that
+/// fails can be retried. With `:as`, the
+/// module is evaluated into its own scope and its definitions are accessible
+/// as `<namespace>/<name>`.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (import "utils.lisp")
+/// (import utils)
+/// (import math :as m)
+/// ```
+pub fn evaluate_import(list: &[Expr], env: &mut PassableScope) -> Result<Expr, EvalError> {
+    let namespace = match list {
+        [_, _] => None,
+        [_, _, Expr::Symbol(keyword), Expr::Symbol(namespace)] if keyword == ":as" => {
+            Some(namespace.clone())
+        }
+        [_, _, _, _] => {
+            return Err(EvalError::IllegalArgument(
+                "import",
+                "Namespace must be given as `:as <symbol>`",
+            ))
+        }
+        _ => return Err(EvalError::ArgumentCount("import".to_string(), 1)),
+    };
+
+    let globals = env.borrow().globals();
+
+    if globals.borrow().sandboxed {
+        return Err(EvalError::ImportFailed(
+            list[1].to_string(),
+            "Imports are disabled in the sandbox".to_string(),
+        ));
+    }
+
+    let base_dir = globals
+        .borrow()
+        .current_file
+        .as_ref()
+        .and_then(|file| file.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+
+    let search_path = globals.borrow().search_path.clone();
+
+    let (name, path) = resolve_module(&list[1], &base_dir, &search_path)?;
+
+    match namespace {
+        None => {
+            // Do not evaluate the same module twice, it is marked before it
+            // is loaded so that circular imports stop
+            if globals.borrow_mut().imported.insert(path.clone()) {
+                if let Err(err) = load_module(&name, path.clone(), env) {
+                    globals.borrow_mut().imported.remove(&path);
+
+                    return Err(err);
+                }
+            }
+        }
+        Some(namespace) => {
+            let cached = globals.borrow().modules.get(&path).cloned();
+
+            let module = match cached {
+                Some(module) => module,
+                None => {
+                    let mut module = Scope::with_globals(globals.clone()).wrap();
+
+                    globals
+                        .borrow_mut()
+                        .modules
+                        .insert(path.clone(), module.clone());
+
+                    if let Err(err) = load_module(&name, path.clone(), &mut module) {
+                        globals.borrow_mut().modules.remove(&path);
+
+                        return Err(err);
+                    }
+
+                    module
+                }
+            };
+
+            env.borrow_mut().set_namespace(namespace, module);
+        }
+    }
+
+    Ok(Expr::Nil)
+}
+
+/// Reads, parses and evaluates the module at `path` into `env`
+fn load_module(name: &str, path: PathBuf, env: &mut PassableScope) -> Result<(), EvalError> {
+    let globals = env.borrow().globals();
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|err| EvalError::ImportFailed(name.to_string(), err.to_string()))?;
+
+    let tokens =
+        lex(&content).map_err(|err| EvalError::ImportFailed(name.to_string(), err.to_string()))?;
+    let program = parse_program(&mut tokens.into_iter().peekable())
+        .map_err(|err| EvalError::ImportFailed(name.to_string(), err.to_string()))?;
+
+    // Relative imports inside the module are resolved against the module itself
+    let importing_file = globals.borrow_mut().current_file.replace(path);
+    let evaluated = evaluate_program(&program, env);
+    globals.borrow_mut().current_file = importing_file;
+
+    evaluated.map(|_| ())
+}
+
+/// Finds the file referenced by an `import` argument.
+///
+/// Strings are taken as paths relative to `base_dir`. Bare symbols have
+/// the module extensions appended and are looked up in `base_dir` first, then
+/// in every directory of the search path.
+fn resolve_module(
+    module: &Expr,
+    base_dir: &Path,
+    search_path: &[PathBuf],
+) -> Result<(String, PathBuf), EvalError> {
+    let (name, candidates): (&String, Vec<PathBuf>) = match module {
+        Expr::String(path) => (path, vec![base_dir.join(path)]),
+        Expr::Symbol(name) => (
+            name,
+            std::iter::once(base_dir)
+                .chain(search_path.iter().map(PathBuf::as_path))
+                .flat_map(|directory| {
+                    MODULE_EXTENSIONS
+                        .iter()
+                        .map(|extension| directory.join(name).with_extension(extension))
+                })
+                .collect(),
+        ),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "import",
+                "Module must be a path or a symbol",
+            ))
+        }
+    };
+
+    candidates
+        .iter()
+        .find_map(|candidate| candidate.canonicalize().ok())
+        .map(|path| (name.clone(), path))
+        .ok_or_else(|| EvalError::ImportFailed(name.clone(), "Module not found".to_string()))
+}
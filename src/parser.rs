@@ -2,70 +2,464 @@
 
 use std::iter::Peekable;
 
-use crate::{expr::Expr, lexer::Token};
+use crate::{
+    arithmetic::big_rational,
+    expr::Expr,
+    lexer::Token,
+    span::{SourceMap, Span, Spanned},
+};
+
+/// Defines how deeply lists can be nested, deeper input would overflow the stack
+pub static MAX_NESTING_DEPTH: usize = 1024;
+
+/// Expands the expression read after the prefix of a reader macro
+pub type ReaderMacro = fn(Expr) -> Expr;
+
+/// Reader macros by the prefix they are written with, which is at most two
+/// characters long. Prefixes starting with the same character are listed
+/// from the longest.
+pub static READER_MACROS: &[(&str, ReaderMacro)] = &[
+    ("'", |expr| wrap("quote", expr)),
+    ("`", |expr| wrap("quasiquote", expr)),
+    (",@", |expr| wrap("unquote-splicing", expr)),
+    (",", |expr| wrap("unquote", expr)),
+];
 
 pub enum ParseError {
     ParenExpected,
+    NestingTooDeep(usize),
+    ExpressionExpected,
+    /// A list is closed by a different kind of delimiter than the one it was
+    /// opened with, holding both of them and the position of the closing one
+    MismatchedDelimiter(char, char, Span),
+    /// The input ends inside of a list, holding the delimiter it was opened
+    /// with and its position
+    UnexpectedEof(char, Span),
+    /// A closing delimiter without an opening one, holding it and its position
+    UnbalancedParen(char, Span),
+    /// A dotted pair, which cannot be read as lists are always proper,
+    /// holding the position of the dot
+    DottedPair(Span),
+}
+
+impl ParseError {
+    /// Returns the position of the problem in the input, if it is known
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::MismatchedDelimiter(_, _, span)
+            | ParseError::UnexpectedEof(_, span)
+            | ParseError::UnbalancedParen(_, span)
+            | ParseError::DottedPair(span) => Some(*span),
+            ParseError::ParenExpected
+            | ParseError::NestingTooDeep(_)
+            | ParseError::ExpressionExpected => None,
+        }
+    }
 }
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ParseError::ParenExpected => write!(f, "Opening parenthesis expected"),
+            ParseError::NestingTooDeep(max) => {
+                write!(f, "Lists cannot be nested more than {max} levels deep")
+            }
+            ParseError::ExpressionExpected => write!(f, "Expression expected"),
+            ParseError::MismatchedDelimiter(open, close, span) => {
+                write!(
+                    f,
+                    "Closing `{close}` at {span} does not match opening `{open}`"
+                )
+            }
+            ParseError::UnexpectedEof(open, span) => {
+                write!(
+                    f,
+                    "Unexpected end of input, `{open}` at {span} is not closed"
+                )
+            }
+            ParseError::UnbalancedParen(close, span) => {
+                write!(f, "Closing `{close}` at {span} has no opening delimiter")
+            }
+            ParseError::DottedPair(span) => {
+                write!(f, "Dotted pairs are not supported, found `.` at {span}")
+            }
         }
     }
 }
 
+/// Parses the first expression of the input, leaving the tokens after it.
+/// Input with several expressions, like a file or a line of the REPL, is
+/// parsed by `parse_program`, which reports any unbalanced delimiter.
 pub fn parse<I>(tokens: &mut Peekable<I>) -> Result<Expr, ParseError>
 where
-    I: Iterator<Item = Token>,
+    I: Iterator<Item = Spanned<Token>>,
+{
+    let spans = &mut Vec::new();
+
+    skip_datum_comments(tokens, spans, 0)?;
+
+    parse_top_level(tokens, spans)
+}
+
+/// Parses all top-level expressions of a program, like the forms of a file
+pub fn parse_program<I>(tokens: &mut Peekable<I>) -> Result<Vec<Expr>, ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
 {
-    // Check if first token is a paranthesis
-    if let Some(Token::OpenParen) = tokens.peek() {
-        // Continue, everything is fine.
-        tokens.next();
-    } else {
-        // Throw error
-        return Err(ParseError::ParenExpected);
+    parse_program_with_spans(tokens).map(|(program, _)| program)
+}
+
+/// Parses all top-level expressions of a program together with the
+/// positions of all of its expressions
+pub fn parse_program_with_spans<I>(
+    tokens: &mut Peekable<I>,
+) -> Result<(Vec<Expr>, SourceMap), ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    let mut program = Vec::new();
+    let spans = &mut Vec::new();
+
+    loop {
+        skip_datum_comments(tokens, spans, 0)?;
+
+        if tokens.peek().is_none() {
+            break;
+        }
+
+        program.push(parse_top_level(tokens, spans)?);
     }
 
-    let mut exprs: Vec<Expr> = Vec::new();
+    let source_map = SourceMap::new(&program, spans);
 
-    while let Some(token) = tokens.peek() {
-        match token {
-            Token::Integer(integer) => {
-                exprs.push(Expr::Integer(*integer));
-                tokens.next();
-            }
-            Token::Boolean(boolean) => {
-                exprs.push(Expr::Boolean(*boolean));
-                tokens.next();
-            }
-            Token::If => {
-                exprs.push(Expr::If);
-                tokens.next();
-            }
-            Token::BinaryOp(operator) => {
-                exprs.push(Expr::Op(operator.clone()));
-                tokens.next();
-            }
-            Token::Keyword(keyword) => {
-                exprs.push(Expr::Keyword(keyword.clone()));
-                tokens.next();
-            }
-            Token::Symbol(symbol) => {
-                exprs.push(Expr::Symbol(symbol.clone()));
-                tokens.next();
-            }
-            Token::OpenParen => {
-                exprs.push(parse(tokens)?);
+    Ok((program, source_map))
+}
+
+/// Parses all top-level expressions of a program, recovering from syntax
+/// errors by skipping to the next top-level expression, so that all errors
+/// are found at once. Errors without a position of their own are reported at
+/// the start of the top-level expression they are found in.
+pub fn parse_program_recovering<I>(
+    tokens: &mut Peekable<I>,
+) -> (Vec<Expr>, Vec<Spanned<ParseError>>)
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    let mut program = Vec::new();
+    let mut errors = Vec::new();
+
+    while let Some(form) = next_form(tokens) {
+        let start = form[0].span;
+        let mut form = form.into_iter().peekable();
+        let spans = &mut Vec::new();
+
+        let parsed = skip_datum_comments(&mut form, spans, 0).and_then(|_| match form.peek() {
+            Some(_) => parse_top_level(&mut form, spans).map(Some),
+            None => Ok(None),
+        });
+
+        match parsed {
+            Ok(Some(expr)) => program.push(expr),
+            Ok(None) => {}
+            Err(err) => {
+                let span = err.span().unwrap_or(start);
+
+                errors.push(Spanned::new(err, span));
             }
-            Token::CloseParen => {
-                tokens.next();
+        }
+    }
+
+    (program, errors)
+}
+
+/// Takes the tokens of the next top-level expression, including the `#;`
+/// comments and quotes in front of it. Delimiters are only counted, so that
+/// the expression ends where it would if they were matched correctly.
+fn next_form<I>(tokens: &mut Peekable<I>) -> Option<Vec<Spanned<Token>>>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    let mut form = Vec::new();
+    let mut depth: usize = 0;
+
+    for token in tokens.by_ref() {
+        match token.value {
+            Token::OpenParen | Token::OpenBracket => depth += 1,
+            Token::CloseParen | Token::CloseBracket => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+
+        let prefix = matches!(token.value, Token::DatumComment | Token::ReaderMacro(_));
+
+        form.push(token);
+
+        if depth == 0 && !prefix {
+            break;
+        }
+    }
+
+    (!form.is_empty()).then_some(form)
+}
+
+/// Parses an expression at the top level of the input, a list or an atom
+fn parse_top_level<I>(tokens: &mut Peekable<I>, spans: &mut Vec<Span>) -> Result<Expr, ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    match peek(tokens) {
+        Some(Token::OpenParen | Token::OpenBracket | Token::CloseParen | Token::CloseBracket) => {
+            parse_list(tokens, spans, 0)
+        }
+        _ => parse_expr(tokens, spans, 0),
+    }
+}
+
+/// Returns the next token without its span
+fn peek<I>(tokens: &mut Peekable<I>) -> Option<&Token>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    tokens.peek().map(|token| &token.value)
+}
+
+/// Skips the expressions commented out by `#;` in front of the next one
+fn skip_datum_comments<I>(
+    tokens: &mut Peekable<I>,
+    spans: &mut Vec<Span>,
+    depth: usize,
+) -> Result<(), ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    let parsed = spans.len();
+
+    while tokens
+        .next_if(|token| matches!(token.value, Token::DatumComment))
+        .is_some()
+    {
+        // Comments in front of the commented out expression nest deeper
+        if depth >= MAX_NESTING_DEPTH {
+            return Err(ParseError::NestingTooDeep(MAX_NESTING_DEPTH));
+        }
+
+        parse_expr(tokens, spans, depth + 1)?;
+    }
+
+    // Commented out expressions are not part of the program
+    spans.truncate(parsed);
+
+    Ok(())
+}
+
+/// Parses a list nested `depth` levels deep, recording the positions of it
+/// and its elements in `spans` in the order they start in
+fn parse_list<I>(
+    tokens: &mut Peekable<I>,
+    spans: &mut Vec<Span>,
+    depth: usize,
+) -> Result<Expr, ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    if depth >= MAX_NESTING_DEPTH {
+        return Err(ParseError::NestingTooDeep(MAX_NESTING_DEPTH));
+    }
+
+    // Check if first token is a paranthesis or a bracket
+    let (open, close, span) = match tokens.peek() {
+        Some(Spanned {
+            value: Token::OpenParen,
+            span,
+        }) => ('(', ')', *span),
+        Some(Spanned {
+            value: Token::OpenBracket,
+            span,
+        }) => ('[', ']', *span),
+        _ => return Err(missing_list(tokens)),
+    };
+
+    tokens.next();
+    spans.push(span);
+
+    let mut exprs: Vec<Expr> = Vec::new();
+
+    loop {
+        skip_datum_comments(tokens, spans, depth)?;
+
+        match peek(tokens) {
+            Some(Token::CloseParen | Token::CloseBracket) => {
+                close_list(tokens, open, close)?;
+
                 return Ok(Expr::List(exprs));
             }
+            Some(_) => exprs.push(parse_expr(tokens, spans, depth)?),
+            None => return Err(ParseError::UnexpectedEof(open, span)),
+        }
+    }
+}
+
+/// Describes why the next token does not open a list
+#[inline(never)]
+fn missing_list<I>(tokens: &mut Peekable<I>) -> ParseError
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    match tokens.next() {
+        Some(Spanned {
+            value: Token::CloseParen,
+            span,
+        }) => ParseError::UnbalancedParen(')', span),
+        Some(Spanned {
+            value: Token::CloseBracket,
+            span,
+        }) => ParseError::UnbalancedParen(']', span),
+        _ => ParseError::ParenExpected,
+    }
+}
+
+/// Consumes the delimiter closing a list, which must be `close`. Kept out of
+/// `parse_list` so that its stack frame stays small for deeply nested lists.
+#[inline(never)]
+fn close_list<I>(tokens: &mut Peekable<I>, open: char, close: char) -> Result<(), ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    let Some(token) = tokens.next() else {
+        return Ok(());
+    };
+
+    let found = match token.value {
+        Token::CloseParen => ')',
+        _ => ']',
+    };
+
+    if found != close {
+        return Err(ParseError::MismatchedDelimiter(open, found, token.span));
+    }
+
+    Ok(())
+}
+
+/// Parses a single expression inside of a list nested `depth` levels deep
+fn parse_expr<I>(
+    tokens: &mut Peekable<I>,
+    spans: &mut Vec<Span>,
+    depth: usize,
+) -> Result<Expr, ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    skip_datum_comments(tokens, spans, depth)?;
+
+    match peek(tokens) {
+        Some(Token::OpenParen | Token::OpenBracket) => parse_list(tokens, spans, depth + 1),
+        Some(Token::ReaderMacro(_)) => parse_reader_macro(tokens, spans, depth),
+        Some(Token::CloseParen | Token::CloseBracket | Token::DatumComment) | None => {
+            Err(ParseError::ExpressionExpected)
+        }
+        Some(_) => match tokens.next() {
+            Some(token) => {
+                spans.push(token.span);
+
+                parse_atom(token)
+            }
+            None => Err(ParseError::ExpressionExpected),
+        },
+    }
+}
+
+/// Parses the expression following the prefix of a reader macro and expands
+/// it with the macro
+#[inline(never)]
+fn parse_reader_macro<I>(
+    tokens: &mut Peekable<I>,
+    spans: &mut Vec<Span>,
+    depth: usize,
+) -> Result<Expr, ParseError>
+where
+    I: Iterator<Item = Spanned<Token>>,
+{
+    if depth >= MAX_NESTING_DEPTH {
+        return Err(ParseError::NestingTooDeep(MAX_NESTING_DEPTH));
+    }
+
+    let (prefix, span) = match tokens.next() {
+        Some(Spanned {
+            value: Token::ReaderMacro(prefix),
+            span,
+        }) => (prefix, span),
+        _ => return Err(ParseError::ExpressionExpected),
+    };
+
+    let Some((_, expand)) = READER_MACROS.iter().find(|(name, _)| *name == prefix) else {
+        return Err(ParseError::ExpressionExpected);
+    };
+
+    let parsed = spans.len();
+    let expr = parse_expr(tokens, spans, depth + 1)?;
+    let mut expr_spans = Some(spans.split_off(parsed));
+
+    let expanded = expand(expr.clone());
+    locate_expansion(&expanded, &expr, span, &mut expr_spans, spans);
+
+    Ok(expanded)
+}
+
+/// Records the positions of the expressions of a reader macro expansion. The
+/// expanded expression keeps its own, the rest start at the prefix.
+fn locate_expansion(
+    expanded: &Expr,
+    expr: &Expr,
+    prefix: Span,
+    expr_spans: &mut Option<Vec<Span>>,
+    spans: &mut Vec<Span>,
+) {
+    if expanded == expr {
+        if let Some(own) = expr_spans.take() {
+            spans.extend(own);
+            return;
         }
     }
 
-    Ok(Expr::List(exprs))
+    spans.push(prefix);
+
+    if let Expr::List(list) = expanded {
+        for element in list {
+            locate_expansion(element, expr, prefix, expr_spans, spans);
+        }
+    }
+}
+
+/// Wraps an expression in a list starting with a keyword
+fn wrap(keyword: &str, expr: Expr) -> Expr {
+    Expr::List(vec![Expr::Keyword(keyword.to_string()), expr])
+}
+
+/// Converts a token that is not a delimiter or a reader macro into an
+/// expression.
+/// Kept out of `parse_expr` so that its stack frame stays small for deeply
+/// nested lists.
+#[inline(never)]
+fn parse_atom(token: Spanned<Token>) -> Result<Expr, ParseError> {
+    let expr = match token.value {
+        Token::Integer(integer) => Expr::Integer(integer),
+        Token::BigInt(integer) => Expr::BigInt(integer),
+        Token::Rational(numerator, denominator) => big_rational(*numerator, *denominator),
+        Token::Float(float) => Expr::Float(float),
+        Token::String(string) => Expr::String(string),
+        // Characters are strings of length one
+        Token::Char(char) => Expr::String(char.to_string()),
+        Token::Bytes(bytes) => Expr::Bytes(bytes),
+        Token::Boolean(boolean) => Expr::Boolean(boolean),
+        Token::Nil => Expr::Nil,
+        Token::If => Expr::If,
+        Token::BinaryOp(operator) => Expr::Op(operator),
+        Token::Keyword(keyword) => Expr::Keyword(keyword),
+        Token::Symbol(symbol) => Expr::Symbol(symbol),
+        // Improper lists cannot be represented, lists are not made of pairs
+        Token::Dot => return Err(ParseError::DottedPair(token.span)),
+        _ => return Err(ParseError::ExpressionExpected),
+    };
+
+    Ok(expr)
 }
@@ -0,0 +1,222 @@
+//! Promises, expressions evaluated lazily with `delay` and `force`, and
+//! streams built from them
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+#[derive(Debug)]
+enum PromiseState {
+    /// Expression that has not been evaluated yet, with the scope it is evaluated in
+    Delayed(Expr, PassableScope),
+    /// Value of the expression, evaluated the first time it has been forced
+    Forced(Expr),
+}
+
+/// Value that is evaluated once it is needed, shared by all of its copies
+#[derive(Debug, Clone)]
+pub struct Promise(Rc<RefCell<PromiseState>>);
+
+impl Promise {
+    /// Creates a promise to evaluate `expr` in `env`
+    pub fn new(expr: Expr, env: PassableScope) -> Self {
+        Promise(Rc::new(RefCell::new(PromiseState::Delayed(expr, env))))
+    }
+
+    /// Returns the value of the promise, evaluating it the first time
+    pub fn force(&self, depth: usize) -> Result<Expr, EvalError> {
+        let (expr, mut env) = match &*self.0.borrow() {
+            PromiseState::Forced(value) => return Ok(value.clone()),
+            PromiseState::Delayed(expr, env) => (expr.clone(), env.clone()),
+        };
+
+        let value = evaluate_expr(&expr, &mut env, depth + 1)?;
+
+        // The promise can be forced while it is being evaluated, the first value is kept
+        let mut state = self.0.borrow_mut();
+
+        match &*state {
+            PromiseState::Forced(value) => Ok(value.clone()),
+            PromiseState::Delayed(_, _) => {
+                *state = PromiseState::Forced(value.clone());
+
+                Ok(value)
+            }
+        }
+    }
+}
+
+/// Promises are compared by identity, like scopes
+impl PartialEq for Promise {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Evaluates `delay` built-in, creating a promise to evaluate the expression
+/// once it is forced.
+///
+/// Expected Lisper syntax:
+///
+/// ```(delay (expensive-computation))```
+pub fn evaluate_delay(list: &[Expr], env: &mut PassableScope) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("delay".to_string(), 1));
+    }
+
+    Ok(Expr::Promise(Promise::new(list[1].clone(), env.clone())))
+}
+
+/// Evaluates `force` built-in, returning the value of a promise. The delayed
+/// expression is evaluated only the first time, other values are returned as
+/// they are.
+///
+/// Expected Lisper syntax:
+///
+/// ```(force p)```
+pub fn evaluate_force(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("force".to_string(), 1));
+    }
+
+    match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::Promise(promise) => promise.force(depth),
+        value => Ok(value),
+    }
+}
+
+/// Splits a non-empty stream into its first element and the promise of the rest
+fn stream_parts(name: &'static str, stream: Expr) -> Result<(Expr, Promise), EvalError> {
+    match stream {
+        Expr::List(mut parts) => match (parts.pop(), parts.pop()) {
+            (Some(Expr::Promise(rest)), Some(first)) if parts.is_empty() => Ok((first, rest)),
+            _ => Err(EvalError::IllegalArgument(
+                name,
+                "Argument must be a stream",
+            )),
+        },
+        Expr::Nil => Err(EvalError::IllegalArgument(name, "Stream is empty")),
+        _ => Err(EvalError::IllegalArgument(
+            name,
+            "Argument must be a stream",
+        )),
+    }
+}
+
+/// Evaluates `stream-cons` built-in, creating a stream from its first element
+/// and an expression evaluating to the rest of it, which is delayed until it
+/// is needed. `nil` is the empty stream.
+///
+/// Expected Lisper syntax:
+///
+/// ```(stream-cons n (naturals (+ n 1)))```
+pub fn evaluate_stream_cons(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("stream-cons".to_string(), 2));
+    }
+
+    let first = evaluate_expr(&list[1], env, depth + 1)?;
+    let rest = Promise::new(list[2].clone(), env.clone());
+
+    Ok(Expr::List(vec![first, Expr::Promise(rest)]))
+}
+
+/// Evaluates `stream-car` built-in, returning the first element of a stream.
+///
+/// Expected Lisper syntax:
+///
+/// ```(stream-car s)```
+pub fn evaluate_stream_car(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("stream-car".to_string(), 1));
+    }
+
+    let stream = evaluate_expr(&list[1], env, depth + 1)?;
+
+    stream_parts("stream-car", stream).map(|(first, _)| first)
+}
+
+/// Evaluates `stream-cdr` built-in, returning the rest of a stream and
+/// evaluating it the first time.
+///
+/// Expected Lisper syntax:
+///
+/// ```(stream-cdr s)```
+pub fn evaluate_stream_cdr(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("stream-cdr".to_string(), 1));
+    }
+
+    let stream = evaluate_expr(&list[1], env, depth + 1)?;
+    let (_, rest) = stream_parts("stream-cdr", stream)?;
+
+    rest.force(depth)
+}
+
+/// Evaluates `stream-take` built-in, collecting at most `n` first elements of
+/// a stream into a list.
+///
+/// Expected Lisper syntax:
+///
+/// ```(stream-take (naturals 0) 5)```
+pub fn evaluate_stream_take(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("stream-take".to_string(), 2));
+    }
+
+    let mut stream = evaluate_expr(&list[1], env, depth + 1)?;
+
+    let count = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::Integer(count) if count >= 0 => count,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "stream-take",
+                "Count must be a non-negative integer",
+            ))
+        }
+    };
+
+    let mut taken = Vec::new();
+
+    for _ in 0..count {
+        if stream == Expr::Nil {
+            break;
+        }
+
+        let (first, rest) = stream_parts("stream-take", stream)?;
+        taken.push(first);
+        stream = rest.force(depth)?;
+    }
+
+    Ok(Expr::List(taken))
+}
@@ -0,0 +1,112 @@
+//! Quasiquote, quoted list templates with evaluated parts
+
+use crate::{
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+/// Returns the expression wrapped in `keyword`, eg. `x` for `(unquote x)`
+fn unwrap_keyword<'a>(expr: &'a Expr, keyword: &str) -> Option<&'a Expr> {
+    match expr {
+        Expr::List(list) => match list.as_slice() {
+            [Expr::Keyword(name), inner] if name == keyword => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Wraps an expression in `keyword`, eg. `(unquote x)` for `x`
+fn wrap_keyword(keyword: &str, expr: Expr) -> Expr {
+    Expr::List(vec![Expr::Keyword(keyword.to_string()), expr])
+}
+
+/// Fills in a template, evaluating the parts unquoted at `level` zero.
+///
+/// Nested quasiquotes increase the level, so that their unquoted parts are
+/// only evaluated once the inner template is filled in.
+fn expand(
+    template: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+    level: usize,
+) -> Result<Expr, EvalError> {
+    if let Some(inner) = unwrap_keyword(template, "unquote") {
+        return match level {
+            0 => evaluate_expr(inner, env, depth + 1),
+            _ => Ok(wrap_keyword(
+                "unquote",
+                expand(inner, env, depth + 1, level - 1)?,
+            )),
+        };
+    }
+
+    if let Some(inner) = unwrap_keyword(template, "unquote-splicing") {
+        return match level {
+            0 => Err(EvalError::IllegalArgument(
+                "unquote-splicing",
+                "Can only be used inside of a list",
+            )),
+            _ => Ok(wrap_keyword(
+                "unquote-splicing",
+                expand(inner, env, depth + 1, level - 1)?,
+            )),
+        };
+    }
+
+    if let Some(inner) = unwrap_keyword(template, "quasiquote") {
+        return Ok(wrap_keyword(
+            "quasiquote",
+            expand(inner, env, depth + 1, level + 1)?,
+        ));
+    }
+
+    let list = match template {
+        Expr::List(list) => list,
+        other => return Ok(other.clone()),
+    };
+
+    let mut expanded = Vec::with_capacity(list.len());
+
+    for item in list {
+        match unwrap_keyword(item, "unquote-splicing") {
+            Some(inner) if level == 0 => match evaluate_expr(inner, env, depth + 1)? {
+                Expr::List(values) => expanded.extend(values),
+                Expr::Nil => {}
+                _ => {
+                    return Err(EvalError::IllegalArgument(
+                        "unquote-splicing",
+                        "Spliced value must be a list",
+                    ))
+                }
+            },
+            _ => expanded.push(expand(item, env, depth + 1, level)?),
+        }
+    }
+
+    Ok(Expr::List(expanded))
+}
+
+/// Evaluates `quasiquote` built-in, returning the template without evaluating
+/// it, apart from the parts marked with `unquote` (`,`). Lists marked with
+/// `unquote-splicing` (`,@`) are inserted element by element.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (quasiquote (1 (unquote (+ 1 1)) 3))
+/// `(sum ,(+ 1 2) ,@(list 4 5))
+/// ```
+pub fn evaluate_quasiquote(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("quasiquote".to_string(), 1));
+    }
+
+    expand(&list[1], env, depth, 0)
+}
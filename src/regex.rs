@@ -0,0 +1,772 @@
+//! Regular expressions, matched in linear time by simulating all alternatives at once
+//!
+//! Supported syntax: literals, `.`, character classes like `[a-z_]` or `[^0-9]`,
+//! the escapes `\d`, `\w`, `\s` (and their negations `\D`, `\W`, `\S`),
+//! anchors `^`, `$` and `\b`, capturing groups `(...)`, non-capturing groups
+//! `(?:...)`, alternation `|` and the quantifiers `*`, `+`, `?`, `{n}`,
+//! `{n,}` and `{n,m}`, which are lazy when followed by `?`.
+
+use crate::{
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+};
+
+/// Highest count allowed in a `{n,m}` quantifier
+const MAX_REPEAT: usize = 1000;
+
+/// Highest number of instructions a pattern may compile into
+const MAX_PROGRAM_SIZE: usize = 100_000;
+
+/// Deepest nesting of groups allowed in a pattern
+const MAX_NESTING_DEPTH: usize = 100;
+
+/// Returned when a pattern is not a valid regular expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexError(pub &'static str);
+
+impl std::fmt::Display for RegexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid regular expression: {}", self.0)
+    }
+}
+
+/// Ranges of characters, matching any character outside of them when negated
+#[derive(Debug, Clone)]
+struct Class {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.ranges
+            .iter()
+            .any(|(low, high)| (*low..=*high).contains(&c))
+            != self.negated
+    }
+}
+
+/// Parsed pattern
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    /// Group with the index of its capture, `None` for non-capturing groups
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    /// Node repeated at least `min` times, at most `max` times if bounded
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+/// Instructions of the compiled pattern
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    /// Records the current position into a capture slot
+    Save(usize),
+    /// Continues at both targets, the first one being preferred
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// A compiled regular expression
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    /// Number of capturing groups, not counting the whole match
+    groups: usize,
+}
+
+impl Regex {
+    /// Compiles a pattern
+    pub fn new(pattern: &str) -> Result<Regex, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            position: 0,
+            groups: 0,
+        };
+
+        let node = parser.parse_alternation(0)?;
+
+        if parser.position < parser.chars.len() {
+            return Err(RegexError("Unmatched closing parenthesis"));
+        }
+
+        let mut program = vec![Inst::Save(0)];
+        compile(&node, &mut program)?;
+        program.push(Inst::Save(1));
+        program.push(Inst::Match);
+
+        Ok(Regex {
+            program,
+            groups: parser.groups,
+        })
+    }
+
+    /// Number of capturing groups, not counting the whole match
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    /// Finds the leftmost match starting at or after `start`, returning the
+    /// start and end of the whole match and of every group. Positions are
+    /// character indices.
+    pub fn captures_at(&self, text: &[char], start: usize) -> Option<Vec<Option<usize>>> {
+        let slots = 2 * (self.groups + 1);
+
+        let mut current: Vec<(usize, Vec<Option<usize>>)> = Vec::new();
+        let mut matched = None;
+
+        for position in start..=text.len() {
+            // A new attempt starts at every position until a match is found,
+            // with a lower priority than the attempts started before
+            if matched.is_none() {
+                current.push((0, vec![None; slots]));
+            }
+
+            // Only threads preferred over the match found could still win
+            if current.is_empty() {
+                break;
+            }
+
+            let mut next = Vec::new();
+            let mut visited = vec![false; self.program.len()];
+
+            for (pc, captures) in current {
+                if let Some(found) =
+                    self.follow(pc, captures, text, position, &mut visited, &mut next)
+                {
+                    // Threads with a lower priority cannot win anymore
+                    matched = Some(found);
+                    break;
+                }
+            }
+
+            current = next
+                .into_iter()
+                .filter_map(|(pc, captures)| {
+                    let c = *text.get(position)?;
+
+                    let consumed = match &self.program[pc] {
+                        Inst::Char(expected) => *expected == c,
+                        Inst::Any => c != '\n',
+                        Inst::Class(class) => class.matches(c),
+                        _ => false,
+                    };
+
+                    consumed.then_some((pc + 1, captures))
+                })
+                .collect();
+        }
+
+        matched
+    }
+
+    /// Follows the instructions that do not consume a character starting at
+    /// `pc`, in order of priority. Threads waiting for a character are added
+    /// to `next`, the captures are returned when the pattern matches.
+    fn follow(
+        &self,
+        pc: usize,
+        captures: Vec<Option<usize>>,
+        text: &[char],
+        position: usize,
+        visited: &mut [bool],
+        next: &mut Vec<(usize, Vec<Option<usize>>)>,
+    ) -> Option<Vec<Option<usize>>> {
+        // An explicit stack keeps long patterns from overflowing the call stack
+        let mut stack = vec![(pc, captures)];
+
+        while let Some((pc, mut captures)) = stack.pop() {
+            if visited[pc] {
+                continue;
+            }
+
+            visited[pc] = true;
+
+            match &self.program[pc] {
+                Inst::Char(_) | Inst::Any | Inst::Class(_) => next.push((pc, captures)),
+                Inst::Start if position == 0 => stack.push((pc + 1, captures)),
+                Inst::End if position == text.len() => stack.push((pc + 1, captures)),
+                Inst::Start | Inst::End => {}
+                Inst::WordBoundary(expected) => {
+                    let is_word = |i: usize| text.get(i).is_some_and(|c| is_word_char(*c));
+                    let before = position > 0 && is_word(position - 1);
+
+                    if (before != is_word(position)) == *expected {
+                        stack.push((pc + 1, captures));
+                    }
+                }
+                Inst::Save(slot) => {
+                    captures[*slot] = Some(position);
+                    stack.push((pc + 1, captures));
+                }
+                Inst::Split(first, second) => {
+                    stack.push((*second, captures.clone()));
+                    stack.push((*first, captures));
+                }
+                Inst::Jump(target) => stack.push((*target, captures)),
+                Inst::Match => return Some(captures),
+            }
+        }
+
+        None
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Recursive descent parser of patterns
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    /// Number of capturing groups seen so far
+    groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let matches = self.peek() == Some(expected);
+
+        if matches {
+            self.position += 1;
+        }
+
+        matches
+    }
+
+    fn parse_alternation(&mut self, depth: usize) -> Result<Node, RegexError> {
+        if depth > MAX_NESTING_DEPTH {
+            return Err(RegexError("Groups are nested too deeply"));
+        }
+
+        let mut alternatives = vec![self.parse_concat(depth)?];
+
+        while self.eat('|') {
+            alternatives.push(self.parse_concat(depth)?);
+        }
+
+        match alternatives.len() {
+            1 => Ok(alternatives.remove(0)),
+            _ => Ok(Node::Alternate(alternatives)),
+        }
+    }
+
+    fn parse_concat(&mut self, depth: usize) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+
+            let atom = self.parse_atom(depth)?;
+            nodes.push(self.parse_quantifiers(atom)?);
+        }
+
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_quantifiers(&mut self, mut node: Node) -> Result<Node, RegexError> {
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => match self.parse_bounds()? {
+                    Some(bounds) => bounds,
+                    // A brace not starting a quantifier is a literal
+                    None => return Ok(node),
+                },
+                _ => return Ok(node),
+            };
+
+            // Skips the quantifier, or the closing brace of bounds
+            self.position += 1;
+
+            if matches!(node, Node::Start | Node::End | Node::WordBoundary(_)) {
+                return Err(RegexError("Anchors cannot be repeated"));
+            }
+
+            let greedy = !self.eat('?');
+
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                greedy,
+            };
+        }
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}`, leaving the closing brace to be
+    /// skipped. `None` if the brace does not start a quantifier.
+    fn parse_bounds(&mut self) -> Result<Option<(usize, Option<usize>)>, RegexError> {
+        let start = self.position;
+        self.position += 1;
+
+        let min = self.parse_number();
+        let max = if self.eat(',') {
+            self.parse_number()
+        } else {
+            min
+        };
+
+        let bounds = match (min, self.peek()) {
+            (Some(min), Some('}')) => (min, max),
+            _ => {
+                self.position = start;
+                return Ok(None);
+            }
+        };
+
+        match bounds {
+            (min, _) if min > MAX_REPEAT => Err(RegexError("Repetition count is too large")),
+            (_, Some(max)) if max > MAX_REPEAT => Err(RegexError("Repetition count is too large")),
+            (min, Some(max)) if min > max => Err(RegexError("Repetition bounds are reversed")),
+            bounds => Ok(Some(bounds)),
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let start = self.position;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        let digits: String = self.chars[start..self.position].iter().collect();
+
+        // Numbers too long to fit are certainly too large to repeat
+        if digits.is_empty() {
+            None
+        } else {
+            Some(digits.parse().unwrap_or(usize::MAX))
+        }
+    }
+
+    fn parse_atom(&mut self, depth: usize) -> Result<Node, RegexError> {
+        match self.next() {
+            Some('(') => {
+                let index = if self.chars[self.position..].starts_with(&['?', ':']) {
+                    self.position += 2;
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+
+                let node = self.parse_alternation(depth + 1)?;
+
+                if !self.eat(')') {
+                    return Err(RegexError("Unclosed group"));
+                }
+
+                Ok(Node::Group(Box::new(node), index))
+            }
+            Some('[') => self.parse_class().map(Node::Class),
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('\\') => match self.next() {
+                Some('b') => Ok(Node::WordBoundary(true)),
+                Some('B') => Ok(Node::WordBoundary(false)),
+                Some(c) => Ok(escape_class(c).map_or(Node::Char(escape_char(c)), Node::Class)),
+                None => Err(RegexError("Pattern ends with a backslash")),
+            },
+            Some('*' | '+' | '?') => Err(RegexError("Nothing to repeat")),
+            Some(c) => Ok(Node::Char(c)),
+            None => Err(RegexError("Pattern ended unexpectedly")),
+        }
+    }
+
+    /// Parses a character class after its opening bracket
+    fn parse_class(&mut self) -> Result<Class, RegexError> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+
+        loop {
+            let low = match self.next() {
+                // A bracket right at the start is a literal
+                Some(']') if !first => break,
+                Some('\\') => match self.next() {
+                    Some(c) => match escape_class(c) {
+                        Some(class) if !class.negated => {
+                            ranges.extend(class.ranges);
+                            first = false;
+                            continue;
+                        }
+                        Some(_) => {
+                            return Err(RegexError("Negated escapes cannot be used in classes"))
+                        }
+                        None => escape_char(c),
+                    },
+                    None => return Err(RegexError("Pattern ends with a backslash")),
+                },
+                Some(c) => c,
+                None => return Err(RegexError("Unclosed character class")),
+            };
+
+            first = false;
+
+            let is_range = self.peek() == Some('-')
+                && self.chars.get(self.position + 1).is_some_and(|c| *c != ']');
+
+            if !is_range {
+                ranges.push((low, low));
+                continue;
+            }
+
+            self.position += 1;
+
+            let high = match self.next() {
+                Some('\\') => match self.next() {
+                    Some(c) if escape_class(c).is_none() => escape_char(c),
+                    _ => return Err(RegexError("Invalid range in character class")),
+                },
+                Some(c) => c,
+                None => return Err(RegexError("Unclosed character class")),
+            };
+
+            if low > high {
+                return Err(RegexError("Invalid range in character class"));
+            }
+
+            ranges.push((low, high));
+        }
+
+        Ok(Class { ranges, negated })
+    }
+}
+
+/// Returns the class of an escape like `\d`, `None` for other escapes
+fn escape_class(c: char) -> Option<Class> {
+    let ranges = match c.to_ascii_lowercase() {
+        'd' => vec![('0', '9')],
+        'w' => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+        's' => vec![(' ', ' '), ('\t', '\r')],
+        _ => return None,
+    };
+
+    Some(Class {
+        ranges,
+        negated: c.is_ascii_uppercase(),
+    })
+}
+
+/// Returns the character written by an escape like `\n` or `\.`
+fn escape_char(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        other => other,
+    }
+}
+
+/// Appends the instructions of a node to the program
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Result<(), RegexError> {
+    if program.len() > MAX_PROGRAM_SIZE {
+        return Err(RegexError("Pattern is too large"));
+    }
+
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class(class) => program.push(Inst::Class(class.clone())),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::WordBoundary(expected) => program.push(Inst::WordBoundary(*expected)),
+        Node::Group(node, None) => compile(node, program)?,
+        Node::Group(node, Some(index)) => {
+            program.push(Inst::Save(2 * index));
+            compile(node, program)?;
+            program.push(Inst::Save(2 * index + 1));
+        }
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program)?;
+            }
+        }
+        Node::Alternate(alternatives) => {
+            // Every alternative but the last one is preceded by a split to
+            // the next one and followed by a jump to the end
+            let mut jumps = Vec::new();
+
+            for (i, alternative) in alternatives.iter().enumerate() {
+                if i == alternatives.len() - 1 {
+                    compile(alternative, program)?;
+                    break;
+                }
+
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(alternative, program)?;
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+
+            for jump in jumps {
+                program[jump] = Inst::Jump(program.len());
+            }
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+
+            let prefer = |program: &mut Vec<Inst>, at: usize, node: usize, skip: usize| {
+                program[at] = if *greedy {
+                    Inst::Split(node, skip)
+                } else {
+                    Inst::Split(skip, node)
+                };
+            };
+
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Jump(0));
+                    compile(node, program)?;
+                    program.push(Inst::Jump(split));
+                    prefer(program, split, split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Jump(0));
+                        compile(node, program)?;
+                    }
+
+                    for split in splits {
+                        prefer(program, split, split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Evaluates `re-match` built-in, finding the first match of a pattern in a
+/// string. Returns a list of the whole match followed by the groups, where
+/// groups that did not participate are `nil`, or `false` without a match.
+///
+/// Expected Lisper syntax:
+///
+/// ```(re-match "(\\w+)@(\\w+)" "mail me at user@example")```
+pub fn evaluate_re_match(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("re-match".to_string(), 2));
+    }
+
+    let (regex, text) = evaluate_arguments("re-match", list, env, depth)?;
+
+    match regex.captures_at(&text, 0) {
+        Some(captures) => Ok(captures_to_list(&captures, &text)),
+        None => Ok(Expr::Boolean(false)),
+    }
+}
+
+/// Evaluates `re-find-all` built-in, finding all matches of a pattern in a
+/// string that do not overlap. Matches are returned as strings when the
+/// pattern has no groups, otherwise as lists like the ones of `re-match`.
+///
+/// Expected Lisper syntax:
+///
+/// ```(re-find-all "\\d+" "1 apple, 20 pears")```
+pub fn evaluate_re_find_all(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("re-find-all".to_string(), 2));
+    }
+
+    let (regex, text) = evaluate_arguments("re-find-all", list, env, depth)?;
+
+    let matches = find_all(&regex, &text)
+        .iter()
+        .map(|captures| match regex.groups() {
+            0 => slice(&captures[0..2], &text),
+            _ => captures_to_list(captures, &text),
+        })
+        .collect();
+
+    Ok(Expr::List(matches))
+}
+
+/// Evaluates `re-replace` built-in, replacing all matches of a pattern in a
+/// string. In the replacement, `$0` stands for the whole match, `$1` to `$9`
+/// for the groups and `$$` for a dollar sign.
+///
+/// Expected Lisper syntax:
+///
+/// ```(re-replace "(\\w+)@(\\w+)" "user@example" "$2 at $1")```
+pub fn evaluate_re_replace(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 4 {
+        return Err(EvalError::ArgumentCount("re-replace".to_string(), 3));
+    }
+
+    let (regex, text) = evaluate_arguments("re-replace", list, env, depth)?;
+
+    let replacement: Vec<char> = match evaluate_expr(&list[3], env, depth + 1)? {
+        Expr::String(replacement) => replacement.chars().collect(),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "re-replace",
+                "Replacement must be a string",
+            ))
+        }
+    };
+
+    let mut result = String::new();
+    let mut copied = 0;
+
+    for captures in find_all(&regex, &text) {
+        let (start, end) = (captures[0].unwrap_or(0), captures[1].unwrap_or(0));
+        result.extend(&text[copied..start]);
+
+        let mut chars = replacement.iter().peekable();
+
+        while let Some(c) = chars.next() {
+            let group = match (c, chars.peek()) {
+                ('$', Some('$')) => {
+                    chars.next();
+                    result.push('$');
+                    continue;
+                }
+                ('$', Some(digit)) => digit.to_digit(10),
+                _ => None,
+            };
+
+            match group.filter(|group| *group as usize <= regex.groups()) {
+                Some(group) => {
+                    chars.next();
+
+                    let group = group as usize;
+
+                    if let (Some(start), Some(end)) = (captures[2 * group], captures[2 * group + 1])
+                    {
+                        result.extend(&text[start..end]);
+                    }
+                }
+                None => result.push(*c),
+            }
+        }
+
+        copied = end;
+    }
+
+    result.extend(&text[copied..]);
+
+    Ok(Expr::String(result))
+}
+
+/// Finds all matches that do not overlap, an empty match is never directly
+/// followed by another match at the same position
+fn find_all(regex: &Regex, text: &[char]) -> Vec<Vec<Option<usize>>> {
+    let mut matches = Vec::new();
+    let mut position = 0;
+
+    while position <= text.len() {
+        let captures = match regex.captures_at(text, position) {
+            Some(captures) => captures,
+            None => break,
+        };
+
+        let (start, end) = (captures[0].unwrap_or(0), captures[1].unwrap_or(0));
+        position = if end == start { end + 1 } else { end };
+
+        matches.push(captures);
+    }
+
+    matches
+}
+
+/// Evaluates the pattern and the string matched against it
+fn evaluate_arguments(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<(Regex, Vec<char>), EvalError> {
+    let regex = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::String(pattern) => {
+            Regex::new(&pattern).map_err(|err| EvalError::IllegalArgument(name, err.0))?
+        }
+        _ => return Err(EvalError::IllegalArgument(name, "Pattern must be a string")),
+    };
+
+    match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::String(text) => Ok((regex, text.chars().collect())),
+        _ => Err(EvalError::IllegalArgument(
+            name,
+            "Matched value must be a string",
+        )),
+    }
+}
+
+/// Converts the positions of a match into a list of the whole match and the groups
+fn captures_to_list(captures: &[Option<usize>], text: &[char]) -> Expr {
+    Expr::List(captures.chunks(2).map(|span| slice(span, text)).collect())
+}
+
+/// Returns the text between two positions, `nil` if the group did not participate
+fn slice(span: &[Option<usize>], text: &[char]) -> Expr {
+    match span {
+        [Some(start), Some(end)] => Expr::String(text[*start..*end].iter().collect()),
+        _ => Expr::Nil,
+    }
+}
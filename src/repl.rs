@@ -14,8 +14,8 @@ use rustyline_derive::{Completer, Helper, Validator};
 
 use crate::eval::evaluate;
 use crate::lexer::lex;
-use crate::parser::parse;
-use crate::scope::Scope;
+use crate::parser::parse_program;
+use crate::scope::PassableScope;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -127,7 +127,7 @@ pub struct CommandHint {
     complete_up_to: usize,
 }
 
-pub fn run_repl() -> rustyline::Result<()> {
+pub fn run_repl(mut env: PassableScope) -> rustyline::Result<()> {
     println!(
         "
 ==========  Lisper v{}  ==========
@@ -153,8 +153,6 @@ To exit the REPL, type `exit`.
         EventHandler::Conditional(Box::new(TabEventHandler)),
     );
 
-    let mut env = Scope::new().wrap();
-
     loop {
         let line = editor.readline("> ")?.trim().to_string();
 
@@ -164,18 +162,28 @@ To exit the REPL, type `exit`.
             return Ok(());
         }
 
-        let tokens = lex(&line);
+        let tokens = match lex(&line) {
+            Ok(tokens) => tokens,
+            Err(lexer_error) => {
+                println!("LEXER ERROR: {lexer_error}");
+                continue;
+            }
+        };
 
-        match parse(&mut tokens.into_iter().peekable()) {
+        match parse_program(&mut tokens.into_iter().peekable()) {
             Err(parser_error) => {
                 println!("PARSER ERROR: {parser_error}");
             }
-            Ok(parsed) => {
-                let evaluated = evaluate(&parsed, &mut env);
-
-                match evaluated {
-                    Ok(result) => println!("{result}"),
-                    Err(err) => println!("EVAL ERROR: {err}"),
+            Ok(program) => {
+                // Every expression on the line is evaluated and printed in turn
+                for expr in &program {
+                    match evaluate(expr, &mut env) {
+                        Ok(result) => println!("{result}"),
+                        Err(err) => {
+                            println!("EVAL ERROR: {err}");
+                            break;
+                        }
+                    }
                 }
             }
         }
@@ -1,24 +1,138 @@
 //! Scope and environment handling
 
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    rc::Rc,
+};
 
-use crate::expr::Expr;
+use crate::{
+    condition::ConditionType,
+    debugger::PassableDebugger,
+    eval::Truthiness,
+    expr::Expr,
+    lexer::GENSYM_PREFIX,
+    span::{SourceMap, Span},
+};
+
+/// Destination of everything printed by Lisper code
+#[derive(Debug, Default)]
+pub enum Output {
+    /// Standard output, diagnostics go to standard error
+    #[default]
+    Stdout,
+    /// Collected in memory, eg. to be sent to a notebook, together with diagnostics
+    Captured(String),
+}
+
+/// Holds interpreter-wide state shared by every scope derived from the same root
+#[derive(Debug, Default)]
+pub struct Globals {
+    /// Canonical paths of all modules that have already been imported
+    pub imported: HashSet<PathBuf>,
+    /// Scopes of modules that have been imported under a namespace, by canonical path
+    pub modules: HashMap<PathBuf, PassableScope>,
+    /// File that is currently being evaluated, `None` in the REPL
+    pub current_file: Option<PathBuf>,
+    /// Directories searched for modules imported by their bare name
+    pub search_path: Vec<PathBuf>,
+    /// Number of symbols generated by `gensym` so far
+    gensym_counter: usize,
+    /// Condition types defined by `define-condition`
+    pub conditions: HashMap<String, ConditionType>,
+    /// Which values count as true in conditional forms
+    pub truthiness: Truthiness,
+    /// Where printed output goes
+    pub output: Output,
+    /// Debugger notified about the evaluation
+    pub debugger: Option<PassableDebugger>,
+    /// Evaluation steps left before evaluation is stopped, unlimited if `None`
+    pub fuel: Option<u64>,
+    /// Untrusted code is evaluated, files cannot be accessed
+    pub sandboxed: bool,
+    /// Command line arguments passed to the evaluated script
+    pub args: Vec<String>,
+    /// Positions of the expressions of the evaluated program
+    pub source_map: SourceMap,
+    /// Position of the innermost expression of the program an error has been
+    /// raised in, until the error is handled
+    pub error_span: Option<Span>,
+}
+
+impl Globals {
+    /// Writes printed output of Lisper code
+    pub fn print(&mut self, text: &str) {
+        match &mut self.output {
+            Output::Stdout => print!("{text}"),
+            Output::Captured(captured) => captured.push_str(text),
+        }
+    }
+
+    /// Writes diagnostics of Lisper code, kept apart from the printed output
+    /// unless it is captured
+    pub fn eprint(&mut self, text: &str) {
+        match &mut self.output {
+            Output::Stdout => eprint!("{text}"),
+            Output::Captured(captured) => captured.push_str(text),
+        }
+    }
+
+    /// Returns the output captured so far, leaving the buffer empty
+    pub fn take_output(&mut self) -> String {
+        match &mut self.output {
+            Output::Stdout => String::new(),
+            Output::Captured(captured) => std::mem::take(captured),
+        }
+    }
+
+    /// Generates a symbol name that is distinct from every other generated one.
+    ///
+    /// Generated names start with `#:`, which the lexer rejects in the source
+    /// code, so code generated with them does not capture user variables.
+    pub fn gensym(&mut self) -> String {
+        self.gensym_counter += 1;
+
+        format!("{GENSYM_PREFIX}g{}", self.gensym_counter)
+    }
+}
+
+pub type PassableGlobals = Rc<RefCell<Globals>>;
+
+/// A value stored in a `Scope`
+#[derive(Debug)]
+struct Binding {
+    value: Expr,
+    /// Constant bindings cannot be redefined
+    constant: bool,
+}
 
 /// Holds information on any stored variables and functions
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct Scope {
     /// Stores all defined variables and functions
-    entities: HashMap<String, Expr>,
+    entities: HashMap<String, Binding>,
     /// Reference to parent scope, eg. when calling a function
     parent: Option<PassableScope>,
+    /// Scopes of namespaced modules, accessible as `<namespace>/<name>`
+    namespaces: HashMap<String, PassableScope>,
+    /// Interpreter-wide state, shared with the parent scope
+    globals: PassableGlobals,
 }
 
 impl Scope {
     /// Create a new scope with defaults
     pub fn new() -> Self {
+        Scope::with_globals(Rc::new(RefCell::new(Globals::default())))
+    }
+
+    /// Create a new top-level scope sharing the provided interpreter-wide state
+    pub fn with_globals(globals: PassableGlobals) -> Self {
         Scope {
             entities: HashMap::new(),
             parent: None,
+            namespaces: HashMap::new(),
+            globals,
         }
     }
 
@@ -29,28 +143,133 @@ impl Scope {
 
     /// Creates a new `Scope` while setting `from` to be the parent
     pub fn extend(from: PassableScope) -> PassableScope {
+        let globals = from.borrow().globals.clone();
+
         Scope {
             entities: HashMap::new(),
             parent: Some(from),
+            namespaces: HashMap::new(),
+            globals,
         }
         .wrap()
     }
 
+    /// Returns the interpreter-wide state of this `Scope`
+    pub fn globals(&self) -> PassableGlobals {
+        self.globals.clone()
+    }
+
     /// Set a value in a `Scope`
     pub fn set(&mut self, key: String, value: Expr) {
-        self.entities.insert(key, value);
+        self.entities.insert(
+            key,
+            Binding {
+                value,
+                constant: false,
+            },
+        );
+    }
+
+    /// Set a value in a `Scope` that cannot be redefined
+    pub fn set_constant(&mut self, key: String, value: Expr) {
+        self.entities.insert(
+            key,
+            Binding {
+                value,
+                constant: true,
+            },
+        );
     }
 
-    /// Gets a value from a `Scope`
+    /// Replaces the value of an existing binding in a `Scope` or its parents.
+    ///
+    /// Returns `false` if `key` is not bound anywhere.
+    pub fn update(&mut self, key: &str, value: Expr) -> bool {
+        match self.entities.get_mut(key) {
+            Some(binding) => {
+                binding.value = value;
+                true
+            }
+            None => self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.borrow_mut().update(key, value)),
+        }
+    }
+
+    /// Checks whether `key` is bound to a constant in a `Scope` or its parents
+    pub fn is_constant(&self, key: &str) -> bool {
+        match self.entities.get(key) {
+            Some(binding) => binding.constant,
+            None => self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.borrow().is_constant(key)),
+        }
+    }
+
+    /// Returns all values stored directly in a `Scope`, sorted by their names
+    pub fn entries(&self) -> Vec<(String, Expr)> {
+        let mut entries: Vec<_> = self
+            .entities
+            .iter()
+            .map(|(key, binding)| (key.clone(), binding.value.clone()))
+            .collect();
+
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        entries
+    }
+
+    /// Makes the entities of `module` accessible as `<namespace>/<name>`
+    pub fn set_namespace(&mut self, namespace: String, module: PassableScope) {
+        self.namespaces.insert(namespace, module);
+    }
+
+    /// Gets a value from a `Scope`, qualified names are looked up in their namespace
     pub fn get(&self, key: &str) -> Option<Expr> {
+        self.get_local(key).or_else(|| {
+            let (namespace, name) = key.split_once('/')?;
+
+            self.get_namespace(namespace)?.borrow().get(name)
+        })
+    }
+
+    /// Gets a value from a `Scope` or its parents, ignoring namespaces
+    fn get_local(&self, key: &str) -> Option<Expr> {
         match self.entities.get(key) {
-            Some(value) => Some(value.clone()),
+            Some(binding) => Some(binding.value.clone()),
             None => self
                 .parent
                 .as_ref()
-                .and_then(|parent| parent.borrow().get(key)),
+                .and_then(|parent| parent.borrow().get_local(key)),
         }
     }
+
+    /// Gets the scope of a namespaced module
+    fn get_namespace(&self, namespace: &str) -> Option<PassableScope> {
+        match self.namespaces.get(namespace) {
+            Some(module) => Some(module.clone()),
+            None => self
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().get_namespace(namespace)),
+        }
+    }
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Scope::new()
+    }
+}
+
+/// Scopes are compared by identity. Comparing their contents could recurse
+/// forever, because functions hold the scope they are defined in.
+impl PartialEq for Scope {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
 }
 
 pub type PassableScope = Rc<RefCell<Scope>>;
@@ -0,0 +1,77 @@
+//! Locations of tokens and expressions in the source code
+
+use std::collections::HashMap;
+
+use crate::expr::Expr;
+
+/// Position where a token starts in the source code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// Line counted from one
+    pub line: usize,
+    /// Column counted in characters from one
+    pub col: usize,
+    /// Offset in bytes from the start of the source code
+    pub offset: usize,
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// A value together with the position where it starts in the source code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Self {
+        Spanned { value, span }
+    }
+}
+
+/// Positions of the expressions of a parsed program in its source code.
+///
+/// Expressions are told apart by their address, so positions are known for
+/// the parsed expressions only, not for their copies, and only as long as the
+/// program is not changed.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    spans: HashMap<*const Expr, Span>,
+}
+
+impl SourceMap {
+    /// Maps every expression of the program to its span, with the spans
+    /// given in the order the expressions start in the source code
+    pub(crate) fn new(program: &[Expr], spans: &[Span]) -> Self {
+        let mut source_map = SourceMap::default();
+        let mut spans = spans.iter();
+
+        for expr in program {
+            source_map.insert(expr, &mut spans);
+        }
+
+        source_map
+    }
+
+    fn insert<'a>(&mut self, expr: &Expr, spans: &mut impl Iterator<Item = &'a Span>) {
+        if let Some(span) = spans.next() {
+            self.spans.insert(expr, *span);
+        }
+
+        if let Expr::List(list) = expr {
+            for element in list {
+                self.insert(element, spans);
+            }
+        }
+    }
+
+    /// Returns the position of an expression of the program
+    pub fn get(&self, expr: &Expr) -> Option<Span> {
+        self.spans.get(&(expr as *const Expr)).copied()
+    }
+}
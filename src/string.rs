@@ -0,0 +1,509 @@
+//! String manipulation, strings are indexed by characters rather than bytes
+
+use crate::{
+    arithmetic::{big_rational, integer},
+    bigint::BigInt,
+    eval::{consume_fuel, evaluate_expr, EvalError},
+    expr::Expr,
+    lexer::{lex, Token},
+    scope::PassableScope,
+};
+
+/// Evaluates `string-append` built-in, joining strings into a new string.
+///
+/// Expected Lisper syntax:
+///
+/// ```(string-append "Hello, " "world")```
+pub fn evaluate_string_append(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    let mut result = String::new();
+
+    for expr in &list[1..] {
+        result.push_str(&evaluate_string("string-append", expr, env, depth)?);
+    }
+
+    Ok(Expr::String(result))
+}
+
+/// Evaluates `string-length` built-in, returning the number of characters of
+/// a string.
+///
+/// Expected Lisper syntax:
+///
+/// ```(string-length "héllo")```
+pub fn evaluate_string_length(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("string-length".to_string(), 1));
+    }
+
+    let string = evaluate_string("string-length", &list[1], env, depth)?;
+
+    Ok(Expr::Integer(string.chars().count() as i64))
+}
+
+/// Evaluates `substring` built-in, returning the characters of a string from
+/// the start index up to but excluding the end index, which defaults to the
+/// end of the string.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (substring "Hello, world" 7)
+/// (substring "Hello, world" 0 5)
+/// ```
+pub fn evaluate_substring(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 && list.len() != 4 {
+        return Err(EvalError::ArgumentCount("substring".to_string(), 3));
+    }
+
+    let chars: Vec<char> = evaluate_string("substring", &list[1], env, depth)?
+        .chars()
+        .collect();
+
+    let start = evaluate_index("substring", &list[2], env, depth)?;
+    let end = match list.get(3) {
+        Some(end) => evaluate_index("substring", end, env, depth)?,
+        None => chars.len() as i64,
+    };
+
+    let position = |index: i64| match usize::try_from(index) {
+        Ok(position) if position <= chars.len() => Ok(position),
+        _ => Err(EvalError::IndexOutOfRange("substring", index, chars.len())),
+    };
+
+    let (start, end) = (position(start)?, position(end)?);
+
+    if start > end {
+        return Err(EvalError::IllegalArgument(
+            "substring",
+            "Start must not be after the end",
+        ));
+    }
+
+    Ok(Expr::String(chars[start..end].iter().collect()))
+}
+
+/// Evaluates `string-ref` built-in, returning the character of a string at
+/// an index counted from zero, as a string of its own.
+///
+/// Expected Lisper syntax:
+///
+/// ```(string-ref "héllo" 1)```
+pub fn evaluate_string_ref(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("string-ref".to_string(), 2));
+    }
+
+    let string = evaluate_string("string-ref", &list[1], env, depth)?;
+    let index = evaluate_index("string-ref", &list[2], env, depth)?;
+
+    let char = usize::try_from(index)
+        .ok()
+        .and_then(|position| string.chars().nth(position));
+
+    match char {
+        Some(char) => Ok(Expr::String(char.to_string())),
+        None => Err(EvalError::IndexOutOfRange(
+            "string-ref",
+            index,
+            string.chars().count(),
+        )),
+    }
+}
+
+/// Evaluates `trim`, `upcase` and `downcase` built-ins, removing whitespace
+/// around a string or converting it to upper or lower case.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (trim "  padded  ")
+/// (upcase "Hello")
+/// ```
+pub fn evaluate_string_transform(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 1));
+    }
+
+    let string = evaluate_string(name, &list[1], env, depth)?;
+
+    let result = match name {
+        "trim" => string.trim().to_string(),
+        "upcase" => string.to_uppercase(),
+        _ => string.to_lowercase(),
+    };
+
+    Ok(Expr::String(result))
+}
+
+/// Largest width in characters a string can be padded to
+const MAX_PAD_WIDTH: usize = 1 << 24;
+
+/// Evaluates `pad-left` and `pad-right` built-ins, extending a string to a
+/// width in characters by adding copies of a padding character before or
+/// after it. The padding is a space by default, longer strings are kept as
+/// they are.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (pad-left "42" 5 "0")
+/// (pad-right "name" 10)
+/// ```
+pub fn evaluate_pad(
+    name: &'static str,
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 && list.len() != 4 {
+        return Err(EvalError::ArgumentCount(name.to_string(), 3));
+    }
+
+    let string = evaluate_string(name, &list[1], env, depth)?;
+
+    let width = match evaluate_expr(&list[2], env, depth + 1)? {
+        Expr::Integer(width) if width >= 0 => width as usize,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                name,
+                "Width must be a non-negative number",
+            ))
+        }
+    };
+    let missing = width.saturating_sub(string.chars().count());
+    consume_fuel(env, missing as u64)?;
+
+    if width > MAX_PAD_WIDTH {
+        return Err(EvalError::IllegalArgument(name, "Width is too large"));
+    }
+
+    let padding = match list.get(3) {
+        Some(padding) => {
+            let padding = evaluate_string(name, padding, env, depth)?;
+            let mut chars = padding.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => char,
+                _ => {
+                    return Err(EvalError::IllegalArgument(
+                        name,
+                        "Padding must be a single character",
+                    ))
+                }
+            }
+        }
+        None => ' ',
+    };
+
+    let padding = padding.to_string().repeat(missing);
+
+    match name {
+        "pad-left" => Ok(Expr::String(padding + &string)),
+        _ => Ok(Expr::String(string + &padding)),
+    }
+}
+
+/// Evaluates `format` built-in, filling the directives of a template with the
+/// arguments. `~a` inserts a value as `print` outputs it, `~s` as it is
+/// written in code with strings quoted, `~%` inserts a newline and `~~` a
+/// tilde. With `true` before the template, the result is printed instead of
+/// returned.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (format "x=~a y=~s" 1 "two")
+/// (format true "~a apples~%" 3)
+/// ```
+pub fn evaluate_format(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 2 {
+        return Err(EvalError::ArgumentCount("format".to_string(), 1));
+    }
+
+    let values = list[1..]
+        .iter()
+        .map(|expr| evaluate_expr(expr, env, depth + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (printed, template, args) = match values.as_slice() {
+        [Expr::Boolean(true), Expr::String(template), args @ ..] => (true, template, args),
+        [Expr::String(template), args @ ..] => (false, template, args),
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "format",
+                "Template must be a string",
+            ))
+        }
+    };
+
+    let mut result = String::new();
+    let mut args = args.iter();
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some(directive @ ('a' | 's')) => {
+                let arg = args.next().ok_or(EvalError::IllegalArgument(
+                    "format",
+                    "Not enough arguments for the directives",
+                ))?;
+
+                match directive {
+                    'a' => result.push_str(&display(arg)),
+                    _ => result.push_str(&arg.to_string()),
+                }
+            }
+            Some('%') => result.push('\n'),
+            Some('~') => result.push('~'),
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "format",
+                    "Unknown directive, expected ~a, ~s, ~% or ~~",
+                ))
+            }
+        }
+    }
+
+    if args.len() > 0 {
+        return Err(EvalError::IllegalArgument(
+            "format",
+            "Too many arguments for the directives",
+        ));
+    }
+
+    if printed {
+        env.borrow().globals().borrow_mut().print(&result);
+
+        return Ok(Expr::Nil);
+    }
+
+    Ok(Expr::String(result))
+}
+
+/// Writes a value as `print` outputs it, strings without quotes
+pub fn display(value: &Expr) -> String {
+    match value {
+        Expr::String(string) => string.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Evaluates `string->number` built-in, parsing a number written the same
+/// way as in Lisper code. With a radix between 2 and 36, an integer written
+/// in that base is parsed.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (string->number "1/3")
+/// (string->number "ff" 16)
+/// ```
+pub fn evaluate_string_to_number(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 && list.len() != 3 {
+        return Err(EvalError::ArgumentCount("string->number".to_string(), 2));
+    }
+
+    let text = evaluate_string("string->number", &list[1], env, depth)?;
+    let radix = evaluate_radix("string->number", list.get(2), env, depth)?;
+
+    let number = if radix == 10 {
+        parse_decimal(text.trim())
+    } else {
+        parse_integer(text.trim(), radix)
+    };
+
+    number.ok_or(EvalError::IllegalArgument(
+        "string->number",
+        "String is not a number",
+    ))
+}
+
+/// Evaluates `number->string` built-in, writing a number the same way as in
+/// Lisper code. With a radix between 2 and 36, an integer is written in that
+/// base using lowercase letters for digits above 9.
+///
+/// Expected Lisper syntax:
+///
+/// ```
+/// (number->string 1.5)
+/// (number->string 255 16)
+/// ```
+pub fn evaluate_number_to_string(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 && list.len() != 3 {
+        return Err(EvalError::ArgumentCount("number->string".to_string(), 2));
+    }
+
+    let number = evaluate_expr(&list[1], env, depth + 1)?;
+    let radix = evaluate_radix("number->string", list.get(2), env, depth)?;
+
+    let value = match (&number, radix) {
+        (
+            Expr::Integer(_)
+            | Expr::BigInt(_)
+            | Expr::Rational(_, _)
+            | Expr::BigRational(_, _)
+            | Expr::Float(_),
+            10,
+        ) => return Ok(Expr::String(number.to_string())),
+        (Expr::Integer(value), _) => BigInt::from_i128(*value as i128),
+        (Expr::BigInt(value), _) => value.clone(),
+        (Expr::Rational(_, _) | Expr::BigRational(_, _) | Expr::Float(_), _) => {
+            return Err(EvalError::IllegalArgument(
+                "number->string",
+                "Only integers can be written in a radix other than 10",
+            ))
+        }
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "number->string",
+                "Argument must be a number",
+            ))
+        }
+    };
+
+    Ok(Expr::String(format_integer(&value, radix)))
+}
+
+/// Parses a number literal, `None` if the text is not a single number
+fn parse_decimal(text: &str) -> Option<Expr> {
+    let tokens: Vec<Token> = lex(text)
+        .ok()?
+        .into_iter()
+        .map(|token| token.value)
+        .collect();
+
+    match tokens.as_slice() {
+        [Token::Integer(integer)] => Some(Expr::Integer(*integer)),
+        [Token::BigInt(integer)] => Some(Expr::BigInt(integer.clone())),
+        [Token::Rational(numerator, denominator)] => {
+            Some(big_rational(*numerator.clone(), *denominator.clone()))
+        }
+        [Token::Float(float)] => Some(Expr::Float(*float)),
+        _ => None,
+    }
+}
+
+/// Parses an integer with an optional sign written in the radix
+fn parse_integer(text: &str, radix: u32) -> Option<Expr> {
+    BigInt::parse_radix(text, radix).map(integer)
+}
+
+/// Writes an integer in the radix, digits above 9 are lowercase letters
+fn format_integer(value: &BigInt, radix: u32) -> String {
+    let base = BigInt::from_i128(radix as i128);
+    let mut rest = value.abs();
+    let mut digits = Vec::new();
+
+    // Digits are collected least significant first, zero still has one digit
+    while let Some((quotient, remainder)) = rest.div_rem(&base) {
+        let digit = remainder.to_i64().unwrap_or_default() as u32;
+        digits.push(std::char::from_digit(digit, radix).unwrap_or('0'));
+        rest = quotient;
+
+        if rest.is_zero() {
+            break;
+        }
+    }
+
+    if value < &BigInt::default() {
+        digits.push('-');
+    }
+
+    digits.iter().rev().collect()
+}
+
+/// Evaluates the optional radix of a number conversion, `10` if missing
+fn evaluate_radix(
+    name: &'static str,
+    expr: Option<&Expr>,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<u32, EvalError> {
+    let expr = match expr {
+        Some(expr) => expr,
+        None => return Ok(10),
+    };
+
+    match evaluate_expr(expr, env, depth + 1)? {
+        Expr::Integer(radix @ 2..=36) => Ok(radix as u32),
+        _ => Err(EvalError::IllegalArgument(
+            name,
+            "Radix must be a number between 2 and 36",
+        )),
+    }
+}
+
+/// Evaluates an argument of `name`, which must be a string
+pub(crate) fn evaluate_string(
+    name: &'static str,
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<String, EvalError> {
+    match evaluate_expr(expr, env, depth + 1)? {
+        Expr::String(string) => Ok(string),
+        _ => Err(EvalError::IllegalArgument(
+            name,
+            "Argument must be a string",
+        )),
+    }
+}
+
+/// Evaluates an index into a string, which must be an integer
+fn evaluate_index(
+    name: &'static str,
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<i64, EvalError> {
+    match evaluate_expr(expr, env, depth + 1)? {
+        Expr::Integer(index) => Ok(index),
+        _ => Err(EvalError::IllegalArgument(name, "Index must be an integer")),
+    }
+}
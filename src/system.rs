@@ -0,0 +1,149 @@
+//! Access to the environment of the interpreter process
+
+use crate::{eval::EvalError, expr::Expr, scope::PassableScope, string::evaluate_string};
+
+/// Evaluates `argv` built-in, returning the arguments passed to the script
+/// on the command line after its path, as a list of strings.
+///
+/// Expected Lisper syntax:
+///
+/// ```(argv)```
+pub fn evaluate_argv(list: &[Expr], env: &mut PassableScope) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 1 {
+        return Err(EvalError::ArgumentCount("argv".to_string(), 0));
+    }
+
+    let args = env.borrow().globals().borrow().args.clone();
+
+    Ok(Expr::List(args.into_iter().map(Expr::String).collect()))
+}
+
+/// Evaluates `getenv` built-in, returning the value of an environment variable
+/// or nil if it is not set.
+///
+/// Expected Lisper syntax:
+///
+/// ```(getenv <name>)```
+pub fn evaluate_getenv(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 2 {
+        return Err(EvalError::ArgumentCount("getenv".to_string(), 1));
+    }
+
+    let name = evaluate_variable_name("getenv", &list[1], env, depth)?;
+
+    Ok(match std::env::var_os(name) {
+        Some(value) => Expr::String(value.to_string_lossy().into_owned()),
+        None => Expr::Nil,
+    })
+}
+
+/// Evaluates `setenv` built-in, setting an environment variable of the
+/// interpreter process and of the processes it starts.
+///
+/// Expected Lisper syntax:
+///
+/// ```(setenv <name> <value>)```
+pub fn evaluate_setenv(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("setenv".to_string(), 2));
+    }
+
+    let name = evaluate_variable_name("setenv", &list[1], env, depth)?;
+    let value = evaluate_string("setenv", &list[2], env, depth)?;
+
+    if value.contains('\0') {
+        return Err(EvalError::IllegalArgument(
+            "setenv",
+            "Value must not contain a NUL character",
+        ));
+    }
+
+    std::env::set_var(name, value);
+
+    Ok(Expr::Nil)
+}
+
+/// Evaluates `exec` built-in, running a program with string arguments until it
+/// exits. Returns a list of its exit code (nil if it was killed by a signal),
+/// its standard output and its standard error. The program is looked up in
+/// `PATH` and reads no input.
+///
+/// Expected Lisper syntax:
+///
+/// ```(exec "ls" "-l" "/tmp")```
+pub fn evaluate_exec(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() < 2 {
+        return Err(EvalError::ArgumentCount("exec".to_string(), 1));
+    }
+
+    let program = evaluate_string("exec", &list[1], env, depth)?;
+    let args = list[2..]
+        .iter()
+        .map(|arg| evaluate_string("exec", arg, env, depth))
+        .collect::<Result<Vec<String>, EvalError>>()?;
+
+    if env.borrow().globals().borrow().sandboxed {
+        return Err(EvalError::IoFailed(
+            program,
+            "Processes cannot be started in the sandbox".to_string(),
+        ));
+    }
+
+    let output = std::process::Command::new(&program)
+        .args(args)
+        .output()
+        .map_err(|err| EvalError::IoFailed(program, err.to_string()))?;
+
+    Ok(Expr::List(vec![
+        output
+            .status
+            .code()
+            .map_or(Expr::Nil, |code| Expr::Integer(code as i64)),
+        Expr::String(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Expr::String(String::from_utf8_lossy(&output.stderr).into_owned()),
+    ]))
+}
+
+/// Evaluates the name of an environment variable, which cannot be accessed in
+/// the sandbox
+fn evaluate_variable_name(
+    name: &'static str,
+    expr: &Expr,
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<String, EvalError> {
+    let variable = evaluate_string(name, expr, env, depth)?;
+
+    if env.borrow().globals().borrow().sandboxed {
+        return Err(EvalError::IllegalArgument(
+            name,
+            "The environment cannot be accessed in the sandbox",
+        ));
+    }
+
+    // Such names would make the standard library panic
+    if variable.is_empty() || variable.contains(['=', '\0']) {
+        return Err(EvalError::IllegalArgument(
+            name,
+            "Name must be non-empty and must not contain '=' or a NUL character",
+        ));
+    }
+
+    Ok(variable)
+}
@@ -0,0 +1,306 @@
+//! Time of the system clock and formatting and parsing of timestamps.
+//! Timestamps are seconds since the Unix epoch, always in UTC.
+
+use std::{iter::Peekable, str::Chars};
+
+use crate::{
+    eval::{evaluate_expr, EvalError},
+    expr::Expr,
+    scope::PassableScope,
+    string::evaluate_string,
+};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Date and time of day broken down into its fields
+struct DateTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+}
+
+/// Evaluates `now` built-in, returning the current time as seconds since the
+/// Unix epoch.
+///
+/// Expected Lisper syntax:
+///
+/// ```(now)```
+pub fn evaluate_now(list: &[Expr]) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 1 {
+        return Err(EvalError::ArgumentCount("now".to_string(), 0));
+    }
+
+    let seconds = match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    };
+
+    Ok(Expr::Integer(seconds))
+}
+
+/// Evaluates `format-time` built-in, writing a timestamp as described by a
+/// format. The format supports `%Y`, `%m`, `%d`, `%H`, `%M` and `%S` for the
+/// numeric fields, `%b` and `%B` for the name of the month, `%a` and `%A`
+/// for the name of the day of the week and `%%` for a percent sign.
+///
+/// Expected Lisper syntax:
+///
+/// ```(format-time (now) "%Y-%m-%d %H:%M:%S")```
+pub fn evaluate_format_time(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("format-time".to_string(), 2));
+    }
+
+    let timestamp = match evaluate_expr(&list[1], env, depth + 1)? {
+        Expr::Integer(timestamp) => timestamp,
+        _ => {
+            return Err(EvalError::IllegalArgument(
+                "format-time",
+                "Time must be an integer",
+            ))
+        }
+    };
+    let format = evaluate_string("format-time", &list[2], env, depth)?;
+
+    let days = timestamp.div_euclid(86400);
+    let seconds = timestamp.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let weekday = WEEKDAYS[(days + 4).rem_euclid(7) as usize];
+
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => formatted.push_str(&format!("{year:04}")),
+            Some('m') => formatted.push_str(&format!("{month:02}")),
+            Some('d') => formatted.push_str(&format!("{day:02}")),
+            Some('H') => formatted.push_str(&format!("{:02}", seconds / 3600)),
+            Some('M') => formatted.push_str(&format!("{:02}", seconds / 60 % 60)),
+            Some('S') => formatted.push_str(&format!("{:02}", seconds % 60)),
+            Some('b') => formatted.push_str(&MONTHS[month as usize - 1][..3]),
+            Some('B') => formatted.push_str(MONTHS[month as usize - 1]),
+            Some('a') => formatted.push_str(&weekday[..3]),
+            Some('A') => formatted.push_str(weekday),
+            Some('%') => formatted.push('%'),
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "format-time",
+                    "Unknown directive in format",
+                ))
+            }
+        }
+    }
+
+    Ok(Expr::String(formatted))
+}
+
+/// Evaluates `parse-time` built-in, reading a timestamp written as described
+/// by a format with the directives of `format-time`. Fields missing from the
+/// format are taken from midnight of 1 January 1970.
+///
+/// Expected Lisper syntax:
+///
+/// ```(parse-time "2024-02-29 12:30:00" "%Y-%m-%d %H:%M:%S")```
+pub fn evaluate_parse_time(
+    list: &[Expr],
+    env: &mut PassableScope,
+    depth: usize,
+) -> Result<Expr, EvalError> {
+    // Check argument count
+    if list.len() != 3 {
+        return Err(EvalError::ArgumentCount("parse-time".to_string(), 2));
+    }
+
+    let text = evaluate_string("parse-time", &list[1], env, depth)?;
+    let format = evaluate_string("parse-time", &list[2], env, depth)?;
+
+    let mismatch = EvalError::IllegalArgument("parse-time", "String does not match the format");
+
+    let mut date = DateTime {
+        year: 1970,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+    let mut text = text.chars().peekable();
+    let mut format = format.chars();
+
+    while let Some(c) = format.next() {
+        if c != '%' {
+            if text.next() != Some(c) {
+                return Err(mismatch);
+            }
+
+            continue;
+        }
+
+        match format.next() {
+            Some('Y') => date.year = parse_number(&mut text, 4).ok_or(mismatch.clone())?,
+            Some('m') => date.month = parse_number(&mut text, 2).ok_or(mismatch.clone())?,
+            Some('d') => date.day = parse_number(&mut text, 2).ok_or(mismatch.clone())?,
+            Some('H') => date.hour = parse_number(&mut text, 2).ok_or(mismatch.clone())?,
+            Some('M') => date.minute = parse_number(&mut text, 2).ok_or(mismatch.clone())?,
+            Some('S') => date.second = parse_number(&mut text, 2).ok_or(mismatch.clone())?,
+            Some(directive @ ('b' | 'B')) => {
+                let index =
+                    parse_name(&mut text, &MONTHS, directive == 'b').ok_or(mismatch.clone())?;
+
+                date.month = index as i64 + 1;
+            }
+            Some(directive @ ('a' | 'A')) => {
+                parse_name(&mut text, &WEEKDAYS, directive == 'a').ok_or(mismatch.clone())?;
+            }
+            Some('%') => {
+                if text.next() != Some('%') {
+                    return Err(mismatch);
+                }
+            }
+            _ => {
+                return Err(EvalError::IllegalArgument(
+                    "parse-time",
+                    "Unknown directive in format",
+                ))
+            }
+        }
+    }
+
+    if text.next().is_some() {
+        return Err(mismatch);
+    }
+
+    if !(1..=12).contains(&date.month)
+        || !(1..=days_in_month(date.year, date.month)).contains(&date.day)
+        || date.hour > 23
+        || date.minute > 59
+        || date.second > 59
+    {
+        return Err(EvalError::IllegalArgument(
+            "parse-time",
+            "Date or time is out of range",
+        ));
+    }
+
+    Ok(Expr::Integer(
+        days_from_civil(date.year, date.month, date.day) * 86400
+            + date.hour * 3600
+            + date.minute * 60
+            + date.second,
+    ))
+}
+
+/// Reads a decimal number of at most `max_digits` digits
+fn parse_number(text: &mut Peekable<Chars>, max_digits: usize) -> Option<i64> {
+    let mut digits = String::new();
+
+    while digits.len() < max_digits {
+        match text.next_if(|c| c.is_ascii_digit()) {
+            Some(digit) => digits.push(digit),
+            None => break,
+        }
+    }
+
+    digits.parse().ok()
+}
+
+/// Reads one of the names, or of their first three letters when `short`,
+/// returning its index
+fn parse_name(text: &mut Peekable<Chars>, names: &[&str], short: bool) -> Option<usize> {
+    let mut word = String::new();
+
+    while let Some(c) = text.next_if(|c| c.is_ascii_alphabetic()) {
+        word.push(c);
+    }
+
+    names.iter().position(|name| {
+        let name = if short { &name[..3] } else { name };
+
+        name.eq_ignore_ascii_case(&word)
+    })
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts days since the Unix epoch to the year, month and day of the
+/// proleptic Gregorian calendar, counting in 400 year cycles from March
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Converts a date of the proleptic Gregorian calendar to days since the Unix
+/// epoch, the inverse of `civil_from_days`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
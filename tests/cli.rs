@@ -0,0 +1,42 @@
+//! Options of the command line interface
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs `lisper` with the arguments, feeding it the input, and returns what it printed
+fn lisper(args: &[&str], input: &str) -> String {
+    let mut process = Command::new(env!("CARGO_BIN_EXE_lisper"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    process
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let output = process.wait_with_output().unwrap();
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_sandbox_stops_processes_and_the_environment() {
+    let script = std::env::temp_dir().join(format!("lisper-sandbox-{}.lpr", std::process::id()));
+    std::fs::write(&script, "(println (getenv \"HOME\"))").unwrap();
+    let script = script.to_str().unwrap();
+
+    assert!(lisper(&["--sandbox", script], "").starts_with("EVAL ERROR"));
+    assert!(!lisper(&[script], "").starts_with("EVAL ERROR"));
+
+    let request = r#"{"jsonrpc": "2.0", "id": 1, "method": "execute", "params": {"code": "(exec \"true\")"}}"#;
+
+    assert!(lisper(&["kernel", "--sandbox"], request).contains("sandbox"));
+    assert!(!lisper(&["kernel"], request).contains("sandbox"));
+}
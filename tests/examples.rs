@@ -0,0 +1,127 @@
+//! Golden tests running every program in `examples/` and comparing its output
+//! with the expected output stored next to it in a `.out` file.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to overwrite the expected output after an
+//! intended change of behavior.
+
+use std::path::{Path, PathBuf};
+
+use lisper::{
+    eval::evaluate_source,
+    lexer::lex,
+    parser::parse_program_with_spans,
+    scope::{Output, Scope},
+};
+
+/// Stack size of the thread running an example, programs may recurse up to the recursion limit
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Runs a program and returns everything it printed, errors are reported the same way as by the CLI
+fn run_example(path: &Path) -> String {
+    let content = std::fs::read_to_string(path).expect("Example cannot be read");
+
+    let mut env = Scope::new().wrap();
+    let globals = env.borrow().globals();
+
+    {
+        let mut globals = globals.borrow_mut();
+        globals.current_file = std::fs::canonicalize(path).ok();
+        globals.output = Output::Captured(String::new());
+    }
+
+    let result = match lex(&content) {
+        Err(err) => Some(format!("LEXER ERROR: {err}\n")),
+        Ok(tokens) => match parse_program_with_spans(&mut tokens.into_iter().peekable()) {
+            Err(err) => Some(format!("PARSER ERROR: {err}\n")),
+            Ok((program, source_map)) => evaluate_source(&program, source_map, &mut env)
+                .err()
+                .map(|err| format!("EVAL ERROR: {err}\n")),
+        },
+    };
+
+    let mut output = globals.borrow_mut().take_output();
+    output.extend(result);
+
+    output
+}
+
+/// Returns the programs in `examples/`, sorted by name
+fn examples() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+
+    let mut examples: Vec<_> = std::fs::read_dir(dir)
+        .expect("Examples directory cannot be read")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lpr"))
+        .collect();
+
+    examples.sort();
+
+    examples
+}
+
+/// Describes the first line that differs between the expected and actual output
+fn describe_difference(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<_> = expected.lines().collect();
+    let actual_lines: Vec<_> = actual.lines().collect();
+
+    for i in 0..expected_lines.len().max(actual_lines.len()) {
+        let expected_line = expected_lines.get(i).copied().unwrap_or("<end of output>");
+        let actual_line = actual_lines.get(i).copied().unwrap_or("<end of output>");
+
+        if expected_line != actual_line {
+            return format!(
+                "line {}\n  expected: {expected_line}\n  actual:   {actual_line}",
+                i + 1
+            );
+        }
+    }
+
+    "trailing whitespace".to_string()
+}
+
+#[test]
+fn examples_print_expected_output() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut failures = Vec::new();
+
+    for example in examples() {
+        let snapshot = example.with_extension("out");
+
+        let actual = {
+            let path = example.clone();
+
+            std::thread::Builder::new()
+                .stack_size(STACK_SIZE)
+                .spawn(move || run_example(&path))
+                .expect("Thread cannot be spawned")
+                .join()
+                .unwrap_or_else(|_| format!("{} panicked", example.display()))
+        };
+
+        if update {
+            std::fs::write(&snapshot, &actual).expect("Snapshot cannot be written");
+            continue;
+        }
+
+        match std::fs::read_to_string(&snapshot) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}: {}",
+                example.display(),
+                describe_difference(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!(
+                "{}: missing {}",
+                example.display(),
+                snapshot.display()
+            )),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Output of examples differs, run with UPDATE_SNAPSHOTS=1 if this is intended\n\n{}",
+        failures.join("\n\n")
+    );
+}
@@ -0,0 +1,58 @@
+//! Parsing JSON texts
+
+use lisper::json::{Json, JsonError};
+
+#[test]
+fn numbers_follow_the_json_grammar() {
+    for text in ["1.", "-", "1.e3", "1e", "1e+", "--1", "-.5"] {
+        assert_eq!(
+            Json::parse(text),
+            Err(JsonError("Invalid number")),
+            "{text}"
+        );
+    }
+
+    // Leading zeros end the number, the rest of the text is left over
+    for text in ["01", "-01", "00", "1-2", "0x10"] {
+        assert_eq!(
+            Json::parse(text),
+            Err(JsonError("Trailing characters")),
+            "{text}"
+        );
+    }
+
+    for text in ["+1", ".5"] {
+        assert_eq!(
+            Json::parse(text),
+            Err(JsonError("Unexpected character")),
+            "{text}"
+        );
+    }
+}
+
+#[test]
+fn numbers_with_a_fraction_or_an_exponent_are_floats() {
+    assert_eq!(Json::parse("1.5e3"), Ok(Json::Number(1500.0)));
+    assert_eq!(
+        Json::parse("[1e5, -0.25, 0E-2]"),
+        Ok(Json::Array(vec![
+            Json::Number(1e5),
+            Json::Number(-0.25),
+            Json::Number(0.0)
+        ]))
+    );
+    assert_eq!(Json::parse("-0"), Ok(Json::from(0)));
+    assert_eq!(Json::parse("120"), Ok(Json::from(120)));
+}
+
+#[test]
+fn control_characters_in_strings_are_rejected() {
+    for text in ["\"\t\"", "\"a\nb\"", "\"\u{0}\""] {
+        assert_eq!(
+            Json::parse(text),
+            Err(JsonError("Control character in string"))
+        );
+    }
+
+    assert_eq!(Json::parse("\"\\t\""), Ok(Json::from("\t")));
+}
@@ -0,0 +1,78 @@
+//! Tokens produced by the lexer and the positions they are found at
+
+use lisper::{
+    lexer::{lex, LexError, Token},
+    span::Span,
+};
+
+#[test]
+fn tokens_carry_the_position_they_start_at() {
+    let spans: Vec<Span> = lex("(println \"é\"\n  #| skipped |# 'x)")
+        .unwrap()
+        .into_iter()
+        .map(|token| token.span)
+        .collect();
+
+    let positions: Vec<(usize, usize, usize)> = spans
+        .iter()
+        .map(|span| (span.line, span.col, span.offset))
+        .collect();
+
+    assert_eq!(
+        positions,
+        [
+            (1, 1, 0),
+            (1, 2, 1),
+            (1, 10, 9),
+            (2, 17, 30),
+            (2, 18, 31),
+            (2, 19, 32)
+        ]
+    );
+}
+
+#[test]
+fn whitespace_and_comments_produce_no_tokens() {
+    let tokens = lex("  \n\t#| a #| nested |# comment |#  ").unwrap();
+
+    assert!(tokens.is_empty());
+    assert!(
+        matches!(lex("#;x").unwrap().as_slice(), [comment, _] if matches!(comment.value, Token::DatumComment))
+    );
+}
+
+#[test]
+fn malformed_literals_are_errors_at_their_position() {
+    assert_eq!(
+        lex("(println \"open").unwrap_err(),
+        LexError::UnterminatedString(Span {
+            line: 1,
+            col: 10,
+            offset: 9
+        })
+    );
+    assert!(matches!(
+        lex("#| never closed").unwrap_err(),
+        LexError::UnterminatedComment(_)
+    ));
+    assert!(matches!(
+        lex("\"\\q\"").unwrap_err(),
+        LexError::InvalidEscape(escape, Span { col: 2, .. }) if escape == "\\q"
+    ));
+    assert!(matches!(
+        lex("b\"\\xZZ\"").unwrap_err(),
+        LexError::InvalidEscape(_, _)
+    ));
+    assert!(matches!(
+        lex("(+ 1 2x)").unwrap_err(),
+        LexError::InvalidNumber(word, Span { col: 6, .. }) if word == "2x"
+    ));
+    assert!(matches!(
+        lex("#\\bogus").unwrap_err(),
+        LexError::InvalidCharacter(name, _) if name == "bogus"
+    ));
+    assert!(matches!(
+        lex("(def #:g1 5)").unwrap_err(),
+        LexError::ReservedSymbol(name, Span { col: 6, .. }) if name == "#:g1"
+    ));
+}
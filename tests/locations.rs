@@ -0,0 +1,61 @@
+//! Positions in the source code that evaluation errors are reported at
+
+use lisper::{
+    eval::{evaluate_source, LocatedError},
+    lexer::lex,
+    parser::parse_program_with_spans,
+    scope::{Output, Scope},
+};
+
+fn run(code: &str) -> Result<String, LocatedError> {
+    let tokens = lex(code).unwrap_or_else(|err| panic!("{code} does not lex: {err}"));
+    let (program, source_map) = parse_program_with_spans(&mut tokens.into_iter().peekable())
+        .unwrap_or_else(|err| panic!("{code} does not parse: {err}"));
+
+    let mut env = Scope::new().wrap();
+    env.borrow().globals().borrow_mut().output = Output::Captured(String::new());
+
+    evaluate_source(&program, source_map, &mut env).map(|value| value.to_string())
+}
+
+/// Line and column of the error raised by `code`
+fn error_position(code: &str) -> Option<(usize, usize)> {
+    run(code)
+        .err()
+        .and_then(|err| err.span)
+        .map(|span| (span.line, span.col))
+}
+
+#[test]
+fn errors_are_located_at_the_innermost_expression() {
+    assert_eq!(
+        error_position("(def x 1)\n(print (+ x 1) (+ x y))"),
+        Some((2, 21))
+    );
+    assert_eq!(error_position("(list '(1 2) (car '()))"), Some((1, 14)));
+    assert_eq!(
+        run("(def x 1)\n(print (+ x y))")
+            .err()
+            .map(|err| err.to_string()),
+        Some("Undefined variable: y at 2:13".to_string())
+    );
+}
+
+#[test]
+fn errors_in_functions_are_located_at_the_call() {
+    let code = "(defun f (lambda (x) (+ x undefined)))\n(print #;(f 1) (f 2))";
+
+    assert_eq!(error_position(code), Some((2, 16)));
+}
+
+#[test]
+fn handled_errors_are_not_located() {
+    let code = "(handler-case (car '()) (error (e) nil))\n(print (/ 1 0))";
+
+    assert_eq!(error_position(code), Some((2, 8)));
+}
+
+#[test]
+fn errors_in_reader_macros_are_located_in_the_expression() {
+    assert_eq!(error_position("(print `(1 ,(car '())))"), Some((1, 13)));
+}
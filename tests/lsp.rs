@@ -0,0 +1,176 @@
+//! Language server driven over stdio like an editor does
+
+use std::{
+    io::BufReader,
+    process::{Command, Stdio},
+};
+
+use lisper::{
+    json::Json,
+    lsp::{read_message, write_message},
+};
+
+const URI: &str = "file:///test.lpr";
+
+fn request(id: i64, method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id.into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn at(line: i64, character: i64) -> Json {
+    Json::object([
+        ("textDocument", Json::object([("uri", URI.into())])),
+        (
+            "position",
+            Json::object([("line", line.into()), ("character", character.into())]),
+        ),
+    ])
+}
+
+/// Opens a document in the language server, sends the requests and returns
+/// every message the server has sent back
+fn session(text: &str, requests: Vec<Json>) -> Vec<Json> {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_lisper"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut input = server.stdin.take().unwrap();
+
+    let open = notification(
+        "textDocument/didOpen",
+        Json::object([(
+            "textDocument",
+            Json::object([("uri", URI.into()), ("text", text.into())]),
+        )]),
+    );
+
+    for message in std::iter::once(open)
+        .chain(requests)
+        .chain([notification("exit", Json::Null)])
+    {
+        write_message(&mut input, message).unwrap();
+    }
+
+    let mut output = BufReader::new(server.stdout.take().unwrap());
+    let mut messages = Vec::new();
+
+    while let Some(message) = read_message(&mut output).unwrap() {
+        messages.push(message);
+    }
+
+    assert!(server.wait().unwrap().success());
+
+    messages
+}
+
+/// Finds the result of the request with the id
+fn result(messages: &[Json], id: i64) -> &Json {
+    messages
+        .iter()
+        .find(|message| message.get("id").and_then(Json::as_i64) == Some(id))
+        .and_then(|message| message.get("result"))
+        .unwrap_or_else(|| panic!("No result for request {id}"))
+}
+
+fn diagnostics(messages: &[Json]) -> Vec<String> {
+    let published = messages
+        .iter()
+        .find(|message| {
+            message.get("method").and_then(Json::as_str) == Some("textDocument/publishDiagnostics")
+        })
+        .expect("No diagnostics published");
+
+    published
+        .pointer(&["params", "diagnostics"])
+        .and_then(Json::as_array)
+        .unwrap()
+        .iter()
+        .map(|diagnostic| {
+            diagnostic
+                .get("message")
+                .and_then(Json::as_str)
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn commented_out_code_is_ignored() {
+    let text = "#; (def hidden 1)\n#| (def secret 2) |#\n(defun shown (x) x)\n(shown hidden)\n#; (def if 3)";
+
+    let messages = session(
+        text,
+        vec![
+            request(1, "textDocument/completion", at(0, 0)),
+            request(2, "textDocument/hover", at(0, 9)),
+            request(3, "textDocument/definition", at(3, 2)),
+            request(4, "textDocument/definition", at(3, 9)),
+        ],
+    );
+
+    assert!(diagnostics(&messages).is_empty());
+
+    let labels: Vec<_> = result(&messages, 1)
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|item| item.get("label").and_then(Json::as_str))
+        .collect();
+
+    assert!(labels.contains(&"shown"));
+    assert!(!labels.contains(&"hidden"));
+    assert!(!labels.contains(&"secret"));
+
+    assert_eq!(result(&messages, 2), &Json::Null);
+    assert_eq!(
+        result(&messages, 3).pointer(&["range", "start", "line"]),
+        Some(&Json::from(2))
+    );
+    assert_eq!(result(&messages, 4), &Json::Null);
+}
+
+#[test]
+fn syntax_errors_are_reported() {
+    let messages = session("(def x (+ 1 2)\n(println \"open", Vec::new());
+
+    let diagnostics = diagnostics(&messages);
+
+    assert!(diagnostics
+        .iter()
+        .any(|message| message.starts_with("Closing quote")));
+}
+
+#[test]
+fn hover_shows_definitions() {
+    let messages = session(
+        "(defun double (x) (* x 2))\n(double \"é\" 4)",
+        vec![request(1, "textDocument/hover", at(1, 3))],
+    );
+
+    let hover = result(&messages, 1);
+
+    assert_eq!(
+        hover.pointer(&["contents", "value"]).and_then(Json::as_str),
+        Some("```lisper\n(defun double (x) (* x 2))\n```")
+    );
+    assert_eq!(
+        hover.pointer(&["range", "end", "character"]),
+        Some(&Json::from(7))
+    );
+}
@@ -0,0 +1,106 @@
+//! Importing modules from files
+
+use std::path::{Path, PathBuf};
+
+use lisper::{
+    eval::{evaluate_program, EvalError},
+    lexer::lex,
+    parser::parse_program,
+    scope::{Output, PassableScope, Scope},
+};
+
+/// Creates an empty directory for the modules of a test
+fn module_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lisper-{}-{test}", std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+/// Creates a scope evaluating code as if it was in a file of `dir`
+fn scope_in(dir: &Path) -> PassableScope {
+    let env = Scope::new().wrap();
+    let globals = env.borrow().globals();
+
+    {
+        let mut globals = globals.borrow_mut();
+        globals.current_file = Some(dir.join("main.lpr"));
+        globals.output = Output::Captured(String::new());
+    }
+
+    env
+}
+
+fn run(code: &str, env: &mut PassableScope) -> Result<String, EvalError> {
+    let tokens = lex(code).unwrap_or_else(|err| panic!("{code} does not lex: {err}"));
+    let program = parse_program(&mut tokens.into_iter().peekable())
+        .unwrap_or_else(|err| panic!("{code} does not parse: {err}"));
+
+    evaluate_program(&program, env).map(|value| value.to_string())
+}
+
+fn output(env: &PassableScope) -> String {
+    env.borrow().globals().borrow_mut().take_output()
+}
+
+#[test]
+fn modules_are_evaluated_once() {
+    let dir = module_dir("once");
+    std::fs::write(
+        dir.join("greet.lpr"),
+        "(println \"loaded\") (def greeting \"hi\")",
+    )
+    .unwrap();
+
+    let mut env = scope_in(&dir);
+
+    assert_eq!(
+        run(
+            "(import greet) (import greet) (import \"greet.lpr\") greeting",
+            &mut env
+        )
+        .ok(),
+        Some("\"hi\"".to_string())
+    );
+    assert_eq!(output(&env), "loaded\n");
+}
+
+#[test]
+fn failed_imports_can_be_retried() {
+    let dir = module_dir("retry");
+    let mut env = scope_in(&dir);
+
+    for broken in ["(def x", "(def x (car 1))"] {
+        std::fs::write(dir.join("broken.lpr"), broken).unwrap();
+
+        assert!(run("(import broken)", &mut env).is_err());
+        assert!(run("(import broken :as b)", &mut env).is_err());
+    }
+
+    std::fs::write(dir.join("broken.lpr"), "(def x 42)").unwrap();
+
+    assert_eq!(
+        run("(import broken) x", &mut env).ok(),
+        Some("42".to_string())
+    );
+    assert_eq!(
+        run("(import broken :as b) b/x", &mut env).ok(),
+        Some("42".to_string())
+    );
+}
+
+#[test]
+fn circular_imports_stop() {
+    let dir = module_dir("circular");
+    std::fs::write(dir.join("a.lpr"), "(import b) (def a 1)").unwrap();
+    std::fs::write(dir.join("b.lpr"), "(import a) (def b 2)").unwrap();
+
+    let mut env = scope_in(&dir);
+
+    assert_eq!(
+        run("(import a) (+ a b)", &mut env).ok(),
+        Some("3".to_string())
+    );
+}
@@ -0,0 +1,160 @@
+//! Expressions produced by the parser and the syntax errors it reports
+
+use lisper::{
+    expr::Expr,
+    lexer::lex,
+    parser::{parse, parse_program, parse_program_recovering, ParseError},
+    span::{Span, Spanned},
+};
+
+fn parse_code(code: &str) -> Result<Expr, ParseError> {
+    let tokens = lex(code).unwrap_or_else(|err| panic!("{code} does not lex: {err}"));
+
+    parse(&mut tokens.into_iter().peekable())
+}
+
+#[test]
+fn programs_hold_every_top_level_expression() {
+    let tokens = lex("(def x 1) #;(skipped) (print x)").unwrap();
+    let program = parse_program(&mut tokens.into_iter().peekable()).ok();
+
+    assert_eq!(
+        program,
+        Some(vec![
+            Expr::List(vec![
+                Expr::Keyword("def".to_string()),
+                Expr::Symbol("x".to_string()),
+                Expr::Integer(1)
+            ]),
+            Expr::List(vec![
+                Expr::Keyword("print".to_string()),
+                Expr::Symbol("x".to_string())
+            ])
+        ])
+    );
+}
+
+#[test]
+fn atoms_are_expressions_at_the_top_level() {
+    assert!(matches!(parse_code("x"), Ok(Expr::Symbol(symbol)) if symbol == "x"));
+    assert!(matches!(parse_code("'x"), Ok(Expr::List(list)) if list.len() == 2));
+
+    let tokens = lex("5 true (f)").unwrap();
+
+    assert_eq!(
+        parse_program(&mut tokens.into_iter().peekable()).ok(),
+        Some(vec![
+            Expr::Integer(5),
+            Expr::Boolean(true),
+            Expr::List(vec![Expr::Symbol("f".to_string())])
+        ])
+    );
+}
+
+#[test]
+fn brackets_delimit_lists_like_parentheses() {
+    assert_eq!(
+        parse_code("[let [(x 1)] x]").ok(),
+        parse_code("(let ((x 1)) x)").ok()
+    );
+}
+
+#[test]
+fn lists_are_closed_by_the_delimiter_they_are_opened_with() {
+    assert!(matches!(
+        parse_code("(let [(x 1)) x)"),
+        Err(ParseError::MismatchedDelimiter('[', ')', span)) if span.col == 12
+    ));
+    assert!(matches!(
+        parse_code("[+ 1 2)"),
+        Err(ParseError::MismatchedDelimiter('[', ')', _))
+    ));
+}
+
+#[test]
+fn unclosed_lists_are_reported_at_their_opening_delimiter() {
+    assert!(matches!(
+        parse_code("(+ 1 2"),
+        Err(ParseError::UnexpectedEof('(', span)) if span.col == 1
+    ));
+    assert!(matches!(
+        parse_code("(let [(x 1)]\n  (+ x 1"),
+        Err(ParseError::UnexpectedEof('(', span)) if span.line == 2 && span.col == 3
+    ));
+}
+
+#[test]
+fn closing_delimiters_without_opening_ones_are_reported() {
+    let tokens = lex("(+ 1 2)\n]").unwrap();
+
+    assert!(matches!(
+        parse_program(&mut tokens.into_iter().peekable()),
+        Err(ParseError::UnbalancedParen(']', span)) if span.line == 2 && span.col == 1
+    ));
+}
+
+#[test]
+fn closing_delimiters_after_the_expression_are_rejected() {
+    let tokens = lex("(+ 1 2))").unwrap();
+
+    assert!(matches!(
+        parse_program(&mut tokens.into_iter().peekable()),
+        Err(ParseError::UnbalancedParen(')', span)) if span.col == 8
+    ));
+}
+
+#[test]
+fn recovering_parser_reports_every_error() {
+    let code = "(def x 1)\n(let [(y 2)) y)\n(print x)\nstray (f ')\n#;(skipped) (+ 1 2";
+    let tokens = lex(code).unwrap();
+
+    let (program, errors) = parse_program_recovering(&mut tokens.into_iter().peekable());
+
+    assert_eq!(program.len(), 3);
+    assert!(matches!(
+        errors.as_slice(),
+        [
+            Spanned {
+                value: ParseError::MismatchedDelimiter('[', ')', _),
+                span: Span {
+                    line: 2,
+                    col: 12,
+                    ..
+                }
+            },
+            Spanned {
+                value: ParseError::ExpressionExpected,
+                span: Span {
+                    line: 4,
+                    col: 7,
+                    ..
+                }
+            },
+            Spanned {
+                value: ParseError::UnexpectedEof('(', _),
+                span: Span {
+                    line: 5,
+                    col: 13,
+                    ..
+                }
+            },
+        ]
+    ));
+}
+
+#[test]
+fn dotted_pairs_are_rejected() {
+    assert!(matches!(
+        parse_code("(quote (a . b))"),
+        Err(ParseError::DottedPair(span)) if span.col == 11
+    ));
+    assert!(parse_code("(list a.b .5)").is_ok());
+}
+
+#[test]
+fn reader_macros_expand_the_expression_following_them() {
+    assert_eq!(
+        parse_code("('a `(b ,c ,@d))").ok(),
+        parse_code("((quote a) (quasiquote (b (unquote c) (unquote-splicing d))))").ok()
+    );
+}
@@ -0,0 +1,278 @@
+//! Inputs that used to crash the interpreter instead of returning an error
+
+use lisper::{
+    eval::{evaluate, EvalError},
+    lexer::lex,
+    parser::{parse, ParseError, MAX_NESTING_DEPTH},
+    scope::{Output, Scope},
+};
+
+/// Evaluates `code` in a sandbox with limited fuel
+fn run(code: &str, fuel: u64) -> Result<String, EvalError> {
+    let tokens = lex(code).unwrap_or_else(|err| panic!("{code} does not lex: {err}"));
+    let expr = parse(&mut tokens.into_iter().peekable())
+        .unwrap_or_else(|err| panic!("{code} does not parse: {err}"));
+
+    let mut env = Scope::new().wrap();
+    let globals = env.borrow().globals();
+
+    {
+        let mut globals = globals.borrow_mut();
+        globals.fuel = Some(fuel);
+        globals.sandboxed = true;
+        globals.output = Output::Captured(String::new());
+    }
+
+    evaluate(&expr, &mut env).map(|value| value.to_string())
+}
+
+#[test]
+fn deeply_nested_lists_are_rejected_by_the_parser() {
+    let code = "(".repeat(MAX_NESTING_DEPTH + 1) + &")".repeat(MAX_NESTING_DEPTH + 1);
+
+    assert!(matches!(
+        parse(&mut lex(&code).unwrap().into_iter().peekable()),
+        Err(ParseError::NestingTooDeep(_))
+    ));
+}
+
+#[test]
+fn chained_datum_comments_are_rejected_by_the_parser() {
+    let code = "(".to_string() + &"#;".repeat(100_000) + &"x ".repeat(100_000) + ")";
+
+    assert!(matches!(
+        parse(&mut lex(&code).unwrap().into_iter().peekable()),
+        Err(ParseError::NestingTooDeep(_))
+    ));
+}
+
+#[test]
+fn division_by_zero_is_an_error() {
+    assert!(matches!(
+        run("(/ 1 0)", 100),
+        Err(EvalError::DivisionByZero("/"))
+    ));
+}
+
+#[test]
+fn indexing_outside_of_a_list_is_an_error() {
+    assert!(matches!(
+        run("(nth (list 1 2) -1)", 100),
+        Err(EvalError::IndexOutOfRange("nth", -1, 2))
+    ));
+    assert!(matches!(
+        run("(nth (list 1 2) 9223372036854775807)", 100),
+        Err(EvalError::IndexOutOfRange("nth", i64::MAX, 2))
+    ));
+}
+
+#[test]
+fn arithmetic_overflow_promotes_to_big_integers() {
+    assert_eq!(
+        run("(+ 9223372036854775807 1)", 100).unwrap(),
+        "9223372036854775808"
+    );
+    assert_eq!(
+        run("(/ -9223372036854775808 -1)", 100).unwrap(),
+        "9223372036854775808"
+    );
+    assert_eq!(
+        run("(* 99999999999 99999999999)", 100).unwrap(),
+        "9999999999800000000001"
+    );
+}
+
+#[test]
+fn rationals_stay_exact_beyond_i64() {
+    assert_eq!(
+        run("(= (/ (expt 2 70) 3) (/ (+ 1 (expt 2 70)) 3))", 100).unwrap(),
+        "false"
+    );
+    assert_eq!(
+        run("(- -9223372036854775808/3)", 100).unwrap(),
+        "9223372036854775808/3"
+    );
+    assert_eq!(
+        run("(* 3 (/ (expt 2 70) 3))", 100).unwrap(),
+        "1180591620717411303424"
+    );
+    assert_eq!(
+        run("(floor (/ (- (expt 2 70)) 3))", 100).unwrap(),
+        "-393530540239137101142"
+    );
+}
+
+#[test]
+fn calling_functions_with_missing_arguments_is_an_error() {
+    assert!(matches!(
+        run("((defun f (lambda (x y) (+ x y))) (f 1))", 100),
+        Err(EvalError::ArgumentCount(_, 2))
+    ));
+}
+
+#[test]
+fn if_needs_two_or_three_arguments() {
+    for code in ["(if true)", "(if true 1 2 3)"] {
+        let err = run(code, 100).unwrap_err();
+
+        assert!(matches!(err, EvalError::ArgumentCountRange(ref name, 2, 3) if name == "if"));
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument count for if, 2 or 3 needed"
+        );
+    }
+}
+
+#[test]
+fn calling_functions_with_extra_arguments_is_an_error() {
+    assert!(matches!(
+        run("((defun double (lambda (x) (* x 2))) (double 1 2 3))", 100),
+        Err(EvalError::ArgumentCount(name, 1)) if name == "double"
+    ));
+    assert!(matches!(
+        run("((defun double (lambda (x) (* x 2))) (double))", 100),
+        Err(EvalError::ArgumentCount(name, 1)) if name == "double"
+    ));
+}
+
+#[test]
+fn evaluation_stops_when_fuel_runs_out() {
+    assert!(matches!(
+        run("((defun f (lambda (x) (f x))) (f 1))", 100),
+        Err(EvalError::FuelExhausted)
+    ));
+    assert!(matches!(
+        run("(for/list (i 9223372036854775807) i)", 1000),
+        Err(EvalError::FuelExhausted)
+    ));
+    assert!(matches!(
+        run("(repeat 9223372036854775807 0)", 1000),
+        Err(EvalError::FuelExhausted)
+    ));
+    assert!(matches!(
+        run("(range -9223372036854775808 9223372036854775807)", 1000),
+        Err(EvalError::FuelExhausted)
+    ));
+}
+
+#[test]
+fn repeating_huge_counts_is_an_error() {
+    assert!(matches!(
+        run("(repeat 9223372036854775807 0)", u64::MAX),
+        Err(EvalError::IllegalArgument("repeat", _))
+    ));
+}
+
+#[test]
+fn huge_ranges_are_an_error() {
+    assert!(matches!(
+        run("(range 0 9223372036854775807 1000)", u64::MAX),
+        Err(EvalError::IllegalArgument("range", _))
+    ));
+}
+
+#[test]
+fn padding_to_huge_widths_is_an_error() {
+    assert!(matches!(
+        run("(pad-left \"a\" 99999999999999 \"x\")", 1000),
+        Err(EvalError::FuelExhausted)
+    ));
+    assert!(matches!(
+        run("(pad-right \"a\" 99999999999999)", u64::MAX),
+        Err(EvalError::IllegalArgument("pad-right", _))
+    ));
+}
+
+#[test]
+fn running_out_of_fuel_cannot_be_handled() {
+    assert!(matches!(
+        run("(handler-case (for/list (i 100000) i) (error () 0))", 1000),
+        Err(EvalError::FuelExhausted)
+    ));
+    assert!(matches!(
+        run("(try (for/list (i 100000) i) (catch e 0))", 1000),
+        Err(EvalError::FuelExhausted)
+    ));
+}
+
+#[test]
+fn condition_types_cannot_be_derived_from_themselves() {
+    assert!(matches!(
+        run("(define-condition a a ())", 100),
+        Err(EvalError::IllegalArgument("define-condition", _))
+    ));
+    assert!(matches!(
+        run(
+            "((define-condition a (x)) (define-condition b a ()) (define-condition a b ()))",
+            100
+        ),
+        Err(EvalError::IllegalArgument("define-condition", _))
+    ));
+    assert_eq!(
+        run(
+            "((define-condition a (x)) (define-condition b a ()) \
+              (try (define-condition a b ()) (catch e nil)) \
+              (handler-case (signal b 1) (a () 0)))",
+            1000
+        )
+        .unwrap(),
+        "(nil nil nil 0)"
+    );
+}
+
+#[test]
+fn nested_regex_quantifiers_do_not_backtrack() {
+    let code = format!("(re-match \"(a*)*b\" \"{}\")", "a".repeat(10000));
+
+    assert_eq!(run(&code, 100).unwrap(), "false");
+}
+
+#[test]
+fn deeply_nested_json_is_an_error() {
+    let code = format!("(json-parse \"{}\")", "[".repeat(100_000));
+
+    assert!(matches!(
+        run(&code, 100),
+        Err(EvalError::IllegalArgument("json-parse", _))
+    ));
+}
+
+#[test]
+fn files_cannot_be_accessed_in_the_sandbox() {
+    assert!(matches!(
+        run("(read-bytes \"/etc/passwd\")", 100),
+        Err(EvalError::IoFailed(_, _))
+    ));
+    assert!(matches!(
+        run("(append-file \"/tmp/lisper-sandbox\" \"escaped\")", 100),
+        Err(EvalError::IoFailed(_, _))
+    ));
+}
+
+#[test]
+fn the_environment_cannot_be_accessed_in_the_sandbox() {
+    assert!(matches!(
+        run("(getenv \"HOME\")", 100),
+        Err(EvalError::IllegalArgument("getenv", _))
+    ));
+    assert!(matches!(
+        run("(setenv \"PATH\" \"\")", 100),
+        Err(EvalError::IllegalArgument("setenv", _))
+    ));
+}
+
+#[test]
+fn processes_cannot_be_started_in_the_sandbox() {
+    assert!(matches!(
+        run("(exec \"sh\" \"-c\" \"exit 0\")", 100),
+        Err(EvalError::IoFailed(_, _))
+    ));
+}
+
+#[test]
+fn imports_are_disabled_in_the_sandbox() {
+    assert!(matches!(
+        run("(import io)", 100),
+        Err(EvalError::ImportFailed(_, _))
+    ));
+}
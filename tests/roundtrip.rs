@@ -0,0 +1,162 @@
+//! Property-based tests checking that printed expressions parse back into themselves
+
+use lisper::{
+    arithmetic::{integer, rational},
+    bigint::BigInt,
+    eval::evaluate,
+    expr::Expr,
+    lexer::{lex, Token, KEYWORDS, OPERATORS},
+    parser::parse,
+    scope::Scope,
+};
+
+/// Number of random expression trees checked
+const CASES: u64 = 2000;
+
+/// Deterministic xorshift generator, so failing cases can be reproduced from their seed
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+fn symbol(rng: &mut Rng) -> String {
+    const ALPHABET: &[u8] = b"abcxyz0123456789-+*/?!<>=_:#.";
+
+    loop {
+        let length = 1 + rng.below(8);
+        let name: String = (0..length)
+            .map(|_| ALPHABET[rng.below(ALPHABET.len() as u64) as usize] as char)
+            .collect();
+
+        // Names of built-ins and number literals are not lexed as symbols
+        if matches!(lex(&name).as_deref(), Ok([token]) if matches!(token.value, Token::Symbol(_))) {
+            return name;
+        }
+    }
+}
+
+fn string(rng: &mut Rng) -> String {
+    const ALPHABET: &[char] = &[
+        'a', 'z', '0', ' ', '(', ')', '"', '\\', '\n', '\t', '\r', '\0', ';', 'é',
+    ];
+
+    (0..rng.below(8))
+        .map(|_| ALPHABET[rng.below(ALPHABET.len() as u64) as usize])
+        .collect()
+}
+
+fn float(rng: &mut Rng) -> f64 {
+    loop {
+        let float = match rng.below(2) {
+            0 => f64::from_bits(rng.next()),
+            _ => (rng.next() as i64 >> rng.below(64)) as f64 / 1024.0,
+        };
+
+        if float.is_finite() {
+            return float;
+        }
+    }
+}
+
+fn big_integer(rng: &mut Rng) -> String {
+    let sign = if rng.below(2) == 0 { "-" } else { "" };
+    let digits: String = (0..1 + rng.below(60))
+        .map(|_| char::from(b'0' + rng.below(10) as u8))
+        .collect();
+
+    format!("{sign}{digits}")
+}
+
+fn atom(rng: &mut Rng) -> Expr {
+    match rng.below(12) {
+        0 => Expr::Integer(rng.next() as i64 >> rng.below(64)),
+        1 => Expr::Float(float(rng)),
+        2 => Expr::String(string(rng)),
+        3 => Expr::Boolean(rng.below(2) == 0),
+        4 => Expr::Nil,
+        5 => Expr::If,
+        6 => Expr::Op(rng.pick(OPERATORS).to_string()),
+        7 => Expr::Keyword(rng.pick(KEYWORDS).to_string()),
+        8 => rational(
+            (rng.next() as i64 >> rng.below(64)) as i128,
+            1 + (rng.next() >> (1 + rng.below(63))) as i128,
+        ),
+        9 => integer(BigInt::parse(&big_integer(rng)).unwrap()),
+        10 => Expr::Bytes((0..rng.below(8)).map(|_| rng.next() as u8).collect()),
+        _ => Expr::Symbol(symbol(rng)),
+    }
+}
+
+fn list(rng: &mut Rng, depth: usize) -> Expr {
+    let length = rng.below(5);
+
+    Expr::List((0..length).map(|_| expr(rng, depth)).collect())
+}
+
+fn expr(rng: &mut Rng, depth: usize) -> Expr {
+    if depth == 0 || rng.below(3) == 0 {
+        atom(rng)
+    } else {
+        list(rng, depth - 1)
+    }
+}
+
+fn reparse(printed: &str) -> Option<Expr> {
+    parse(&mut lex(printed).ok()?.into_iter().peekable()).ok()
+}
+
+#[test]
+fn printed_expressions_parse_back() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let original = list(&mut rng, 5);
+        let printed = original.to_string();
+
+        assert_eq!(
+            reparse(&printed),
+            Some(original),
+            "seed {seed} printed as {printed}"
+        );
+    }
+}
+
+#[test]
+fn functions_print_as_lambda_expressions() {
+    let mut env = Scope::new().wrap();
+    let program = reparse("(defun double (lambda (x y) (* x y 2)))").unwrap();
+
+    evaluate(&program, &mut env).unwrap();
+
+    let function = env.borrow().get("double").unwrap();
+
+    assert_eq!(function.to_string(), "(lambda (x y) (* x y 2))");
+}
+
+#[test]
+fn multiple_values_print_as_values_expression() {
+    let mut env = Scope::new().wrap();
+    let program = reparse("(values 1 (+ 1 1) true)").unwrap();
+
+    let printed = evaluate(&program, &mut env).unwrap().to_string();
+
+    assert_eq!(printed, "(values 1 2 true)");
+    assert!(reparse(&printed).is_some());
+}